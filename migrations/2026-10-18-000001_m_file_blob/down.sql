@@ -0,0 +1,7 @@
+DROP INDEX idx_m_file_file_hash ON m_file;
+
+ALTER TABLE m_file
+    DROP COLUMN file_hash,
+    DROP COLUMN file_size;
+
+DROP TABLE m_file_blob;
@@ -0,0 +1,15 @@
+CREATE TABLE m_file_blob (
+    file_hash VARCHAR(64) NOT NULL PRIMARY KEY,
+    file_path VARCHAR(255) NOT NULL,
+    file_size BIGINT NOT NULL,
+    ref_count BIGINT NOT NULL DEFAULT 0,
+    file LONGBLOB NULL,
+    created_on DATETIME NOT NULL,
+    modified_on DATETIME NULL
+);
+
+ALTER TABLE m_file
+    ADD COLUMN file_hash VARCHAR(64) NULL,
+    ADD COLUMN file_size BIGINT NULL;
+
+CREATE INDEX idx_m_file_file_hash ON m_file (file_hash);
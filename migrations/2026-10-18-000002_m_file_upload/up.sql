@@ -0,0 +1,13 @@
+CREATE TABLE m_file_upload (
+    id VARCHAR(36) NOT NULL PRIMARY KEY,
+    file_name VARCHAR(100) NULL,
+    content_type VARCHAR(100) NULL,
+    upload_length BIGINT NOT NULL,
+    upload_offset BIGINT NOT NULL DEFAULT 0,
+    file_id BIGINT NULL,
+    created_on DATETIME NOT NULL,
+    modified_on DATETIME NULL,
+    expires_on DATETIME NOT NULL
+);
+
+CREATE INDEX idx_m_file_upload_expires_on ON m_file_upload (expires_on);
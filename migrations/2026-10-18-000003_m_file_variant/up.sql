@@ -0,0 +1,12 @@
+CREATE TABLE m_file_variant (
+    file_id BIGINT NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    file_hash VARCHAR(64) NOT NULL,
+    file_path VARCHAR(255) NOT NULL,
+    file_size BIGINT NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    width INT NOT NULL,
+    height INT NOT NULL,
+    created_on DATETIME NOT NULL,
+    PRIMARY KEY (file_id, name)
+);
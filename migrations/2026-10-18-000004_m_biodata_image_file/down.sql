@@ -0,0 +1,3 @@
+ALTER TABLE m_biodata DROP FOREIGN KEY fk_m_biodata_image_file;
+
+ALTER TABLE m_biodata DROP COLUMN image_file_id;
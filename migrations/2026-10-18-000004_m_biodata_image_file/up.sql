@@ -0,0 +1,4 @@
+ALTER TABLE m_biodata ADD COLUMN image_file_id BIGINT NULL;
+
+ALTER TABLE m_biodata
+    ADD CONSTRAINT fk_m_biodata_image_file FOREIGN KEY (image_file_id) REFERENCES m_file (id);
@@ -0,0 +1,11 @@
+CREATE TABLE m_file_version (
+    file_id BIGINT NOT NULL,
+    version INT NOT NULL,
+    file_name VARCHAR(100) NULL,
+    file_hash VARCHAR(64) NOT NULL,
+    file_path VARCHAR(255) NOT NULL,
+    file_size BIGINT NOT NULL,
+    created_by BIGINT NOT NULL,
+    created_on DATETIME NOT NULL,
+    PRIMARY KEY (file_id, version)
+);
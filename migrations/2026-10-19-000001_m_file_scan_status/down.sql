@@ -0,0 +1 @@
+ALTER TABLE m_file DROP COLUMN scan_status;
@@ -0,0 +1,3 @@
+-- files stored before scanning existed stay available
+ALTER TABLE m_file ADD COLUMN scan_status VARCHAR(20) NOT NULL DEFAULT 'clean';
+ALTER TABLE m_file ALTER COLUMN scan_status SET DEFAULT 'pending';
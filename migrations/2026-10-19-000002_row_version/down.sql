@@ -0,0 +1,2 @@
+ALTER TABLE m_biodata DROP COLUMN row_version;
+ALTER TABLE m_file DROP COLUMN row_version;
@@ -0,0 +1,2 @@
+ALTER TABLE m_biodata ADD COLUMN row_version BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE m_file ADD COLUMN row_version BIGINT NOT NULL DEFAULT 0;
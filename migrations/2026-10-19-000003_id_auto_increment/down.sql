@@ -0,0 +1,4 @@
+SET FOREIGN_KEY_CHECKS = 0;
+ALTER TABLE m_biodata MODIFY id BIGINT NOT NULL;
+ALTER TABLE m_file MODIFY id BIGINT NOT NULL;
+SET FOREIGN_KEY_CHECKS = 1;
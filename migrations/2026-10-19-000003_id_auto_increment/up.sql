@@ -0,0 +1,5 @@
+-- needed by ID_STRATEGY=auto_increment, explicit ids (snowflake) are still accepted
+SET FOREIGN_KEY_CHECKS = 0;
+ALTER TABLE m_biodata MODIFY id BIGINT NOT NULL AUTO_INCREMENT;
+ALTER TABLE m_file MODIFY id BIGINT NOT NULL AUTO_INCREMENT;
+SET FOREIGN_KEY_CHECKS = 1;
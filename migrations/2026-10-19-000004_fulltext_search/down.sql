@@ -0,0 +1,2 @@
+ALTER TABLE m_biodata DROP INDEX ft_m_biodata_search;
+ALTER TABLE m_file DROP INDEX ft_m_file_search;
@@ -0,0 +1,2 @@
+ALTER TABLE m_biodata ADD FULLTEXT INDEX ft_m_biodata_search (fullname, mobile_phone);
+ALTER TABLE m_file ADD FULLTEXT INDEX ft_m_file_search (file_name);
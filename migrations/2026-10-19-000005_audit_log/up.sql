@@ -0,0 +1,11 @@
+CREATE TABLE audit_log (
+    id BIGINT NOT NULL AUTO_INCREMENT,
+    entity VARCHAR(50) NOT NULL,
+    entity_id BIGINT NOT NULL,
+    action VARCHAR(20) NOT NULL,
+    actor_id BIGINT NOT NULL,
+    changes LONGTEXT NOT NULL,
+    created_on DATETIME NOT NULL,
+    PRIMARY KEY (id),
+    INDEX idx_audit_log_entity (entity, entity_id, id)
+);
@@ -0,0 +1,6 @@
+ALTER TABLE m_user DROP INDEX uq_m_user_email;
+ALTER TABLE m_role DROP INDEX uq_m_role_code;
+SET FOREIGN_KEY_CHECKS = 0;
+ALTER TABLE m_role MODIFY id BIGINT NOT NULL;
+ALTER TABLE m_user MODIFY id BIGINT NOT NULL;
+SET FOREIGN_KEY_CHECKS = 1;
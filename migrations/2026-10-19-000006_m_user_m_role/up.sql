@@ -0,0 +1,7 @@
+-- same id handling as m_biodata and m_file, see 2026-10-19-000003_id_auto_increment
+SET FOREIGN_KEY_CHECKS = 0;
+ALTER TABLE m_role MODIFY id BIGINT NOT NULL AUTO_INCREMENT;
+ALTER TABLE m_user MODIFY id BIGINT NOT NULL AUTO_INCREMENT;
+SET FOREIGN_KEY_CHECKS = 1;
+ALTER TABLE m_role ADD UNIQUE INDEX uq_m_role_code (code);
+ALTER TABLE m_user ADD UNIQUE INDEX uq_m_user_email (email);
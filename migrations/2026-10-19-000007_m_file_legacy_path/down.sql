@@ -0,0 +1,7 @@
+-- only rows rewritten by the server and still holding the rewritten key
+UPDATE m_file
+JOIN m_file_legacy_path ON m_file_legacy_path.file_id = m_file.id
+SET m_file.file_path = m_file_legacy_path.legacy_path
+WHERE m_file.file_path = m_file_legacy_path.file_path;
+
+DROP TABLE m_file_legacy_path;
@@ -0,0 +1,10 @@
+-- files uploaded before the storage backend kept their ./{STORAGE_LOCAL_ROOT}/...
+-- path, the server rewrites them to storage keys at startup and records every
+-- row it rewrote here so the down migration only reverts those
+CREATE TABLE m_file_legacy_path (
+    file_id BIGINT NOT NULL,
+    legacy_path VARCHAR(255) NOT NULL,
+    file_path VARCHAR(255) NOT NULL,
+    migrated_on DATETIME NOT NULL,
+    PRIMARY KEY (file_id)
+);
@@ -0,0 +1,68 @@
+use std::{sync::Mutex, time::Duration};
+
+use crate::{
+    config::environment::CONFIG,
+    dto::{enumerator::id_strategy::IdStrategy, response::app_error::AppError},
+};
+
+/// 2024-01-01T00:00:00Z, start of the snowflake timestamp
+const SNOWFLAKE_EPOCH: i64 = 1_704_067_200_000;
+const NODE_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+const MAX_NODE_ID: i64 = (1 << NODE_BITS) - 1;
+const MAX_SEQUENCE: i64 = (1 << SEQUENCE_BITS) - 1;
+
+/// (last millisecond, sequence within it)
+static SNOWFLAKE_STATE: Mutex<(i64, i64)> = Mutex::new((0, 0));
+
+/// Check the configured strategy, fails startup on an out of range node id.
+pub fn initialize() {
+    if CONFIG.id_strategy == IdStrategy::Snowflake
+        && !(0..=MAX_NODE_ID).contains(&CONFIG.id_node_id)
+    {
+        panic!("ID_NODE_ID must be between 0-{}", MAX_NODE_ID);
+    }
+    log::info!(
+        "id generator: {:?}, node {}",
+        CONFIG.id_strategy,
+        CONFIG.id_node_id
+    );
+}
+
+/// Id for a new row, `0` when the database assigns it on insert.
+pub fn next_id() -> Result<i64, AppError> {
+    match CONFIG.id_strategy {
+        IdStrategy::AutoIncrement => Ok(0),
+        IdStrategy::Snowflake => next_snowflake(CONFIG.id_node_id),
+    }
+}
+
+fn next_snowflake(node_id: i64) -> Result<i64, AppError> {
+    loop {
+        let mut state = SNOWFLAKE_STATE.lock().unwrap();
+        let (last_millis, sequence) = *state;
+        let now_millis = chrono::Utc::now().timestamp_millis() - SNOWFLAKE_EPOCH;
+
+        // a clock adjustment keeps using the last millisecond while its
+        // sequence lasts
+        let (millis, next_sequence) = if now_millis > last_millis {
+            (now_millis, 0)
+        } else if sequence < MAX_SEQUENCE {
+            (last_millis, sequence + 1)
+        } else if now_millis < last_millis {
+            return Err(AppError::Other(format!(
+                "clock moved backwards by {} ms, refusing to generate id",
+                last_millis - now_millis
+            )));
+        } else {
+            // sequence exhausted, wait for the next millisecond without
+            // holding the lock
+            drop(state);
+            std::thread::sleep(Duration::from_millis(1));
+            continue;
+        };
+        *state = (millis, next_sequence);
+
+        return Ok((millis << (NODE_BITS + SEQUENCE_BITS)) | (node_id << SEQUENCE_BITS) | next_sequence);
+    }
+}
@@ -1,3 +1,7 @@
 pub mod logger;
 pub mod environment;
-pub mod database;
\ No newline at end of file
+pub mod database;
+pub mod storage;
+pub mod scanner;
+pub mod scheduler;
+pub mod id_generator;
\ No newline at end of file
@@ -0,0 +1,108 @@
+use std::{path::Path, time::Duration};
+
+use axum::body::Bytes;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::dto::response::app_error::AppError;
+
+use super::main::{ScanResult, Scanner};
+
+/// clamd refuses chunks above its `StreamMaxLength`, stay well below it.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Scans content with a running `clamd` through its INSTREAM command. The
+/// address is either `host:port` or, on unix, the path of the clamd socket.
+pub struct ClamavScanner {
+    address: String,
+    timeout: Duration,
+}
+
+impl ClamavScanner {
+    pub fn new(address: &str, timeout: Duration) -> ClamavScanner {
+        ClamavScanner {
+            address: address.to_string(),
+            timeout,
+        }
+    }
+
+    async fn request<R>(&self, source: &mut R) -> Result<String, std::io::Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        #[cfg(unix)]
+        if self.address.starts_with('/') {
+            let mut stream = tokio::net::UnixStream::connect(&self.address).await?;
+            return instream(&mut stream, source).await;
+        }
+        let mut stream = TcpStream::connect(&self.address).await?;
+        instream(&mut stream, source).await
+    }
+
+    async fn scan_source<R>(&self, source: &mut R) -> Result<ScanResult, AppError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let reply = tokio::time::timeout(self.timeout, self.request(source))
+            .await
+            .map_err(|_| AppError::Other(format!("clamav scan timed out, address: {}", self.address)))?
+            .map_err(|error| {
+                AppError::Other(format!("clamav scan failed: {}, address: {}", error, self.address))
+            })?;
+        parse_reply(&reply)
+    }
+}
+
+/// Send `source` as length prefixed chunks terminated by a zero length chunk,
+/// then read the NUL terminated reply (e.g. `stream: OK`).
+async fn instream<S, R>(stream: &mut S, source: &mut R) -> Result<String, std::io::Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+{
+    stream.write_all(b"zINSTREAM\0").await?;
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        let read = source.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        stream.write_all(&(read as u32).to_be_bytes()).await?;
+        stream.write_all(&chunk[..read]).await?;
+    }
+    stream.write_all(&0u32.to_be_bytes()).await?;
+    stream.flush().await?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await?;
+    Ok(String::from_utf8_lossy(&reply)
+        .trim_end_matches('\0')
+        .trim()
+        .to_string())
+}
+
+fn parse_reply(reply: &str) -> Result<ScanResult, AppError> {
+    let result = reply.strip_prefix("stream: ").unwrap_or(reply);
+    if result == "OK" {
+        return Ok(ScanResult::Clean);
+    }
+    if let Some(signature) = result.strip_suffix(" FOUND") {
+        return Ok(ScanResult::Infected(signature.to_string()));
+    }
+    Err(AppError::Other(format!("clamav scan failed: {}", reply)))
+}
+
+impl Scanner for ClamavScanner {
+    async fn scan(&self, data: Bytes) -> Result<ScanResult, AppError> {
+        self.scan_source(&mut data.as_ref()).await
+    }
+
+    async fn scan_file(&self, path: &Path) -> Result<ScanResult, AppError> {
+        let mut file = tokio::fs::File::open(path)
+            .await
+            .map_err(|error| AppError::Other(format!("open file failed: {}", error)))?;
+        self.scan_source(&mut file).await
+    }
+}
@@ -0,0 +1,58 @@
+use std::{future::Future, path::Path, time::Duration};
+
+use axum::body::Bytes;
+
+use crate::{
+    config::environment::CONFIG,
+    dto::{enumerator::scanner_type::ScannerType, response::app_error::AppError},
+};
+
+use super::{clamav::ClamavScanner, noop::NoopScanner};
+
+#[derive(Debug, PartialEq)]
+pub enum ScanResult {
+    Clean,
+    /// name of the signature that matched
+    Infected(String),
+}
+
+/// Virus scanning for uploaded content. An `Err` means the content could not
+/// be scanned, not that it is unsafe.
+pub trait Scanner {
+    fn scan(&self, data: Bytes) -> impl Future<Output = Result<ScanResult, AppError>> + Send;
+    /// Scan the content of a local file, streamed rather than read whole.
+    fn scan_file(&self, path: &Path) -> impl Future<Output = Result<ScanResult, AppError>> + Send;
+}
+
+pub enum ScannerEngine {
+    Clamav(ClamavScanner),
+    Noop(NoopScanner),
+}
+
+impl ScannerEngine {
+    pub async fn scan(&self, data: Bytes) -> Result<ScanResult, AppError> {
+        match self {
+            ScannerEngine::Clamav(scanner) => scanner.scan(data).await,
+            ScannerEngine::Noop(scanner) => scanner.scan(data).await,
+        }
+    }
+
+    pub async fn scan_file(&self, path: &Path) -> Result<ScanResult, AppError> {
+        match self {
+            ScannerEngine::Clamav(scanner) => scanner.scan_file(path).await,
+            ScannerEngine::Noop(scanner) => scanner.scan_file(path).await,
+        }
+    }
+}
+
+/// Build the scanner selected by `SCANNER_TYPE`.
+pub fn initialize() -> ScannerEngine {
+    let config_env = &CONFIG;
+    match config_env.scanner_type {
+        ScannerType::Clamav => ScannerEngine::Clamav(ClamavScanner::new(
+            &config_env.scanner_clamav_address,
+            Duration::from_secs(config_env.scanner_clamav_timeout),
+        )),
+        ScannerType::Noop => ScannerEngine::Noop(NoopScanner),
+    }
+}
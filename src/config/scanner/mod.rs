@@ -0,0 +1,3 @@
+pub mod main;
+pub mod clamav;
+pub mod noop;
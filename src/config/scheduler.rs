@@ -0,0 +1,40 @@
+use std::{future::Future, str::FromStr, sync::Arc};
+
+use chrono::Utc;
+use cron::Schedule;
+
+use crate::{dto::response::app_error::AppError, state::AppState};
+
+/// Run `job` in the background on every tick of a cron `expression`
+/// (`sec min hour day month weekday`, in UTC). An empty expression disables
+/// the job; an invalid one is returned as an error.
+pub fn spawn_job<F, Fut>(
+    name: &'static str,
+    expression: &str,
+    state: Arc<AppState>,
+    job: F,
+) -> Result<(), AppError>
+where
+    F: Fn(Arc<AppState>) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    if expression.trim().is_empty() {
+        log::info!("scheduler: job {} disabled", name);
+        return Ok(());
+    }
+    let schedule = Schedule::from_str(expression).map_err(|error| {
+        AppError::Other(format!("invalid cron expression for {}: {}", name, error))
+    })?;
+    log::info!("scheduler: job {} scheduled at '{}'", name, expression);
+
+    tokio::spawn(async move {
+        while let Some(next) = schedule.upcoming(Utc).next() {
+            let delay = (next - Utc::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(delay).await;
+
+            log::info!("scheduler: job {} started", name);
+            job(state.clone()).await;
+        }
+    });
+    Ok(())
+}
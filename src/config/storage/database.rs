@@ -0,0 +1,93 @@
+use std::{path::Path, sync::Arc};
+
+use axum::body::Bytes;
+use diesel::{prelude::*, r2d2, update};
+
+use crate::{diesel_schema::m_file_blob::dsl::*, dto::response::app_error::AppError};
+
+use super::main::StorageBackend;
+
+/// Stores file contents in the `m_file_blob.file` column of the row whose
+/// `file_path` equals the storage key. The row must exist before `put`.
+pub struct DatabaseStorage {
+    diesel_pool: Arc<r2d2::Pool<r2d2::ConnectionManager<MysqlConnection>>>,
+}
+
+impl DatabaseStorage {
+    pub fn new(
+        diesel_pool: Arc<r2d2::Pool<r2d2::ConnectionManager<MysqlConnection>>>,
+    ) -> DatabaseStorage {
+        DatabaseStorage { diesel_pool }
+    }
+
+    fn get_connection(
+        &self,
+    ) -> Result<r2d2::PooledConnection<r2d2::ConnectionManager<MysqlConnection>>, AppError> {
+        self.diesel_pool
+            .get()
+            .map_err(|error| AppError::Other(format!("get connection failed {error}")))
+    }
+}
+
+impl StorageBackend for DatabaseStorage {
+    async fn put(&self, key: &str, data: Bytes) -> Result<(), AppError> {
+        let mut conn = self.get_connection()?;
+        let rows_affected = update(m_file_blob.filter(file_path.eq(key)))
+            .set(file.eq(Some(data.to_vec())))
+            .execute(&mut conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, key: {}", error, key)))?;
+        if rows_affected == 0 {
+            return Err(AppError::Other(format!("m_file_blob row not found, key: {}", key)));
+        }
+        Ok(())
+    }
+
+    /// The content ends up in a single column value, so it is read whole.
+    async fn put_file(&self, key: &str, path: &Path) -> Result<(), AppError> {
+        let data = tokio::fs::read(path)
+            .await
+            .map_err(|error| AppError::Other(format!("read file failed: {}, key: {}", error, key)))?;
+        self.put(key, Bytes::from(data)).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, AppError> {
+        let mut conn = self.get_connection()?;
+        let contents = m_file_blob
+            .filter(file_path.eq(key))
+            .select(file)
+            .first::<Option<Vec<u8>>>(&mut conn)
+            .optional()
+            .map_err(|error| AppError::Other(format!("query failed: {}, key: {}", error, key)))?;
+        Ok(contents.flatten().map(Bytes::from))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        let mut conn = self.get_connection()?;
+        update(m_file_blob.filter(file_path.eq(key)))
+            .set(file.eq(None::<Vec<u8>>))
+            .execute(&mut conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, key: {}", error, key)))?;
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, AppError> {
+        let mut conn = self.get_connection()?;
+        let count: i64 = m_file_blob
+            .filter(file_path.eq(key))
+            .filter(file.is_not_null())
+            .count()
+            .get_result(&mut conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, key: {}", error, key)))?;
+        Ok(count > 0)
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, AppError> {
+        let mut conn = self.get_connection()?;
+        let keys = m_file_blob
+            .filter(file.is_not_null())
+            .select(file_path)
+            .load::<String>(&mut conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+        Ok(keys.into_iter().filter(|key| key.starts_with(prefix)).collect())
+    }
+}
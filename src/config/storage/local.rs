@@ -0,0 +1,136 @@
+use std::path::{Component, Path, PathBuf};
+
+use axum::body::Bytes;
+
+use crate::dto::response::app_error::AppError;
+
+use super::main::StorageBackend;
+
+/// Stores files under a configurable root directory, using the storage key as
+/// the relative path.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: &str) -> LocalStorage {
+        LocalStorage {
+            root: PathBuf::from(root),
+        }
+    }
+
+    fn resolve(&self, key: &str) -> Result<PathBuf, AppError> {
+        let relative = Path::new(key);
+        let is_safe = relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if !is_safe {
+            return Err(AppError::Other(format!("invalid storage key: {}", key)));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+impl StorageBackend for LocalStorage {
+    async fn put(&self, key: &str, data: Bytes) -> Result<(), AppError> {
+        let path = self.resolve(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|error| {
+                AppError::Other(format!("create dir failed: {}, key: {}", error, key))
+            })?;
+        }
+        tokio::fs::write(&path, &data)
+            .await
+            .map_err(|error| AppError::Other(format!("write file failed: {}, key: {}", error, key)))
+    }
+
+    async fn put_file(&self, key: &str, source: &Path) -> Result<(), AppError> {
+        let path = self.resolve(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|error| {
+                AppError::Other(format!("create dir failed: {}, key: {}", error, key))
+            })?;
+        }
+        tokio::fs::copy(source, &path)
+            .await
+            .map(|_| ())
+            .map_err(|error| AppError::Other(format!("write file failed: {}, key: {}", error, key)))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, AppError> {
+        let path = self.resolve(key)?;
+        match tokio::fs::read(&path).await {
+            Ok(contents) => Ok(Some(Bytes::from(contents))),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(AppError::Other(format!(
+                "read file failed: {}, key: {}",
+                error, key
+            ))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        let path = self.resolve(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(_) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(AppError::Other(format!(
+                "delete file failed: {}, key: {}",
+                error, key
+            ))),
+        }
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, AppError> {
+        let path = self.resolve(key)?;
+        tokio::fs::try_exists(&path)
+            .await
+            .map_err(|error| AppError::Other(format!("check file failed: {}, key: {}", error, key)))
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, AppError> {
+        let mut keys = Vec::new();
+        let mut pending = vec![self.root.clone()];
+        while let Some(dir) = pending.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(value) => value,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(error) => {
+                    return Err(AppError::Other(format!(
+                        "read dir failed: {}, dir: {}",
+                        error,
+                        dir.display()
+                    )));
+                }
+            };
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|error| AppError::Other(format!("read dir failed: {}", error)))?
+            {
+                let path = entry.path();
+                let file_type = entry
+                    .file_type()
+                    .await
+                    .map_err(|error| AppError::Other(format!("read dir failed: {}", error)))?;
+                if file_type.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+
+                // keys always use `/`, whatever the platform separator is
+                let key = path
+                    .strip_prefix(&self.root)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if key.starts_with(prefix) {
+                    keys.push(key);
+                }
+            }
+        }
+        Ok(keys)
+    }
+}
@@ -0,0 +1,103 @@
+use std::{future::Future, path::Path, sync::Arc};
+
+use axum::body::Bytes;
+use diesel::{r2d2, MysqlConnection};
+
+use crate::{
+    config::environment::CONFIG,
+    dto::{enumerator::storage_type::StorageType, response::app_error::AppError},
+};
+
+use super::{database::DatabaseStorage, local::LocalStorage, s3::S3Storage};
+
+/// Backend-neutral storage for file contents addressed by a storage key
+/// (e.g. `image/2026/01/10/photo.jpg`).
+pub trait StorageBackend {
+    fn put(&self, key: &str, data: Bytes) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// Store the content of a local file without reading it into memory first.
+    fn put_file(&self, key: &str, path: &Path) -> impl Future<Output = Result<(), AppError>> + Send;
+    fn get(&self, key: &str) -> impl Future<Output = Result<Option<Bytes>, AppError>> + Send;
+    fn delete(&self, key: &str) -> impl Future<Output = Result<(), AppError>> + Send;
+    fn exists(&self, key: &str) -> impl Future<Output = Result<bool, AppError>> + Send;
+    /// Every stored key starting with `prefix`.
+    fn list(&self, prefix: &str) -> impl Future<Output = Result<Vec<String>, AppError>> + Send;
+}
+
+pub enum StorageEngine {
+    Local(LocalStorage),
+    Database(DatabaseStorage),
+    S3(S3Storage),
+}
+
+impl StorageEngine {
+    pub async fn put(&self, key: &str, data: Bytes) -> Result<(), AppError> {
+        match self {
+            StorageEngine::Local(storage) => storage.put(key, data).await,
+            StorageEngine::Database(storage) => storage.put(key, data).await,
+            StorageEngine::S3(storage) => storage.put(key, data).await,
+        }
+    }
+
+    pub async fn put_file(&self, key: &str, path: &Path) -> Result<(), AppError> {
+        match self {
+            StorageEngine::Local(storage) => storage.put_file(key, path).await,
+            StorageEngine::Database(storage) => storage.put_file(key, path).await,
+            StorageEngine::S3(storage) => storage.put_file(key, path).await,
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<Bytes>, AppError> {
+        match self {
+            StorageEngine::Local(storage) => storage.get(key).await,
+            StorageEngine::Database(storage) => storage.get(key).await,
+            StorageEngine::S3(storage) => storage.get(key).await,
+        }
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<(), AppError> {
+        match self {
+            StorageEngine::Local(storage) => storage.delete(key).await,
+            StorageEngine::Database(storage) => storage.delete(key).await,
+            StorageEngine::S3(storage) => storage.delete(key).await,
+        }
+    }
+
+    pub async fn exists(&self, key: &str) -> Result<bool, AppError> {
+        match self {
+            StorageEngine::Local(storage) => storage.exists(key).await,
+            StorageEngine::Database(storage) => storage.exists(key).await,
+            StorageEngine::S3(storage) => storage.exists(key).await,
+        }
+    }
+
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>, AppError> {
+        match self {
+            StorageEngine::Local(storage) => storage.list(prefix).await,
+            StorageEngine::Database(storage) => storage.list(prefix).await,
+            StorageEngine::S3(storage) => storage.list(prefix).await,
+        }
+    }
+}
+
+/// Build the storage backend selected by `STORAGE_TYPE`.
+pub fn initialize(
+    diesel_pool: Arc<r2d2::Pool<r2d2::ConnectionManager<MysqlConnection>>>,
+) -> StorageEngine {
+    let config_env = &CONFIG;
+    match config_env.storage_type {
+        StorageType::Local => {
+            StorageEngine::Local(LocalStorage::new(&config_env.storage_local_root))
+        }
+        StorageType::Database => StorageEngine::Database(DatabaseStorage::new(diesel_pool)),
+        StorageType::S3 => StorageEngine::S3(
+            S3Storage::new(
+                &config_env.storage_s3_endpoint,
+                &config_env.storage_s3_region,
+                &config_env.storage_s3_bucket,
+                &config_env.storage_s3_access_key,
+                &config_env.storage_s3_secret_key,
+            )
+            .expect("storage s3 init failed"),
+        ),
+    }
+}
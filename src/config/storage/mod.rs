@@ -0,0 +1,4 @@
+pub mod main;
+pub mod local;
+pub mod database;
+pub mod s3;
\ No newline at end of file
@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use axum::body::Bytes;
+use s3::{creds::Credentials, error::S3Error, Bucket, Region};
+
+use crate::dto::response::app_error::AppError;
+
+use super::main::StorageBackend;
+
+/// Stores files as objects in an S3-compatible bucket. Path-style addressing
+/// is used so a custom endpoint (e.g. a local MinIO) works without DNS setup.
+pub struct S3Storage {
+    bucket: Box<Bucket>,
+}
+
+impl S3Storage {
+    pub fn new(
+        endpoint: &str,
+        region: &str,
+        bucket_name: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Result<S3Storage, AppError> {
+        let region = Region::Custom {
+            region: region.to_string(),
+            endpoint: endpoint.to_string(),
+        };
+        let credentials = Credentials {
+            access_key: Some(access_key.to_string()),
+            secret_key: Some(secret_key.to_string()),
+            security_token: None,
+            session_token: None,
+            expiration: None,
+        };
+        let bucket = Bucket::new(bucket_name, region, credentials)
+            .map_err(|error| AppError::Other(format!("open bucket failed: {}", error)))?
+            .with_path_style();
+        Ok(S3Storage { bucket })
+    }
+}
+
+fn is_not_found(error: &S3Error) -> bool {
+    matches!(error, S3Error::HttpFailWithBody(404, _))
+}
+
+impl StorageBackend for S3Storage {
+    async fn put(&self, key: &str, data: Bytes) -> Result<(), AppError> {
+        self.bucket
+            .put_object(key, &data)
+            .await
+            .map_err(|error| AppError::Other(format!("put object failed: {}, key: {}", error, key)))?;
+        Ok(())
+    }
+
+    async fn put_file(&self, key: &str, path: &Path) -> Result<(), AppError> {
+        let mut file = tokio::fs::File::open(path)
+            .await
+            .map_err(|error| AppError::Other(format!("open file failed: {}, key: {}", error, key)))?;
+        // sent as a multipart upload, one part in memory at a time
+        self.bucket
+            .put_object_stream(&mut file, key)
+            .await
+            .map_err(|error| AppError::Other(format!("put object failed: {}, key: {}", error, key)))?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, AppError> {
+        match self.bucket.get_object(key).await {
+            Ok(response) => Ok(Some(response.bytes().clone())),
+            Err(error) if is_not_found(&error) => Ok(None),
+            Err(error) => Err(AppError::Other(format!(
+                "get object failed: {}, key: {}",
+                error, key
+            ))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        match self.bucket.delete_object(key).await {
+            Ok(_) => Ok(()),
+            Err(error) if is_not_found(&error) => Ok(()),
+            Err(error) => Err(AppError::Other(format!(
+                "delete object failed: {}, key: {}",
+                error, key
+            ))),
+        }
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, AppError> {
+        match self.bucket.head_object(key).await {
+            Ok((_, status_code)) => Ok(status_code == 200),
+            Err(error) if is_not_found(&error) => Ok(false),
+            Err(error) => Err(AppError::Other(format!(
+                "head object failed: {}, key: {}",
+                error, key
+            ))),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, AppError> {
+        let results = self
+            .bucket
+            .list(prefix.to_string(), None)
+            .await
+            .map_err(|error| AppError::Other(format!("list objects failed: {}, prefix: {}", error, prefix)))?;
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .map(|object| object.key)
+            .collect())
+    }
+}
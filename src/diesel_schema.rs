@@ -1,3 +1,17 @@
+diesel::table! {
+    audit_log (id) {
+        id -> Bigint,
+        #[max_length = 50]
+        entity -> Varchar,
+        entity_id -> Bigint,
+        #[max_length = 20]
+        action -> Varchar,
+        actor_id -> Bigint,
+        changes -> Longtext,
+        created_on -> Datetime,
+    }
+}
+
 diesel::table! {
     m_biodata (id) {
         id -> Bigint,
@@ -15,6 +29,8 @@ diesel::table! {
         mobile_phone -> Nullable<Varchar>,
         modified_by -> Nullable<Bigint>,
         modified_on -> Nullable<Datetime>,
+        image_file_id -> Nullable<Bigint>,
+        row_version -> Bigint,
     }
 }
 
@@ -75,6 +91,88 @@ diesel::table! {
         file_type -> Nullable<Varchar>,
         modified_by -> Nullable<Bigint>,
         modified_on -> Nullable<Datetime>,
+        #[max_length = 64]
+        file_hash -> Nullable<Varchar>,
+        file_size -> Nullable<Bigint>,
+        #[max_length = 20]
+        scan_status -> Varchar,
+        row_version -> Bigint,
+    }
+}
+
+diesel::table! {
+    m_file_blob (file_hash) {
+        #[max_length = 64]
+        file_hash -> Varchar,
+        #[max_length = 255]
+        file_path -> Varchar,
+        file_size -> Bigint,
+        ref_count -> Bigint,
+        file -> Nullable<Blob>,
+        created_on -> Datetime,
+        modified_on -> Nullable<Datetime>,
+    }
+}
+
+diesel::table! {
+    m_file_upload (id) {
+        #[max_length = 36]
+        id -> Varchar,
+        #[max_length = 100]
+        file_name -> Nullable<Varchar>,
+        #[max_length = 100]
+        content_type -> Nullable<Varchar>,
+        upload_length -> Bigint,
+        upload_offset -> Bigint,
+        file_id -> Nullable<Bigint>,
+        created_on -> Datetime,
+        modified_on -> Nullable<Datetime>,
+        expires_on -> Datetime,
+    }
+}
+
+diesel::table! {
+    m_file_variant (file_id, name) {
+        file_id -> Bigint,
+        #[max_length = 50]
+        name -> Varchar,
+        #[max_length = 64]
+        file_hash -> Varchar,
+        #[max_length = 255]
+        file_path -> Varchar,
+        file_size -> Bigint,
+        #[max_length = 100]
+        content_type -> Varchar,
+        width -> Integer,
+        height -> Integer,
+        created_on -> Datetime,
+    }
+}
+
+diesel::table! {
+    m_file_legacy_path (file_id) {
+        file_id -> Bigint,
+        #[max_length = 255]
+        legacy_path -> Varchar,
+        #[max_length = 255]
+        file_path -> Varchar,
+        migrated_on -> Datetime,
+    }
+}
+
+diesel::table! {
+    m_file_version (file_id, version) {
+        file_id -> Bigint,
+        version -> Integer,
+        #[max_length = 100]
+        file_name -> Nullable<Varchar>,
+        #[max_length = 64]
+        file_hash -> Varchar,
+        #[max_length = 255]
+        file_path -> Varchar,
+        file_size -> Bigint,
+        created_by -> Bigint,
+        created_on -> Datetime,
     }
 }
 
@@ -82,4 +180,9 @@ diesel::table! {
 diesel::joinable!(m_user -> m_biodata (biodata_id));
 diesel::joinable!(m_user -> m_role (role_id));
 
+diesel::joinable!(m_file_variant -> m_file (file_id));
+diesel::joinable!(m_biodata -> m_file (image_file_id));
+diesel::joinable!(m_file_version -> m_file (file_id));
+
 diesel::allow_tables_to_appear_in_same_query!(m_biodata, m_role, m_user,);
+diesel::allow_tables_to_appear_in_same_query!(m_file, m_file_variant, m_file_version, m_biodata,);
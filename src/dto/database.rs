@@ -1,7 +1,35 @@
-use diesel::prelude::QueryableByName;
+use diesel::{define_sql_function, prelude::QueryableByName};
 
 #[derive(QueryableByName)]
 pub struct CountResult {
     #[diesel(sql_type = diesel::sql_types::BigInt)]
     pub count: i64,
 }
+
+define_sql_function! {
+    /// MySQL `LAST_INSERT_ID()`, the auto increment id of the connection's last insert
+    fn last_insert_id() -> Unsigned<BigInt>;
+}
+
+/// One row of a dynamic query, selected as a MySQL `JSON_OBJECT(...)`.
+#[derive(QueryableByName)]
+pub struct JsonRow {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub json_row: String,
+}
+
+/// Id and relevance of a full-text match.
+#[derive(QueryableByName)]
+pub struct SearchRow {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub id: i64,
+    #[diesel(sql_type = diesel::sql_types::Double)]
+    pub score: f64,
+}
+
+/// Id of a row matched by a dynamic query.
+#[derive(QueryableByName)]
+pub struct IdResult {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub id: i64,
+}
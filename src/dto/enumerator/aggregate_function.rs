@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum AggregateFunction {
+    COUNT,
+    MIN,
+    MAX,
+    SUM,
+    AVG,
+}
@@ -0,0 +1,23 @@
+use std::fmt;
+
+/// Kind of change an audit log record describes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuditAction {
+    CREATE,
+    UPDATE,
+    DELETE,
+    RESTORE,
+    PURGE,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditAction::CREATE => write!(f, "create"),
+            AuditAction::UPDATE => write!(f, "update"),
+            AuditAction::DELETE => write!(f, "delete"),
+            AuditAction::RESTORE => write!(f, "restore"),
+            AuditAction::PURGE => write!(f, "purge"),
+        }
+    }
+}
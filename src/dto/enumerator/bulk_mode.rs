@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// How a bulk request handles a failing item.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkMode {
+    /// one transaction, the first failure rolls every item back
+    #[default]
+    Atomic,
+    /// every item on its own, failures do not affect the others
+    BestEffort,
+}
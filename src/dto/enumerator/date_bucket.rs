@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// Truncation of a datetime column when grouping, buckets are in UTC.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum DateBucket {
+    HOUR,
+    DAY,
+    WEEK,
+    MONTH,
+    YEAR,
+}
@@ -24,4 +24,25 @@ impl fmt::Display for FileType {
             FileType::UNKNOWN => write!(f, "unknown"),
         }
     }
-}
\ No newline at end of file
+}
+
+impl FileType {
+    pub fn from_content_type(content_type: &str) -> FileType {
+        match content_type {
+            // Image types
+            "image/jpeg" | "image/png" | "image/gif" => FileType::IMAGE,
+            // Audio types
+            "audio/mpeg" | "audio/wav" | "audio/ogg" => FileType::AUDIO,
+            // Video types
+            "video/mp4" | "video/x-msvideo" | "video/x-flv" => FileType::VIDEO,
+            // Document types
+            "application/pdf"
+            | "application/msword"
+            | "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+                FileType::DOCUMENT
+            }
+            // Default case for unknown types
+            _ => FileType::UNKNOWN,
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdStrategy {
+    /// the database assigns the id, requires an `AUTO_INCREMENT` primary key
+    AutoIncrement,
+    /// time ordered 64-bit id: 41 bits millisecond, 10 bits node, 12 bits sequence
+    Snowflake,
+}
@@ -1,7 +1,18 @@
+pub mod aggregate_function;
+pub mod audit_action;
+pub mod bulk_mode;
 pub mod database_type;
+pub mod date_bucket;
 
 pub mod filter_data_type;
 pub mod filter_match_mode;
 pub mod filter_mode;
 
-pub mod file_type;
\ No newline at end of file
+pub mod export_format;
+pub mod file_type;
+pub mod id_strategy;
+pub mod reconcile_action;
+pub mod scan_status;
+pub mod scanner_type;
+pub mod search_mode;
+pub mod storage_type;
\ No newline at end of file
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// What the reconciliation job does with stored objects no row points at.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReconcileAction {
+    Report,
+    Delete,
+    Quarantine,
+}
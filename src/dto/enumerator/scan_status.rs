@@ -0,0 +1,18 @@
+use std::fmt;
+
+/// Availability of an `MFile` with respect to virus scanning. Only `CLEAN`
+/// files are served.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScanStatus {
+    PENDING,
+    CLEAN,
+}
+
+impl fmt::Display for ScanStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanStatus::PENDING => write!(f, "pending"),
+            ScanStatus::CLEAN => write!(f, "clean"),
+        }
+    }
+}
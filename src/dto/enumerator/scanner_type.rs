@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScannerType {
+    Clamav,
+    Noop,
+}
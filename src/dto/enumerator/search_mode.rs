@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// How `/search` matches the words of `_q`.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// whole words, any of them, ranked by relevance
+    Natural,
+    /// every word must start a word of the row, for search as you type
+    #[default]
+    Prefix,
+    /// words within a small edit distance, tolerates typos
+    Fuzzy,
+}
@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageType {
+    Local,
+    Database,
+    S3,
+}
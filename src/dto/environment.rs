@@ -2,7 +2,10 @@ use std::fs;
 
 use serde::Deserialize;
 
-use super::enumerator::database_type::DatabaseType;
+use super::enumerator::{
+    database_type::DatabaseType, id_strategy::IdStrategy, reconcile_action::ReconcileAction, scanner_type::ScannerType,
+    storage_type::StorageType,
+};
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Environment {
@@ -18,6 +21,9 @@ pub struct Environment {
     pub database_max_pool: u32,
     pub database_min_pool: u32,
 
+    pub id_strategy: IdStrategy,
+    pub id_node_id: i64,
+
     pub jwt_expiration: i64,
     pub jwt_key: String,
 
@@ -29,6 +35,31 @@ pub struct Environment {
 
     pub redis_cache_enable: bool,
 
+    pub storage_type: StorageType,
+    pub storage_local_root: String,
+    pub storage_s3_endpoint: String,
+    pub storage_s3_region: String,
+    pub storage_s3_bucket: String,
+    pub storage_s3_access_key: String,
+    pub storage_s3_secret_key: String,
+    pub storage_upload_dir: String,
+    pub storage_upload_expiration: i64,
+    pub storage_upload_max_size: i64,
+    pub storage_image_variants: String,
+    pub storage_url_secret: String,
+    pub storage_url_expiration: i64,
+    pub storage_version_retention: i64,
+    pub storage_reconcile_action: ReconcileAction,
+    pub storage_reconcile_interval: u64,
+
+    pub scanner_type: ScannerType,
+    pub scanner_clamav_address: String,
+    pub scanner_clamav_timeout: u64,
+
+    pub scheduler_purge_cron: String,
+    pub purge_m_biodata_retention: i64,
+    pub purge_m_file_retention: i64,
+
     pub rust_backtrace: u8,
     pub rust_log: String,
 
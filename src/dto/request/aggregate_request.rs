@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+    dto::enumerator::{aggregate_function::AggregateFunction, date_bucket::DateBucket},
+    util::serializer::json_string_serializer,
+};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
+pub struct GroupBy {
+    pub id: String,
+    /// only for datetime columns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub bucket: Option<DateBucket>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
+pub struct Aggregate {
+    pub function: AggregateFunction,
+    /// column the function applies to, `COUNT` without one counts rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
+pub struct Aggregation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    #[serde(with = "json_string_serializer")]
+    pub _group_by: Option<Vec<GroupBy>>,
+    /// `COUNT` of rows when empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    #[serde(with = "json_string_serializer")]
+    pub _aggregate: Option<Vec<Aggregate>>,
+}
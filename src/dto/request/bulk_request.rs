@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dto::enumerator::bulk_mode::BulkMode;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BulkOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub mode: Option<BulkMode>,
+}
+
+/// Item of a bulk delete, `row_version` is the one the client last read.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BulkDeleteItem {
+    pub id: i64,
+    #[serde(default)]
+    pub row_version: Option<i64>,
+}
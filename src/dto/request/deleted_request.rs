@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// `?include_deleted=true` returns soft deleted rows as well.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct IncludeDeleted {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub include_deleted: Option<bool>,
+}
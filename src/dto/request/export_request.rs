@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dto::enumerator::export_format::ExportFormat;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Export {
+    /// takes precedence over the `Accept` header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub format: Option<ExportFormat>,
+    /// comma separated column names, every exportable column when empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub _columns: Option<String>,
+}
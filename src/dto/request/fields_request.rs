@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Fields {
+    /// comma separated field names, e.g. `id,fullname`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub _fields: Option<String>,
+}
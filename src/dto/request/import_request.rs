@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dto::enumerator::export_format::ExportFormat;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Import {
+    /// validate only, nothing is written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub dry_run: Option<bool>,
+    /// download the rejected rows as a file instead of the JSON summary
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub report: Option<ExportFormat>,
+}
@@ -1,4 +1,11 @@
+pub mod aggregate_request;
+pub mod bulk_request;
+pub mod deleted_request;
+pub mod export_request;
+pub mod fields_request;
 pub mod filter_request;
+pub mod import_request;
 pub mod pagination_request;
 pub mod search_request;
+pub mod signed_url_request;
 pub mod sort_request;
\ No newline at end of file
@@ -1,8 +1,21 @@
 use serde::{Deserialize, Serialize};
 
+use crate::dto::enumerator::search_mode::SearchMode;
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Search {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub _q: Option<String>,
 }
+
+/// Query of the `/search` endpoints.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FullTextSearch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub _q: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub mode: Option<SearchMode>,
+}
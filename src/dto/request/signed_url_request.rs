@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+/// Query of a link minted by a `signed-url` endpoint.
+#[derive(Deserialize, Debug)]
+pub struct SignedUrlRequest {
+    pub expires: i64,
+    pub signature: String,
+}
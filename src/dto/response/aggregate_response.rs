@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::dto::enumerator::filter_data_type::FilterDataType;
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AggregateColumn {
+    pub name: String,
+    pub data_type: FilterDataType,
+}
+
+/// Group columns first, then one column per aggregate; every row is an
+/// object keyed by column name.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AggregateResponse {
+    pub columns: Vec<AggregateColumn>,
+    pub rows: Vec<Value>,
+}
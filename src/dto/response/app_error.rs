@@ -2,9 +2,11 @@ use std::collections::HashMap;
 
 use axum::{
     Json,
-    http::StatusCode,
+    http::{header::WWW_AUTHENTICATE, StatusCode},
     response::{IntoResponse, Response},
 };
+use diesel::result::DatabaseErrorKind;
+use serde_json::{Value, json};
 use validator::ValidationErrors;
 
 use crate::dto::response::app_response::AppResponse;
@@ -14,10 +16,64 @@ pub enum AppError {
     InvalidRequest(ValidationErrors),
     DataExist,
     NotFound,
+    Unauthorized(String),
+    Forbidden(String),
+    Conflict(String),
+    UnprocessableEntity(String),
+    PreconditionFailed(String),
+    PreconditionRequired,
     InternalServerError,
     Other(String),
 }
 
+impl AppError {
+    /// Status code and error body the variant responds with, for places that
+    /// embed an error instead of responding with it (e.g. bulk results).
+    pub fn status_and_error(&self) -> (StatusCode, Value) {
+        match self {
+            AppError::InvalidRequest(validation_errors) => (
+                StatusCode::BAD_REQUEST,
+                json!(parse_validation_error_message(&format!(
+                    "{validation_errors}"
+                ))),
+            ),
+            AppError::DataExist => (StatusCode::BAD_REQUEST, json!("resource exist")),
+            AppError::NotFound => (StatusCode::NOT_FOUND, json!("resource not found")),
+            AppError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, json!(message)),
+            AppError::Forbidden(message) => (StatusCode::FORBIDDEN, json!(message)),
+            AppError::Conflict(message) => (StatusCode::CONFLICT, json!(message)),
+            AppError::UnprocessableEntity(message) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, json!(message))
+            }
+            AppError::PreconditionFailed(message) => {
+                (StatusCode::PRECONDITION_FAILED, json!(message))
+            }
+            AppError::PreconditionRequired => (
+                StatusCode::PRECONDITION_REQUIRED,
+                json!("If-Match header is mandatory"),
+            ),
+            AppError::InternalServerError => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json!("internal server error"),
+            ),
+            AppError::Other(message) => (StatusCode::INTERNAL_SERVER_ERROR, json!(message)),
+        }
+    }
+}
+
+/// Lets `?` end a `conn.transaction` closure that returns `AppError`. A
+/// duplicate key is the client's mistake, not a server failure.
+impl From<diesel::result::Error> for AppError {
+    fn from(error: diesel::result::Error) -> Self {
+        match error {
+            diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                AppError::DataExist
+            }
+            _ => AppError::Other(format!("query failed: {}", error)),
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         match self {
@@ -49,6 +105,91 @@ impl IntoResponse for AppError {
                 )
                     .into_response()
             }
+            AppError::Unauthorized(message) => {
+                let status_code = StatusCode::UNAUTHORIZED;
+                (
+                    status_code,
+                    [(WWW_AUTHENTICATE, "Bearer")],
+                    Json(AppResponse {
+                        status: status_code.as_str().to_string(),
+                        message: "error".to_owned(),
+                        timestamp: chrono::Utc::now().naive_utc(),
+                        error: Some(message),
+                        data: None,
+                    }),
+                )
+                    .into_response()
+            }
+            AppError::Forbidden(message) => {
+                let status_code = StatusCode::FORBIDDEN;
+                (
+                    status_code,
+                    Json(AppResponse {
+                        status: status_code.as_str().to_string(),
+                        message: "error".to_owned(),
+                        timestamp: chrono::Utc::now().naive_utc(),
+                        error: Some(message),
+                        data: None,
+                    }),
+                )
+                    .into_response()
+            }
+            AppError::Conflict(message) => {
+                let status_code = StatusCode::CONFLICT;
+                (
+                    status_code,
+                    Json(AppResponse {
+                        status: status_code.as_str().to_string(),
+                        message: "error".to_owned(),
+                        timestamp: chrono::Utc::now().naive_utc(),
+                        error: Some(message),
+                        data: None,
+                    }),
+                )
+                    .into_response()
+            }
+            AppError::UnprocessableEntity(message) => {
+                let status_code = StatusCode::UNPROCESSABLE_ENTITY;
+                (
+                    status_code,
+                    Json(AppResponse {
+                        status: status_code.as_str().to_string(),
+                        message: "error".to_owned(),
+                        timestamp: chrono::Utc::now().naive_utc(),
+                        error: Some(message),
+                        data: None,
+                    }),
+                )
+                    .into_response()
+            }
+            AppError::PreconditionFailed(message) => {
+                let status_code = StatusCode::PRECONDITION_FAILED;
+                (
+                    status_code,
+                    Json(AppResponse {
+                        status: status_code.as_str().to_string(),
+                        message: "error".to_owned(),
+                        timestamp: chrono::Utc::now().naive_utc(),
+                        error: Some(message),
+                        data: None,
+                    }),
+                )
+                    .into_response()
+            }
+            AppError::PreconditionRequired => {
+                let status_code = StatusCode::PRECONDITION_REQUIRED;
+                (
+                    status_code,
+                    Json(AppResponse {
+                        status: status_code.as_str().to_string(),
+                        message: "error".to_owned(),
+                        timestamp: chrono::Utc::now().naive_utc(),
+                        error: Some("If-Match header is mandatory".to_string()),
+                        data: None,
+                    }),
+                )
+                    .into_response()
+            }
             AppError::InternalServerError => {
                 let status_code = StatusCode::INTERNAL_SERVER_ERROR;
                 (
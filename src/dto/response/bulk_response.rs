@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Outcome of one item of a bulk request, `index` points into the request array.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct BulkItemResult {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// HTTP status the item would have had as a single request
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// same shape as the `error` of a single request's response
+    pub error: Option<Value>,
+}
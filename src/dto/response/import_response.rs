@@ -0,0 +1,23 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub inserted: usize,
+    pub updated: usize,
+    pub rejected: usize,
+    pub rejected_rows: Vec<ImportRejectedRow>,
+}
+
+/// A row that was not imported, `row` is the line in the uploaded file.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ImportRejectedRow {
+    pub row: u64,
+    pub values: BTreeMap<String, String>,
+    /// per field messages, or a single message for errors of the whole row
+    pub errors: Value,
+}
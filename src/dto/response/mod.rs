@@ -1,3 +1,7 @@
+pub mod aggregate_response;
 pub mod app_response;
 pub mod app_error;
-pub mod pagination_response;
\ No newline at end of file
+pub mod bulk_response;
+pub mod import_response;
+pub mod pagination_response;
+pub mod search_response;
\ No newline at end of file
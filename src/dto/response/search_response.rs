@@ -0,0 +1,13 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One ranked `/search` result.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SearchHit {
+    pub score: f64,
+    /// searched fields that matched, matches wrapped in `<em>`
+    pub highlight: BTreeMap<String, String>,
+    pub data: Value,
+}
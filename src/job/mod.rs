@@ -0,0 +1 @@
+pub mod purge;
\ No newline at end of file
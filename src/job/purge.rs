@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::MysqlConnection;
+
+use crate::{
+    config::environment::CONFIG,
+    dto::response::app_error::AppError,
+    module::{
+        m_biodata::{repository as m_biodata_repository, service as m_biodata_service},
+        m_file::{repository as m_file_repository, service as m_file_service},
+    },
+    state::AppState,
+};
+
+/// Rows removed by one purge of a table.
+#[derive(Debug, Default)]
+struct PurgeSummary {
+    purged: usize,
+    skipped: usize,
+    failed: usize,
+}
+
+/// Permanently delete rows soft deleted longer ago than their table's
+/// retention. Biodata go first, purging a biodata also purges its image file.
+pub async fn run(state: Arc<AppState>) {
+    let mut db_conn = match state.diesel_pool_mysql.get() {
+        Ok(value) => value,
+        Err(error) => {
+            log::error!("purge: get connection failed {}", error);
+            return;
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+    let biodata_cutoff = now - Duration::days(CONFIG.purge_m_biodata_retention);
+    match purge_m_biodata(&state, &mut db_conn, biodata_cutoff).await {
+        Ok(summary) => log::info!(
+            "purge: m_biodata deleted before {}, purged: {}, failed: {}",
+            biodata_cutoff,
+            summary.purged,
+            summary.failed
+        ),
+        Err(error) => log::error!("purge: m_biodata failed: {:?}", error),
+    }
+
+    let file_cutoff = now - Duration::days(CONFIG.purge_m_file_retention);
+    match purge_m_file(&state, &mut db_conn, file_cutoff).await {
+        Ok(summary) => log::info!(
+            "purge: m_file deleted before {}, purged: {}, skipped: {}, failed: {}",
+            file_cutoff,
+            summary.purged,
+            summary.skipped,
+            summary.failed
+        ),
+        Err(error) => log::error!("purge: m_file failed: {:?}", error),
+    }
+}
+
+async fn purge_m_biodata(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    cutoff: NaiveDateTime,
+) -> Result<PurgeSummary, AppError> {
+    let mut summary = PurgeSummary::default();
+    for biodata_id in m_biodata_repository::find_deleted_before(conn, cutoff)? {
+        let biodata = match m_biodata_repository::find_by_id(conn, biodata_id, true)? {
+            Some(value) => value,
+            None => continue,
+        };
+        match m_biodata_service::purge_biodata(state, conn, &biodata).await {
+            Ok(_) => summary.purged += 1,
+            Err(error) => {
+                log::error!("purge: m_biodata failed, id: {}: {:?}", biodata_id, error);
+                summary.failed += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+async fn purge_m_file(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    cutoff: NaiveDateTime,
+) -> Result<PurgeSummary, AppError> {
+    let mut summary = PurgeSummary::default();
+    for file_id in m_file_repository::find_deleted_before(conn, cutoff)? {
+        let m_file = match m_file_repository::find_by_id(conn, file_id, true)? {
+            Some(value) => value,
+            None => continue,
+        };
+        match m_file_service::purge_file(state, conn, &m_file).await {
+            Ok(_) => summary.purged += 1,
+            // still the image of a biodata, it goes once that biodata is purged
+            Err(AppError::Conflict(_)) => summary.skipped += 1,
+            Err(error) => {
+                log::error!("purge: m_file failed, id: {}: {:?}", file_id, error);
+                summary.failed += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
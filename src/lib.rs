@@ -4,4 +4,5 @@ pub mod util;
 pub mod state;
 pub mod middleware;
 pub mod module;
+pub mod job;
 pub mod diesel_schema;
\ No newline at end of file
@@ -2,11 +2,11 @@ use std::sync::Arc;
 
 use axum::{
     extract::DefaultBodyLimit, http::{
-        header::{AUTHORIZATION, CONTENT_TYPE}, HeaderValue, Method
+        header::{AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, LOCATION}, HeaderName, HeaderValue, Method
     }, middleware::from_fn, Extension, Router
 };
 use axum_crud_demo::{
-    config::{self, environment::CONFIG, logger}, dto::environment::Environment, middleware::logger_middleware, module::{health, hello_world, m_biodata, redis_pubsub}, state::AppState
+    config::{self, environment::CONFIG, logger}, dto::{enumerator::reconcile_action::ReconcileAction, environment::Environment, response::app_error::AppError}, job, middleware::logger_middleware, module::{audit_log, auth, health, hello_world, m_biodata, m_file, m_file_upload, m_role, m_user, redis_pubsub}, state::AppState, util::{signed_url, token}
 };
 // use diesel_async::pooled_connection::AsyncDieselConnectionManager;
 use tokio::{net::TcpListener, signal};
@@ -28,20 +28,147 @@ async fn main() {
     // let db_url = Environment::get_database_url(&config);
     // let config_state = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new(db_url);
     // let pool_async = bb8::Pool::builder().build(config_state).await.unwrap();
-    let diesel_pool = config::database::get_diesel_mysql_db_pool();
+    let diesel_pool = Arc::new(config::database::get_diesel_mysql_db_pool());
+    let storage = config::storage::main::initialize(diesel_pool.clone());
+    match diesel_pool.get() {
+        Ok(mut db_conn) => match m_file::legacy_path::migrate(&mut db_conn) {
+            Ok(0) => {}
+            Ok(count) => log::info!("rewrote {} legacy file paths to storage keys", count),
+            Err(error) => {
+                log::error!("legacy file paths: {:?}", error);
+                std::process::exit(1);
+            }
+        },
+        Err(error) => {
+            log::error!("legacy file paths: get connection failed {}", error);
+            std::process::exit(1);
+        }
+    }
+    let scanner = config::scanner::main::initialize();
+    config::id_generator::initialize();
+    if let Err(error) = signed_url::initialize() {
+        log::error!("STORAGE_URL_SECRET: {:?}", error);
+        std::process::exit(1);
+    }
+    if let Err(error) = token::initialize() {
+        log::error!("JWT_KEY: {:?}", error);
+        std::process::exit(1);
+    }
+    m_file::reconcile::initialize();
 
-    let state = AppState { diesel_pool_mysql: Arc::new(diesel_pool), status: "up".to_string() };
+    let state = AppState {
+        diesel_pool_mysql: diesel_pool,
+        storage: Arc::new(storage),
+        scanner: Arc::new(scanner),
+        status: "up".to_string(),
+    };
     let shared_state = Arc::new(state);
 
+    // one-off commands: `reconcile [report|delete|quarantine]` and
+    // `create-admin <email>` reading the password from stdin
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("create-admin") {
+        let email = match args.get(2) {
+            Some(value) => value.clone(),
+            None => {
+                log::error!("usage: create-admin <email>, the password is read from stdin");
+                std::process::exit(1);
+            }
+        };
+        let mut plain_password = String::new();
+        if let Err(error) = std::io::stdin().read_line(&mut plain_password) {
+            log::error!("create-admin failed: read password: {}", error);
+            std::process::exit(1);
+        }
+        let plain_password = plain_password.trim_end_matches(['\r', '\n']).to_string();
+
+        let result = match shared_state.diesel_pool_mysql.get() {
+            Ok(mut db_conn) => m_user::service::create_admin(&mut db_conn, email, plain_password).await,
+            Err(error) => Err(AppError::Other(format!("get connection failed {error}"))),
+        };
+        match result {
+            Ok(user) => println!("{}", serde_json::to_string_pretty(&user).unwrap()),
+            Err(error) => {
+                log::error!("create-admin failed: {:?}", error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("reconcile") {
+        let action = match args.get(2).map(String::as_str) {
+            Some("report") => ReconcileAction::Report,
+            Some("delete") => ReconcileAction::Delete,
+            Some("quarantine") => ReconcileAction::Quarantine,
+            Some(other) => panic!("unknown reconcile action: {}", other),
+            None => config.storage_reconcile_action,
+        };
+        match m_file::reconcile::reconcile(&shared_state, action).await {
+            Ok(report) => {
+                m_file::reconcile::log_report(&report);
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            }
+            Err(error) => {
+                log::error!("reconcile failed: {:?}", error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // background jobs
+    tokio::spawn(m_file_upload::controller::run_expiration(shared_state.clone()));
+    tokio::spawn(m_file::reconcile::run_schedule(shared_state.clone()));
+    if let Err(error) = config::scheduler::spawn_job(
+        "purge",
+        &config.scheduler_purge_cron,
+        shared_state.clone(),
+        job::purge::run,
+    ) {
+        log::error!("SCHEDULER_PURGE_CRON: {:?}", error);
+        std::process::exit(1);
+    }
+
     let cors = CorsLayer::new()
         .allow_origin(["http://localhost:3000".parse::<HeaderValue>().unwrap()])
-        .allow_headers([CONTENT_TYPE, AUTHORIZATION])
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE]);
+        .allow_headers([
+            CONTENT_TYPE,
+            AUTHORIZATION,
+            IF_MATCH,
+            IF_NONE_MATCH,
+            HeaderName::from_static("tus-resumable"),
+            HeaderName::from_static("upload-length"),
+            HeaderName::from_static("upload-offset"),
+            HeaderName::from_static("upload-metadata"),
+        ])
+        .expose_headers([
+            ETAG,
+            LOCATION,
+            HeaderName::from_static("tus-resumable"),
+            HeaderName::from_static("upload-offset"),
+            HeaderName::from_static("upload-length"),
+            HeaderName::from_static("upload-expires"),
+            HeaderName::from_static("upload-file-id"),
+        ])
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::PATCH,
+            Method::HEAD,
+            Method::OPTIONS,
+        ]);
 
     let api = Router::new()
         .nest("/hello-world", hello_world::router::new())
         .nest("/health", health::router::new())
+        .nest("/auth", auth::router::new())
         .nest("/m-biodata", m_biodata::router::new())
+        .nest("/m-file", m_file::router::new())
+        .nest("/m-role", m_role::router::new())
+        .nest("/m-user", m_user::router::new())
+        .nest("/audit-log", audit_log::router::new())
         .nest("/pubsub", redis_pubsub::router::new());
 
     let router = Router::new()
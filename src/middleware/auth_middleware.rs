@@ -0,0 +1,87 @@
+use axum::{
+    extract::{Query, Request},
+    http::{header::AUTHORIZATION, HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    dto::{request::deleted_request::IncludeDeleted, response::app_error::AppError},
+    util::token::{self, Claims},
+};
+
+/// `m_role.code` allowed through `require_admin`.
+pub const ADMIN_ROLE: &str = "ADMIN";
+
+tokio::task_local! {
+    /// `AuthUser.id` of the request the current task serves.
+    static CURRENT_USER_ID: i64;
+}
+
+/// Id of the authenticated caller the current request runs for, `None`
+/// outside `require_user` and `require_admin` (e.g. scheduled jobs). Lets the
+/// audit log record the actor without every write taking it as a parameter.
+pub fn current_user_id() -> Option<i64> {
+    CURRENT_USER_ID.try_with(|user_id| *user_id).ok()
+}
+
+/// Caller of an authenticated request, handlers read it as `Extension<AuthUser>`.
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub id: i64,
+    pub role: Option<String>,
+}
+
+fn bearer_claims(headers: &HeaderMap) -> Result<Claims, AppError> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match token.and_then(|value| token::verify(value.trim())) {
+        Some(claims) => Ok(claims),
+        None => Err(AppError::Unauthorized("valid bearer token is mandatory".to_string())),
+    }
+}
+
+/// Reject requests without a valid access token.
+pub async fn require_user(mut req: Request, next: Next) -> Result<Response, AppError> {
+    let claims = bearer_claims(req.headers())?;
+    req.extensions_mut().insert(AuthUser {
+        id: claims.sub,
+        role: claims.role,
+    });
+    Ok(CURRENT_USER_ID.scope(claims.sub, next.run(req)).await)
+}
+
+/// `require_user` for callers with the `ADMIN_ROLE` role only.
+pub async fn require_admin(mut req: Request, next: Next) -> Result<Response, AppError> {
+    let claims = bearer_claims(req.headers())?;
+    if claims.role.as_deref() != Some(ADMIN_ROLE) {
+        return Err(AppError::Forbidden("admin role is mandatory".to_string()));
+    }
+    req.extensions_mut().insert(AuthUser {
+        id: claims.sub,
+        role: claims.role,
+    });
+    Ok(CURRENT_USER_ID.scope(claims.sub, next.run(req)).await)
+}
+
+/// `require_user` for requests that change data, reads pass untouched.
+pub async fn require_user_for_writes(req: Request, next: Next) -> Result<Response, AppError> {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(next.run(req).await);
+    }
+    require_user(req, next).await
+}
+
+/// `require_admin` for requests asking for soft deleted rows with
+/// `?include_deleted=true`, any other request passes untouched.
+pub async fn require_admin_for_deleted(req: Request, next: Next) -> Result<Response, AppError> {
+    let include_deleted = Query::<IncludeDeleted>::try_from_uri(req.uri())
+        .map(|Query(value)| value.include_deleted.unwrap_or(false))
+        .unwrap_or(false);
+    if !include_deleted {
+        return Ok(next.run(req).await);
+    }
+    require_admin(req, next).await
+}
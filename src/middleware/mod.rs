@@ -1 +1,2 @@
+pub mod auth_middleware;
 pub mod logger_middleware;
\ No newline at end of file
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Json, Path, Query},
+    http::StatusCode,
+};
+use validator::Validate;
+
+use crate::{
+    dto::{
+        request::pagination_request::Pagination,
+        response::{app_error::AppError, app_response::AppResponse, pagination_response::PaginatedResponse},
+    },
+    module::audit_log::{
+        repository,
+        schema::{AuditLog, AUDITED_ENTITIES},
+    },
+    state::AppState,
+};
+
+/// Change history of one row, newest first. Purged rows keep their history.
+pub async fn find_page_by_entity(
+    Path((entity, entity_id)): Path<(String, i64)>,
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_pagination): Query<Pagination>,
+) -> Result<(StatusCode, Json<AppResponse<PaginatedResponse<AuditLog>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(err) = _pagination.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+    if !AUDITED_ENTITIES.contains(&entity.as_str()) {
+        return Err(AppError::NotFound);
+    }
+
+    let mut _page = _pagination.page.unwrap_or(0);
+    if _page < 0 {
+        _page = 0;
+    }
+    let mut _size = _pagination.size.unwrap_or(5);
+    if _size < 1 {
+        _size = 1;
+    }
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {entity_id}")));
+        }
+    };
+
+    let (records, total_of_elements) =
+        repository::find_page_by_entity(&mut db_conn, &entity, entity_id, _page, _size)?;
+    let mut total_of_pages = total_of_elements / _size;
+    if total_of_elements % _size != 0 {
+        total_of_pages += 1;
+    }
+
+    let status_code = StatusCode::OK;
+    Ok((
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(PaginatedResponse {
+                total_of_elements,
+                total_of_pages,
+                content: records,
+            }),
+            error: None,
+        }),
+    ))
+}
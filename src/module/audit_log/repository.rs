@@ -0,0 +1,44 @@
+use diesel::{dsl::insert_into, prelude::*};
+
+use crate::{
+    diesel_schema::audit_log::dsl::*, dto::response::app_error::AppError,
+    module::audit_log::schema::AuditLog,
+};
+
+pub fn insert_audit_log(conn: &mut MysqlConnection, record: AuditLog) -> Result<Option<()>, AppError> {
+    let rows_affected = insert_into(audit_log)
+        .values(&record)
+        .execute(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    if rows_affected > 0 {
+        return Ok(Some(()));
+    }
+    return Ok(None);
+}
+
+/// History of one entity row, newest change first.
+pub fn find_page_by_entity(
+    conn: &mut MysqlConnection,
+    entity_name: &str,
+    audited_id: i64,
+    page: i64,
+    size: i64,
+) -> Result<(Vec<AuditLog>, i64), AppError> {
+    let records = audit_log
+        .filter(entity.eq(entity_name))
+        .filter(entity_id.eq(audited_id))
+        .order(id.desc())
+        .limit(size)
+        .offset(size * page)
+        .select(AuditLog::as_select())
+        .load::<AuditLog>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, audited_id)))?;
+    let count = audit_log
+        .filter(entity.eq(entity_name))
+        .filter(entity_id.eq(audited_id))
+        .count()
+        .get_result::<i64>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, audited_id)))?;
+
+    Ok((records, count))
+}
@@ -0,0 +1,12 @@
+use axum::{middleware::from_fn, routing::get, Router};
+
+use crate::middleware::auth_middleware::require_admin;
+use crate::module::audit_log::controller::find_page_by_entity;
+
+
+pub fn new() -> Router {
+    Router::new()
+    .route("/{entity}/{entity_id}", get(find_page_by_entity))
+    // before/after hold personal data, also of purged rows
+    .route_layer(from_fn(require_admin))
+}
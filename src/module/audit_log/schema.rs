@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::{Insertable, Queryable, QueryableByName};
+use diesel::Selectable;
+use serde::{Deserialize, Serialize};
+
+use crate::diesel_schema::audit_log;
+use crate::module::{m_biodata, m_file, m_role, m_user};
+use crate::util::serializer::{date_serializer, json_text_serializer};
+
+/// One change of an audited entity. `changes` maps every column whose value
+/// changed to `{"before": ..., "after": ...}`.
+#[derive(
+    Debug,
+    Deserialize,
+    Serialize,
+    Clone,
+    Queryable,
+    QueryableByName,
+    Insertable,
+    Selectable
+)]
+#[diesel(table_name = audit_log)]
+pub struct AuditLog {
+    pub id: i64,
+    pub entity: String,
+    pub entity_id: i64,
+    pub action: String,
+    pub actor_id: i64,
+    #[serde(with = "json_text_serializer")]
+    pub changes: String,
+    #[serde(with = "date_serializer")]
+    pub created_on: NaiveDateTime,
+}
+
+/// Tables whose writes are recorded, the `{entity}` of `/audit-log/{entity}/{entity_id}`.
+pub const AUDITED_ENTITIES: &[&str] = &[
+    m_biodata::schema::AUDIT_ENTITY,
+    m_file::schema::AUDIT_ENTITY,
+    m_role::schema::AUDIT_ENTITY,
+    m_user::schema::AUDIT_ENTITY,
+];
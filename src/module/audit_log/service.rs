@@ -0,0 +1,87 @@
+use std::collections::BTreeSet;
+
+use diesel::MysqlConnection;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    dto::{enumerator::audit_action::AuditAction, response::app_error::AppError},
+    middleware::auth_middleware,
+    module::audit_log::{repository, schema::AuditLog},
+};
+
+/// Who changes made outside a request are attributed to, i.e. scheduled jobs
+/// like purge and reconcile; the same `0` entities write to `created_by`.
+pub const SYSTEM_ACTOR: i64 = 0;
+
+/// Write the audit record of one change on the connection that made it, so
+/// it commits or rolls back together with the change.
+pub fn record<T: Serialize>(
+    conn: &mut MysqlConnection,
+    entity: &str,
+    entity_id: i64,
+    action: AuditAction,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> Result<(), AppError> {
+    record_redacted(conn, entity, entity_id, action, before, after, &[])
+}
+
+/// `record` for entities with columns that are never serialized, e.g. a
+/// password hash: the `redacted` ones that changed show up as `"changed"`.
+pub fn record_redacted<T: Serialize>(
+    conn: &mut MysqlConnection,
+    entity: &str,
+    entity_id: i64,
+    action: AuditAction,
+    before: Option<&T>,
+    after: Option<&T>,
+    redacted: &[&str],
+) -> Result<(), AppError> {
+    let mut changes = diff(to_value(before)?, to_value(after)?);
+    if let Value::Object(members) = &mut changes {
+        for column in redacted {
+            members.insert(column.to_string(), json!("changed"));
+        }
+    }
+    let record = AuditLog {
+        id: 0,
+        entity: entity.to_string(),
+        entity_id,
+        action: action.to_string(),
+        actor_id: auth_middleware::current_user_id().unwrap_or(SYSTEM_ACTOR),
+        changes: changes.to_string(),
+        created_on: chrono::Utc::now().naive_utc(),
+    };
+    match repository::insert_audit_log(conn, record)? {
+        Some(_) => Ok(()),
+        None => Err(AppError::Other(format!("save audit log failed, {} {}", entity, entity_id))),
+    }
+}
+
+fn to_value<T: Serialize>(item: Option<&T>) -> Result<Value, AppError> {
+    match item {
+        Some(value) => serde_json::to_value(value)
+            .map_err(|error| AppError::Other(format!("serialize failed: {}", error))),
+        None => Ok(Value::Null),
+    }
+}
+
+/// `{column: {"before": ..., "after": ...}}` for the columns that differ, a
+/// missing side reads as `null`.
+fn diff(before: Value, after: Value) -> Value {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let mut changes = Map::new();
+    let columns: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    for column in columns {
+        let before_value = before.get(column).cloned().unwrap_or(Value::Null);
+        let after_value = after.get(column).cloned().unwrap_or(Value::Null);
+        if before_value != after_value {
+            changes.insert(column.clone(), json!({ "before": before_value, "after": after_value }));
+        }
+    }
+    Value::Object(changes)
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Json},
+    http::StatusCode,
+};
+use validator::Validate;
+
+use crate::{
+    config::environment::CONFIG,
+    dto::response::{app_error::AppError, app_response::AppResponse},
+    module::{
+        auth::schema::{LoginRequest, LoginResponse},
+        m_user::repository as m_user_repository,
+    },
+    state::AppState,
+    util::{
+        password,
+        token::{self, Claims},
+    },
+};
+
+pub async fn login(
+    Extension(_state): Extension<Arc<AppState>>,
+    Json(login_request): Json<LoginRequest>,
+) -> Result<(StatusCode, Json<AppResponse<LoginResponse>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(error) = login_request.validate() {
+        return Err(AppError::InvalidRequest(error));
+    }
+    let email = login_request.email.unwrap_or_default();
+    let plain_password = login_request.password.unwrap_or_default();
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let invalid = || AppError::Unauthorized("invalid email or password".to_string());
+    let m_user = match m_user_repository::find_response_by_email(&mut db_conn, &email)? {
+        Some(value) => value,
+        None => {
+            return Err(invalid());
+        }
+    };
+    let password_hash = m_user.user.password.clone().unwrap_or_default();
+    // argon2 is CPU bound, keep it off the async workers
+    let is_valid = tokio::task::spawn_blocking(move || password::verify(&plain_password, &password_hash))
+        .await
+        .map_err(|error| AppError::Other(format!("verify password failed: {}", error)))?;
+    if !is_valid {
+        return Err(invalid());
+    }
+    if m_user.user.is_locked == Some(true) {
+        return Err(AppError::Forbidden("user is locked".to_string()));
+    }
+
+    let expires_on = chrono::Utc::now().naive_utc() + chrono::Duration::milliseconds(CONFIG.jwt_expiration);
+    let claims = Claims {
+        sub: m_user.user.id,
+        role: m_user.role.and_then(|role| role.code),
+        exp: expires_on.and_utc().timestamp(),
+    };
+    let status_code = StatusCode::OK;
+    Ok((
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(LoginResponse {
+                token: token::issue(&claims)?,
+                expires_on,
+            }),
+            error: None,
+        }),
+    ))
+}
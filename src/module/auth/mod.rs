@@ -0,0 +1,3 @@
+pub mod schema;
+pub mod router;
+pub mod controller;
\ No newline at end of file
@@ -0,0 +1,9 @@
+use axum::{routing::post, Router};
+
+use crate::module::auth::controller::login;
+
+
+pub fn new() -> Router {
+    Router::new()
+    .route("/login", post(login))
+}
@@ -0,0 +1,21 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::util::serializer::date_serializer;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct LoginRequest {
+    #[validate(required(message = "mandatory"))]
+    pub email: Option<String>,
+    #[validate(required(message = "mandatory"))]
+    pub password: Option<String>,
+}
+
+/// Bearer token for the `Authorization` header of protected endpoints.
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    pub token: String,
+    #[serde(with = "date_serializer")]
+    pub expires_on: NaiveDateTime,
+}
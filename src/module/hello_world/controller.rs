@@ -1,21 +1,15 @@
-use std::path::PathBuf;
-
 use axum::{
     Json,
     body::Bytes,
     extract::{Multipart, Path, Query},
     http::StatusCode,
-    response::IntoResponse,
 };
 use chrono::{Datelike, Local};
-use tokio::{
-    fs::File,
-    io::{AsyncReadExt, AsyncWriteExt},
-};
+use tokio::{fs::File, io::AsyncWriteExt};
 
 use crate::{
     dto::response::{app_error::AppError, app_response::AppResponse},
-    module::hello_world::schema::{ErrorType, Params, ParamsFile, Payloads},
+    module::hello_world::schema::{ErrorType, Params, Payloads},
 };
 
 // basic handler that responds with a static string
@@ -220,51 +214,3 @@ pub async fn upload(
         }),
     ))
 }
-
-pub async fn download(param_file: Query<ParamsFile>) -> impl IntoResponse {
-    let param: ParamsFile = param_file.0;
-    let file_path = PathBuf::from(param.file_path);
-
-    let response = match File::open(&file_path).await {
-        Ok(mut file) => {
-            let mut contents = Vec::new();
-            if let Err(_) = file.read_to_end(&mut contents).await {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(AppResponse {
-                        status: StatusCode::INTERNAL_SERVER_ERROR.as_str().to_string(),
-                        message: "error".to_owned(),
-                        timestamp: chrono::Utc::now().naive_utc(),
-                        error: Some("failed to read the file".to_string()),
-                        data: None
-                    }),
-                )
-                    .into_response();
-            }
-
-            let response = axum::http::Response::builder()
-                .header(
-                    "Content-Disposition",
-                    format!("attachment; filename=\"{}\"", param.file_name),
-                )
-                .header("Content-Type", "application/octet-stream")
-                .body(contents.into())
-                .unwrap();
-
-            Ok(response)
-        }
-        Err(_) => Err((
-            StatusCode::NOT_FOUND,
-            Json(AppResponse {
-                status: StatusCode::NOT_FOUND.as_str().to_string(),
-                message: "error".to_owned(),
-                timestamp: chrono::Utc::now().naive_utc(),
-                error: Some(String::from("file not found")),
-                data: None,
-            }),
-        )
-            .into_response()),
-    };
-
-    return response.unwrap();
-}
@@ -1,6 +1,6 @@
 use axum::{routing::{get, post}, Router};
 
-use crate::module::hello_world::controller::{download, error, logger, path_param, payload, query_param, response_body, root, upload};
+use crate::module::hello_world::controller::{error, logger, path_param, payload, query_param, response_body, root, upload};
 
 pub fn new() -> Router {
     Router::new()
@@ -12,5 +12,4 @@ pub fn new() -> Router {
     .route("/logger", get(logger))
     .route("/error", get(error))
     .route("/file/upload", post(upload))
-    .route("/file/download", get(download))
 }
\ No newline at end of file
@@ -17,9 +17,3 @@ pub struct Payloads {
 pub struct ErrorType {
     pub(crate) code: i32, // 200, 400, 404, 500
 }
-
-#[derive(Deserialize)]
-pub struct ParamsFile {
-    pub(crate) file_path: String,
-    pub(crate) file_name: String
-}
\ No newline at end of file
@@ -1,34 +1,54 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Extension, Json, Path, Query},
-    http::StatusCode,
+    body::Body,
+    extract::{Extension, Json, Multipart, Path, Query},
+    http::{header::{ETAG, LOCATION}, HeaderMap, HeaderName, Response, StatusCode},
+    response::IntoResponse,
 };
-use validator::Validate;
+use diesel::MysqlConnection;
+use serde_json::Value;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 use crate::{
+    config::environment::CONFIG,
     dto::{
+        enumerator::{export_format::ExportFormat, file_type::FileType},
         request::{
-            filter_request::Filters, pagination_request::Pagination, search_request::Search,
-            sort_request::Sorts,
+            aggregate_request::Aggregation, bulk_request::{BulkDeleteItem, BulkOptions}, deleted_request::IncludeDeleted, export_request::Export, fields_request::Fields, import_request::Import, filter_request::Filters, pagination_request::Pagination, search_request::{FullTextSearch, Search},
+            signed_url_request::SignedUrlRequest, sort_request::Sorts,
         },
         response::{
-            app_error::AppError, app_response::AppResponse, pagination_response::PaginatedResponse,
+            aggregate_response::AggregateResponse, app_error::AppError, app_response::AppResponse,
+            bulk_response::BulkItemResult,
+            import_response::ImportReport,
+            pagination_response::PaginatedResponse,
+            search_response::SearchHit,
         },
     },
-    module::m_biodata::{
-        repository,
-        schema::{MBiodata, MBiodataRequest},
+    module::{
+        m_biodata::{
+            import, repository,
+            schema::{MBiodata, MBiodataRequest, EXPORT_COLUMNS, COLUMNS, SEARCH_COLUMNS},
+            service,
+        },
+        m_file::{repository as m_file_repository, schema::MFileSignedUrl, service as m_file_service},
     },
     state::AppState,
+    util::{bulk, etag, export, merge_patch, projection, search as full_text, signed_url, unit_of_work},
 };
 
 pub async fn find_by_id(
     Path(biodata_id): Path<i64>,
+    Query(_deleted): Query<IncludeDeleted>,
+    Query(_fields): Query<Fields>,
     Extension(_state): Extension<Arc<AppState>>,
-) -> Result<(StatusCode, Json<AppResponse<MBiodata>>), AppError> {
+    headers: HeaderMap,
+) -> Result<Response<Body>, AppError> {
     log::info!("status: {}", _state.status);
 
+    let fields = projection::resolve(_fields._fields, &COLUMNS, false)?;
+
     // get db connection
     let db_conn_result = _state.diesel_pool_mysql.get();
     let mut db_conn;
@@ -44,20 +64,32 @@ pub async fn find_by_id(
         }
     };
 
-    let result = repository::find_by_id(&mut db_conn, biodata_id);
+    let result = repository::find_query_by_id(
+        &mut db_conn,
+        biodata_id,
+        _deleted.include_deleted.unwrap_or(false),
+        &projection::select_list(&fields, &COLUMNS),
+    );
     match result {
         Ok(Some(value)) => {
+            let etag = etag::of(value.id, value.row_version);
+            if etag::is_not_modified(&headers, &etag) {
+                return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
+            }
+
             let status_code = StatusCode::OK;
             return Ok((
                 status_code,
+                [(ETAG, etag)],
                 Json(AppResponse {
                     status: status_code.as_str().to_string(),
                     message: "success".to_owned(),
                     timestamp: chrono::Utc::now().naive_utc(),
-                    data: Some(value),
+                    data: Some(projection::to_value(&value, &fields)?),
                     error: None,
                 }),
-            ));
+            )
+                .into_response());
         }
         Ok(None) => {
             return Err(AppError::NotFound);
@@ -70,9 +102,13 @@ pub async fn find_by_id(
 
 pub async fn find_all(
     Extension(_state): Extension<Arc<AppState>>,
-) -> Result<(StatusCode, Json<AppResponse<Vec<MBiodata>>>), AppError> {
+    Query(_deleted): Query<IncludeDeleted>,
+    Query(_fields): Query<Fields>,
+) -> Result<(StatusCode, Json<AppResponse<Vec<Value>>>), AppError> {
     log::info!("status: {}", _state.status);
 
+    let fields = projection::resolve(_fields._fields, &COLUMNS, true)?;
+
     // get db connection
     let db_conn_result = _state.diesel_pool_mysql.get();
     let mut db_conn;
@@ -85,7 +121,11 @@ pub async fn find_all(
         }
     };
 
-    let result = repository::find_all(&mut db_conn);
+    let result = repository::find_all(
+        &mut db_conn,
+        _deleted.include_deleted.unwrap_or(false),
+        &projection::select_list(&fields, &COLUMNS),
+    );
     match result {
         Ok(value) => {
             let status_code = StatusCode::OK;
@@ -95,7 +135,7 @@ pub async fn find_all(
                     status: status_code.as_str().to_string(),
                     message: "success".to_owned(),
                     timestamp: chrono::Utc::now().naive_utc(),
-                    data: Some(value),
+                    data: Some(projection::to_values(&value, &fields)?),
                     error: None,
                 }),
             ));
@@ -109,7 +149,8 @@ pub async fn find_all(
 pub async fn delete_by_id(
     Path(biodata_id): Path<i64>,
     Extension(_state): Extension<Arc<AppState>>,
-) -> Result<(StatusCode, Json<AppResponse<String>>), AppError> {
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<AppResponse<MBiodata>>), AppError> {
     log::info!("status: {}", _state.status);
 
     // get db connection
@@ -120,16 +161,28 @@ pub async fn delete_by_id(
             db_conn = value;
         }
         Err(error) => {
-            return Err(AppError::Other(format!(
-                "get connection failed {error}, id: {biodata_id}"
-            ))
-            .into());
+            return Err(AppError::Other(format!("get connection failed {error}, id: {biodata_id}")));
         }
     };
 
-    let result = repository::delete_by_id(&mut db_conn, biodata_id);
+    // check and delete under the row lock
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        let existing = match repository::lock_by_id(conn, biodata_id)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        etag::check_if_match(&headers, &etag::of(existing.id, existing.row_version))?;
+
+        if repository::soft_delete_by_id(conn, biodata_id)?.is_none() {
+            return Err(AppError::NotFound);
+        }
+        // the soft deleted row, still reachable with include_deleted
+        repository::find_by_id(conn, biodata_id, true)
+    });
     match result {
-        Ok(Some(_)) => {
+        Ok(tombstone) => {
             let status_code = StatusCode::OK;
             return Ok((
                 status_code,
@@ -137,11 +190,92 @@ pub async fn delete_by_id(
                     status: status_code.as_str().to_string(),
                     message: "success".to_owned(),
                     timestamp: chrono::Utc::now().naive_utc(),
-                    data: None,
+                    data: tombstone,
                     error: None,
                 }),
             ));
         }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+pub async fn restore(
+    Path(biodata_id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MBiodata>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {biodata_id}")));
+        }
+    };
+
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        if repository::restore_by_id(conn, biodata_id)?.is_none() {
+            return Err(AppError::NotFound);
+        }
+        repository::find_by_id(conn, biodata_id, false)
+    });
+    match result {
+        Ok(restored) => {
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: restored,
+                    error: None,
+                }),
+            ));
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+/// Permanently delete the biodata together with its image file.
+pub async fn purge(
+    Path(biodata_id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<StatusCode, AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {biodata_id}")));
+        }
+    };
+
+    let existing_biodata = match repository::find_by_id(&mut db_conn, biodata_id, true)? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+
+    let result = service::purge_biodata(&_state, &mut db_conn, &existing_biodata).await;
+    match result {
+        Ok(Some(_)) => {
+            return Ok(StatusCode::NO_CONTENT);
+        }
         Ok(None) => {
             return Err(AppError::NotFound);
         }
@@ -154,7 +288,7 @@ pub async fn delete_by_id(
 pub async fn create(
     Extension(_state): Extension<Arc<AppState>>,
     Json(m_biodata_request): Json<MBiodataRequest>,
-) -> Result<(StatusCode, Json<AppResponse<String>>), AppError> {
+) -> Result<(StatusCode, [(HeaderName, String); 2], Json<AppResponse<MBiodata>>), AppError> {
     log::info!("status: {}", _state.status);
 
     let _is_valid = match m_biodata_request.validate() {
@@ -176,30 +310,24 @@ pub async fn create(
         }
     };
 
-    let new_m_biodata = MBiodata::from_create_request(m_biodata_request);
-    let existing_biodata_result = repository::find_by_id(&mut db_conn, new_m_biodata.id);
-    match existing_biodata_result {
-        Ok(Some(_)) => {
-            return Err(AppError::DataExist);
-        }
-        Ok(None) => {}
-        Err(err) => {
-            return Err(err);
-        }
-    };
-
-    let result = repository::insert_biodata(&mut db_conn, new_m_biodata);
+    let mut new_m_biodata = MBiodata::from_create_request(m_biodata_request)?;
+    let result = repository::insert_biodata(&mut db_conn, new_m_biodata.clone());
 
     match result {
-        Ok(Some(_)) => {
-            let status_code = StatusCode::OK;
+        Ok(Some(new_id)) => {
+            new_m_biodata.id = new_id;
+            let status_code = StatusCode::CREATED;
             return Ok((
                 status_code,
+                [
+                    (LOCATION, format!("/m-biodata/{}", new_m_biodata.id)),
+                    (ETAG, etag::of(new_m_biodata.id, new_m_biodata.row_version)),
+                ],
                 Json(AppResponse {
                     status: status_code.as_str().to_string(),
                     message: "success".to_owned(),
                     timestamp: chrono::Utc::now().naive_utc(),
-                    data: None,
+                    data: Some(new_m_biodata),
                     error: None,
                 }),
             ));
@@ -215,8 +343,9 @@ pub async fn create(
 
 pub async fn update(
     Extension(_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
     Json(m_biodata_request): Json<MBiodataRequest>,
-) -> Result<(StatusCode, Json<AppResponse<String>>), AppError> {
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<AppResponse<MBiodata>>), AppError> {
     log::info!("status: {}", _state.status);
 
     let _is_valid = match m_biodata_request.validate() {
@@ -238,39 +367,497 @@ pub async fn update(
         }
     };
 
-    let mut _new_m_biodata = <MBiodata>::new(String::new(), String::new());
-    let existing_biodata_result =
-        repository::find_by_id(&mut db_conn, m_biodata_request.id.unwrap());
-    match existing_biodata_result {
-        Ok(None) => {
-            return Err(AppError::NotFound);
+    // read, check and write under the row lock
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        let existing = match repository::lock_by_id(conn, m_biodata_request.require_id()?)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        etag::check_if_match(&headers, &etag::of(existing.id, existing.row_version))?;
+
+        let new_value = <MBiodata>::from_update_request(m_biodata_request, existing);
+        match repository::update_biodata(conn, new_value.clone())? {
+            Some(_) => Ok(new_value),
+            None => Err(AppError::PreconditionFailed("resource was modified".to_string())),
         }
-        Ok(Some(value)) => {
-            _new_m_biodata = <MBiodata>::from_update_request(m_biodata_request, value);
+    });
+
+    match result {
+        Ok(mut _new_m_biodata) => {
+            _new_m_biodata.row_version += 1;
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                [(ETAG, etag::of(_new_m_biodata.id, _new_m_biodata.row_version))],
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(_new_m_biodata),
+                    error: None,
+                }),
+            ));
         }
         Err(err) => {
             return Err(err);
         }
+    }
+}
+
+/// Create many biodata rows, see `BulkMode` for how a failing item is handled.
+pub async fn bulk_create(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_options): Query<BulkOptions>,
+    Json(m_biodata_requests): Json<Vec<MBiodataRequest>>,
+) -> Result<(StatusCode, Json<AppResponse<Vec<BulkItemResult>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let (results, committed) = bulk::run(
+        &mut db_conn,
+        _options.mode.unwrap_or_default(),
+        m_biodata_requests,
+        StatusCode::CREATED,
+        service::create_biodata,
+    );
+    Ok(bulk::into_response(results, committed))
+}
+
+/// Update many biodata rows, the id of every item comes from its body.
+pub async fn bulk_update(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_options): Query<BulkOptions>,
+    Json(m_biodata_requests): Json<Vec<MBiodataRequest>>,
+) -> Result<(StatusCode, Json<AppResponse<Vec<BulkItemResult>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let (results, committed) = bulk::run(
+        &mut db_conn,
+        _options.mode.unwrap_or_default(),
+        m_biodata_requests,
+        StatusCode::OK,
+        service::update_biodata,
+    );
+    Ok(bulk::into_response(results, committed))
+}
+
+/// Soft delete many biodata rows by id.
+pub async fn bulk_delete(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_options): Query<BulkOptions>,
+    Json(delete_items): Json<Vec<BulkDeleteItem>>,
+) -> Result<(StatusCode, Json<AppResponse<Vec<BulkItemResult>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let (results, committed) = bulk::run(
+        &mut db_conn,
+        _options.mode.unwrap_or_default(),
+        delete_items,
+        StatusCode::OK,
+        service::soft_delete_biodata,
+    );
+    Ok(bulk::into_response(results, committed))
+}
+
+/// Partial update with an RFC 7386 merge patch. Only the supplied members
+/// change, validation runs on the merged result.
+pub async fn patch(
+    Path(biodata_id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<AppResponse<MBiodata>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if !patch.is_object() {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "body",
+            ValidationError::new("object").with_message("must be a JSON object".into()),
+        );
+        return Err(AppError::InvalidRequest(errors));
+    }
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {biodata_id}")));
+        }
     };
 
-    let result = repository::update_biodata(&mut db_conn, _new_m_biodata);
+    // read, merge, check and write under the row lock
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        let existing = match repository::lock_by_id(conn, biodata_id)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        etag::check_if_match(&headers, &etag::of(existing.id, existing.row_version))?;
+
+        // merge onto the stored row, the id always comes from the path
+        let mut document = serde_json::to_value(MBiodataRequest::from_existing(&existing))
+            .map_err(|error| AppError::Other(format!("serialize failed: {}, id: {}", error, biodata_id)))?;
+        merge_patch::apply(&mut document, &patch);
+        document["id"] = serde_json::json!(biodata_id);
+        let m_biodata_request: MBiodataRequest = match serde_json::from_value(document) {
+            Ok(value) => value,
+            Err(error) => {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "body",
+                    ValidationError::new("type").with_message(error.to_string().into()),
+                );
+                return Err(AppError::InvalidRequest(errors));
+            }
+        };
+        if let Err(error) = m_biodata_request.validate() {
+            return Err(AppError::InvalidRequest(error));
+        }
 
+        let new_value = <MBiodata>::from_update_request(m_biodata_request, existing);
+        match repository::update_biodata(conn, new_value.clone())? {
+            Some(_) => Ok(new_value),
+            None => Err(AppError::PreconditionFailed("resource was modified".to_string())),
+        }
+    });
     match result {
-        Ok(Some(_)) => {
+        Ok(mut new_m_biodata) => {
+            new_m_biodata.row_version += 1;
             let status_code = StatusCode::OK;
             return Ok((
                 status_code,
+                [(ETAG, etag::of(new_m_biodata.id, new_m_biodata.row_version))],
                 Json(AppResponse {
                     status: status_code.as_str().to_string(),
                     message: "success".to_owned(),
                     timestamp: chrono::Utc::now().naive_utc(),
-                    data: None,
+                    data: Some(new_m_biodata),
                     error: None,
                 }),
             ));
         }
-        Ok(None) => {
-            return Err(AppError::Other(format!("save data failed")).into());
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+/// Every row matching `_filter`, `_sort` and `_q` of `find_page`, as CSV
+/// streamed page by page or as an XLSX workbook.
+pub async fn export(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_sort): Query<Sorts>,
+    Query(_filter): Query<Filters>,
+    Query(_global_search): Query<Search>,
+    Query(_deleted): Query<IncludeDeleted>,
+    Query(_export): Query<Export>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(err) = _filter.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+    if let Err(err) = _sort.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+
+    let format = export::resolve_format(_export.format, &headers);
+    let columns = export::resolve_columns(_export._columns, EXPORT_COLUMNS)?;
+    let _filters = _filter._filter.unwrap_or_default();
+    let _sorts = export::stable_sorts(_sort._sort.unwrap_or_default());
+    let _q = _global_search._q.unwrap_or_default();
+    let include_deleted = _deleted.include_deleted.unwrap_or(false);
+    log::info!(
+        "format {:?}, columns {:?}, filters {:?}, sorts {:?}, global_search {:?}",
+        format,
+        columns,
+        _filters,
+        _sorts,
+        _q
+    );
+
+    // get db connection
+    let db_conn = match _state.diesel_pool_mysql.get() {
+        Ok(value) => value,
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let select = projection::select_list(&columns, &COLUMNS);
+    let fetch_page = move |conn: &mut MysqlConnection, page: i64| {
+        let (content, _) = repository::pagination(
+            conn,
+            page,
+            export::PAGE_SIZE,
+            _filters.clone(),
+            _sorts.clone(),
+            _q.clone(),
+            include_deleted,
+            &select,
+        )?;
+        export::to_rows(content)
+    };
+    match format {
+        ExportFormat::Csv => {
+            let body = export::stream_csv(db_conn, columns, fetch_page);
+            Ok(export::response(format, "m-biodata", Body::from_stream(body)))
+        }
+        ExportFormat::Xlsx => {
+            let body = export::stream_xlsx(db_conn, columns, fetch_page).await?;
+            Ok(export::response(format, "m-biodata", Body::from_stream(body)))
+        }
+    }
+}
+
+/// Import biodata from a CSV upload, see `import::IMPORT_COLUMNS`. Responds
+/// with a summary, or with the rejected rows as a file when `report` is set.
+pub async fn import(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_import): Query<Import>,
+    mut multipart: Multipart,
+) -> Result<Response<Body>, AppError> {
+    log::info!("status: {}", _state.status);
+
+    let uploaded_file = match m_file_service::read_upload(&mut multipart).await? {
+        Some(value) => value,
+        None => {
+            let mut errors = ValidationErrors::new();
+            errors.add("file", ValidationError::new("required").with_message("mandatory".into()));
+            return Err(AppError::InvalidRequest(errors));
+        }
+    };
+    let dry_run = _import.dry_run.unwrap_or(false);
+    let (rows, mut rejected_rows) = import::parse(&uploaded_file.data)?;
+    let total = rows.len() + rejected_rows.len();
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let (inserted, updated) = import::run(&mut db_conn, rows, dry_run, &mut rejected_rows)?;
+    rejected_rows.sort_by_key(|rejected_row| rejected_row.row);
+    log::info!(
+        "import: dry_run {}, total {}, inserted {}, updated {}, rejected {}",
+        dry_run,
+        total,
+        inserted,
+        updated,
+        rejected_rows.len()
+    );
+
+    if let Some(format) = _import.report {
+        let buffer = import::report_file(format, &rejected_rows)?;
+        return Ok(export::response(format, "m-biodata-rejected", Body::from(buffer)));
+    }
+
+    let status_code = StatusCode::OK;
+    Ok((
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(ImportReport {
+                dry_run,
+                total,
+                inserted,
+                updated,
+                rejected: rejected_rows.len(),
+                rejected_rows,
+            }),
+            error: None,
+        }),
+    )
+        .into_response())
+}
+
+/// Grouped counts and aggregates over the rows matching `_filter`, e.g.
+/// `_group_by=[{"id":"created_on","bucket":"DAY"}]`.
+pub async fn aggregate(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_aggregation): Query<Aggregation>,
+    Query(_filter): Query<Filters>,
+    Query(_deleted): Query<IncludeDeleted>,
+) -> Result<(StatusCode, Json<AppResponse<AggregateResponse>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(err) = _filter.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+    let _filters = _filter._filter.unwrap_or_default();
+    log::info!("aggregation {:?}, filters {:?}", _aggregation, _filters);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let result = repository::aggregate(
+        &mut db_conn,
+        _aggregation,
+        _filters,
+        _deleted.include_deleted.unwrap_or(false),
+    );
+    match result {
+        Ok(value) => {
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(value),
+                    error: None,
+                }),
+            ));
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+/// Full-text search over `SEARCH_COLUMNS`, ranked by relevance with the
+/// matching words highlighted.
+pub async fn search(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_search): Query<FullTextSearch>,
+    Query(_pagination): Query<Pagination>,
+    Query(_deleted): Query<IncludeDeleted>,
+    Query(_fields): Query<Fields>,
+) -> Result<(StatusCode, Json<AppResponse<PaginatedResponse<SearchHit>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(err) = _pagination.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+    let terms = full_text::terms(_search._q)?;
+    let mode = _search.mode.unwrap_or_default();
+    let fields = projection::resolve(_fields._fields, &COLUMNS, true)?;
+    // scoring and highlighting need the search columns whatever `_fields` asks for
+    let select = projection::select_list(&projection::with_required(&fields, SEARCH_COLUMNS), &COLUMNS);
+    let include_deleted = _deleted.include_deleted.unwrap_or(false);
+
+    let mut _page = _pagination.page.unwrap_or(0);
+    if _page < 0 {
+        _page = 0;
+    }
+    let mut _size = _pagination.size.unwrap_or(5);
+    if _size < 1 {
+        _size = 1;
+    }
+    log::info!("page {:?}, size {:?}, terms {:?}, mode {:?}", _page, _size, terms, mode);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let result = full_text::page(
+        &mut db_conn,
+        mode,
+        _page,
+        _size,
+        &terms,
+        SEARCH_COLUMNS,
+        |conn: &mut MysqlConnection, limit, offset| {
+            repository::search(conn, &terms, mode, include_deleted, &select, limit, offset)
+        },
+    );
+    match result {
+        Ok(value) => {
+            let mut total_of_pages = value.1 / _size;
+            if value.1 % _size != 0 {
+                total_of_pages = total_of_pages + 1;
+            }
+
+            let mut content = Vec::new();
+            for (item, score) in &value.0 {
+                content.push(full_text::hit(item, *score, &fields, SEARCH_COLUMNS, &terms, mode)?);
+            }
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(PaginatedResponse {
+                        content,
+                        total_of_elements: value.1,
+                        total_of_pages: total_of_pages,
+                    }),
+                    error: None,
+                }),
+            ));
         }
         Err(err) => {
             return Err(err);
@@ -284,7 +871,9 @@ pub async fn find_page(
     Query(_sort): Query<Sorts>,
     Query(_filter): Query<Filters>,
     Query(_global_search): Query<Search>,
-) -> Result<(StatusCode, Json<AppResponse<PaginatedResponse<MBiodata>>>), AppError> {
+    Query(_deleted): Query<IncludeDeleted>,
+    Query(_fields): Query<Fields>,
+) -> Result<(StatusCode, Json<AppResponse<PaginatedResponse<Value>>>), AppError> {
     log::info!("status: {}", _state.status);
 
     if let Err(err) = _pagination.validate() {
@@ -297,6 +886,8 @@ pub async fn find_page(
         return Err(AppError::InvalidRequest(err).into());
     };
 
+    let fields = projection::resolve(_fields._fields, &COLUMNS, true)?;
+
     let mut _page = _pagination.page.unwrap_or(0);
     if _page < 0 {
         _page = 0;
@@ -329,7 +920,16 @@ pub async fn find_page(
         }
     };
 
-    let result = repository::pagination(&mut db_conn, _page, _size, _filters, _sorts, _q);
+    let result = repository::pagination(
+        &mut db_conn,
+        _page,
+        _size,
+        _filters,
+        _sorts,
+        _q,
+        _deleted.include_deleted.unwrap_or(false),
+        &projection::select_list(&fields, &COLUMNS),
+    );
     match result {
         Ok(value) => {
             let mut total_of_pages = value.1 / _size;
@@ -339,7 +939,7 @@ pub async fn find_page(
 
             let status_code = StatusCode::OK;
             let paginated_response = PaginatedResponse {
-                content: value.0,
+                content: projection::to_values(&value.0, &fields)?,
                 total_of_elements: value.1,
                 total_of_pages: total_of_pages,
             };
@@ -359,3 +959,220 @@ pub async fn find_page(
         }
     }
 }
+
+pub async fn upload_image(
+    Path(biodata_id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<AppResponse<MBiodata>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    let uploaded_file = match m_file_service::read_upload(&mut multipart).await? {
+        Some(value) => value,
+        None => {
+            let mut errors = ValidationErrors::new();
+            errors.add("file", ValidationError::new("required").with_message("mandatory".into()));
+            return Err(AppError::InvalidRequest(errors));
+        }
+    };
+    let file_type = FileType::from_content_type(&uploaded_file.content_type);
+    if file_type != FileType::IMAGE {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "file",
+            ValidationError::new("image").with_message("must be an image".into()),
+        );
+        return Err(AppError::InvalidRequest(errors));
+    }
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!(
+                "get connection failed {error}, id: {biodata_id}"
+            )));
+        }
+    };
+
+    if repository::find_by_id(&mut db_conn, biodata_id, false)?.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let new_m_file = m_file_service::create_file(
+        &_state,
+        &mut db_conn,
+        uploaded_file.file_name,
+        file_type.to_string(),
+        &uploaded_file.file_hash,
+        uploaded_file.data,
+    )
+    .await?;
+
+    // the row stays locked while the image is swapped, so two uploads cannot
+    // both take the same old image for the one they replace
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        let existing_biodata = match repository::lock_by_id(conn, biodata_id)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        match repository::update_image(conn, biodata_id, Some(new_m_file.id), new_m_file.file_path.clone())? {
+            Some(_) => Ok(existing_biodata.image_file_id),
+            None => Err(AppError::Other(format!("save data failed"))),
+        }
+    });
+    let old_file_id = match result {
+        Ok(value) => value,
+        Err(error) => {
+            let _ = m_file_service::purge_file(&_state, &mut db_conn, &new_m_file).await;
+            return Err(error);
+        }
+    };
+
+    // the replaced image is no longer referenced once the swap is committed
+    if let Some(old_file_id) = old_file_id
+        && let Some(old_m_file) = m_file_repository::find_by_id(&mut db_conn, old_file_id, false)?
+    {
+        m_file_service::purge_file(&_state, &mut db_conn, &old_m_file).await?;
+    }
+
+    let updated_biodata = repository::find_by_id(&mut db_conn, biodata_id, false)?;
+    let status_code = StatusCode::OK;
+    Ok((
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: updated_biodata,
+            error: None,
+        }),
+    ))
+}
+
+pub async fn image_signed_url(
+    Path(biodata_id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MFileSignedUrl>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!(
+                "get connection failed {error}, id: {biodata_id}"
+            )));
+        }
+    };
+
+    let image_file_id = match repository::find_by_id(&mut db_conn, biodata_id, false)? {
+        Some(MBiodata {
+            image_file_id: Some(value),
+            ..
+        }) => value,
+        _ => {
+            return Err(AppError::NotFound);
+        }
+    };
+    match m_file_repository::find_by_id(&mut db_conn, image_file_id, false)? {
+        Some(value) => m_file_service::ensure_available(&value)?,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    }
+
+    let expires_on =
+        chrono::Utc::now().naive_utc() + chrono::Duration::seconds(CONFIG.storage_url_expiration);
+    let expires = expires_on.and_utc().timestamp();
+    let signature = signed_url::sign(&format!("m_biodata/{}/image", biodata_id), expires);
+
+    let status_code = StatusCode::OK;
+    Ok((
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(MFileSignedUrl {
+                url: format!(
+                    "/m-biodata/{}/image?expires={}&signature={}",
+                    biodata_id, expires, signature
+                ),
+                expires_on,
+            }),
+            error: None,
+        }),
+    ))
+}
+
+pub async fn find_image(
+    Path(biodata_id): Path<i64>,
+    Query(request): Query<SignedUrlRequest>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<Response<Body>, AppError> {
+    log::info!("status: {}", _state.status);
+
+    signed_url::ensure_valid(&format!("m_biodata/{}/image", biodata_id), request.expires, &request.signature)?;
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!(
+                "get connection failed {error}, id: {biodata_id}"
+            )));
+        }
+    };
+
+    let image_file_id = match repository::find_by_id(&mut db_conn, biodata_id, false)? {
+        Some(MBiodata {
+            image_file_id: Some(value),
+            ..
+        }) => value,
+        _ => {
+            return Err(AppError::NotFound);
+        }
+    };
+    let m_file = match m_file_repository::find_by_id(&mut db_conn, image_file_id, false)? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+    m_file_service::ensure_available(&m_file)?;
+
+    let file_name = m_file.file_name.unwrap_or_default();
+    match _state
+        .storage
+        .get(&m_file.file_path.unwrap_or_default())
+        .await?
+    {
+        Some(contents) => Ok(Response::builder()
+            .header(
+                "Content-Type",
+                m_file_service::content_type_from_name(&file_name),
+            )
+            .header(
+                "Content-Disposition",
+                format!("inline; filename=\"{}\"", file_name),
+            )
+            .body(contents.into())
+            .unwrap()),
+        None => Err(AppError::NotFound),
+    }
+}
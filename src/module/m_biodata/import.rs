@@ -0,0 +1,300 @@
+use std::collections::BTreeMap;
+
+use diesel::{Connection, MysqlConnection};
+use serde_json::{json, Map, Value};
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::{
+    dto::{
+        enumerator::export_format::ExportFormat,
+        response::{app_error::AppError, import_response::ImportRejectedRow},
+    },
+    module::m_biodata::{
+        repository,
+        schema::{MBiodata, MBiodataRequest},
+        service,
+    },
+    util::{export, merge_patch},
+};
+
+/// CSV columns mapped onto `MBiodataRequest`, rows with an `id` update that
+/// row: the supplied cells are merged onto it, omitted columns and empty cells
+/// keep their stored value.
+pub const IMPORT_COLUMNS: &[&str] = &["id", "fullname", "mobile_phone", "image_path", "is_delete"];
+/// rows written per transaction
+const BATCH_SIZE: usize = 500;
+
+/// A row that passed validation, `row` is the line in the uploaded file.
+/// `document` holds the supplied cells, an update merges it onto the stored
+/// row and is validated then.
+pub struct ImportRow {
+    pub row: u64,
+    pub values: BTreeMap<String, String>,
+    pub document: Map<String, Value>,
+    pub request: MBiodataRequest,
+}
+
+/// Map every record onto `MBiodataRequest` and run its `validator` rules.
+/// Returns the valid rows and the rejected ones.
+pub fn parse(data: &[u8]) -> Result<(Vec<ImportRow>, Vec<ImportRejectedRow>), AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(data);
+    let headers = reader
+        .headers()
+        .map_err(|error| invalid_file(format!("unreadable csv: {}", error)))?
+        .clone();
+    if let Some(unknown) = headers.iter().find(|header| !IMPORT_COLUMNS.contains(header)) {
+        return Err(invalid_file(format!(
+            "unknown column {}, allowed: {}",
+            unknown,
+            IMPORT_COLUMNS.join(",")
+        )));
+    }
+
+    let mut rows = Vec::new();
+    let mut rejected_rows = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(value) => value,
+            Err(error) => {
+                let row = error.position().map(|position| position.line()).unwrap_or_default();
+                rejected_rows.push(ImportRejectedRow {
+                    row,
+                    values: BTreeMap::new(),
+                    errors: json!(error.to_string()),
+                });
+                continue;
+            }
+        };
+        let row = record.position().map(|position| position.line()).unwrap_or_default();
+        let values: BTreeMap<String, String> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(header, value)| (header.to_string(), value.to_string()))
+            .collect();
+
+        match to_request(&values) {
+            Ok((document, request)) => rows.push(ImportRow {
+                row,
+                values,
+                document,
+                request,
+            }),
+            Err(error) => rejected_rows.push(ImportRejectedRow {
+                row,
+                values,
+                errors: error.status_and_error().1,
+            }),
+        }
+    }
+    Ok((rows, rejected_rows))
+}
+
+fn to_request(values: &BTreeMap<String, String>) -> Result<(Map<String, Value>, MBiodataRequest), AppError> {
+    let mut type_errors: Vec<(&'static str, ValidationError)> = Vec::new();
+    let mut document = Map::new();
+    for (column, value) in values {
+        // empty cells are missing values
+        if value.is_empty() {
+            continue;
+        }
+        let field = match column.as_str() {
+            "id" => match value.parse::<i64>() {
+                Ok(number) => json!(number),
+                Err(_) => {
+                    type_errors.push(("id", ValidationError::new("type").with_message("must be a number".into())));
+                    continue;
+                }
+            },
+            "is_delete" => match value.to_lowercase().as_str() {
+                "true" | "1" => json!(true),
+                "false" | "0" => json!(false),
+                _ => {
+                    type_errors.push((
+                        "is_delete",
+                        ValidationError::new("type").with_message("must be true or false".into()),
+                    ));
+                    continue;
+                }
+            },
+            _ => json!(value),
+        };
+        document.insert(column.clone(), field);
+    }
+
+    let request: MBiodataRequest = serde_json::from_value(Value::Object(document.clone()))
+        .map_err(|error| AppError::Other(format!("map row failed: {}", error)))?;
+    // rows with an id are validated once it is known whether they update a
+    // stored row, merged onto it, or insert a new one
+    let mut errors = match request.id {
+        Some(_) => ValidationErrors::new(),
+        None => request.validate().err().unwrap_or_default(),
+    };
+    for (field, error) in type_errors {
+        errors.add(field, error);
+    }
+    if !errors.is_empty() {
+        return Err(AppError::InvalidRequest(errors));
+    }
+    Ok((document, request))
+}
+
+/// The stored row with the supplied cells merged on top, validated.
+fn merge_onto(existing: &MBiodata, document: &Map<String, Value>) -> Result<MBiodataRequest, AppError> {
+    let mut merged = serde_json::to_value(MBiodataRequest::from_existing(existing))
+        .map_err(|error| AppError::Other(format!("serialize failed: {}, id: {}", error, existing.id)))?;
+    merge_patch::apply(&mut merged, &Value::Object(document.clone()));
+    let request: MBiodataRequest = serde_json::from_value(merged)
+        .map_err(|error| AppError::Other(format!("map row failed: {}", error)))?;
+    request.validate().map_err(AppError::InvalidRequest)?;
+    Ok(request)
+}
+
+/// Write one row, returns whether it updated an existing one.
+fn write_row(conn: &mut MysqlConnection, row: &ImportRow) -> Result<bool, AppError> {
+    let biodata_id = match row.request.id {
+        Some(value) => value,
+        None => {
+            service::create_biodata(conn, row.request.clone())?;
+            return Ok(false);
+        }
+    };
+
+    // an import overwrites whatever version it finds and inserts the rows it
+    // does not find under their own id, importing the same file again updates them
+    let existing = match repository::lock_by_id(conn, biodata_id)? {
+        Some(value) => value,
+        None => {
+            row.request.validate().map_err(AppError::InvalidRequest)?;
+            let mut new_m_biodata = MBiodata::from_create_request(row.request.clone())?;
+            new_m_biodata.id = biodata_id;
+            return match repository::insert_biodata(conn, new_m_biodata)? {
+                Some(_) => Ok(false),
+                None => Err(AppError::Other("save data failed".to_string())),
+            };
+        }
+    };
+    let request = merge_onto(&existing, &row.document)?;
+    match repository::update_biodata(conn, MBiodata::from_update_request(request, existing))? {
+        Some(_) => Ok(true),
+        None => Err(AppError::PreconditionFailed("resource was modified".to_string())),
+    }
+}
+
+fn invalid_file(message: String) -> AppError {
+    let mut errors = ValidationErrors::new();
+    errors.add("file", ValidationError::new("csv").with_message(message.into()));
+    AppError::InvalidRequest(errors)
+}
+
+/// Upsert the valid rows, one transaction per batch and a savepoint per row:
+/// a row the database refuses is added to `rejected_rows` without undoing the
+/// rest of its batch. A dry run only validates the rows, the ones updating a
+/// stored row once merged onto it. Returns the number of inserted and updated rows.
+pub fn run(
+    conn: &mut MysqlConnection,
+    mut rows: Vec<ImportRow>,
+    dry_run: bool,
+    rejected_rows: &mut Vec<ImportRejectedRow>,
+) -> Result<(usize, usize), AppError> {
+    let mut inserted = 0;
+    let mut updated = 0;
+
+    if dry_run {
+        for row in rows {
+            match row.request.id {
+                Some(biodata_id) => {
+                    let checked = match repository::find_by_id(conn, biodata_id, false)? {
+                        Some(existing) => merge_onto(&existing, &row.document).map(|_| true),
+                        None => row.request.validate().map(|_| false).map_err(AppError::InvalidRequest),
+                    };
+                    match checked {
+                        Ok(true) => updated += 1,
+                        Ok(false) => inserted += 1,
+                        Err(error) => rejected_rows.push(to_rejected(&row, &error)),
+                    }
+                }
+                None => inserted += 1,
+            }
+        }
+        return Ok((inserted, updated));
+    }
+
+    while !rows.is_empty() {
+        let batch: Vec<ImportRow> = rows.drain(..rows.len().min(BATCH_SIZE)).collect();
+        let mut batch_inserted = 0;
+        let mut batch_updated = 0;
+        let mut batch_rejected = Vec::new();
+
+        let outcome = conn.transaction::<(), AppError, _>(|conn| {
+            for row in &batch {
+                match conn.transaction::<bool, AppError, _>(|conn| write_row(conn, row)) {
+                    Ok(true) => batch_updated += 1,
+                    Ok(false) => batch_inserted += 1,
+                    Err(error) => {
+                        if let AppError::Other(_) | AppError::InternalServerError = error {
+                            log::error!("import: row {} failed: {:?}", row.row, error);
+                        }
+                        batch_rejected.push(to_rejected(row, &error));
+                    }
+                }
+            }
+            Ok(())
+        });
+        if let Err(error) = outcome {
+            return Err(AppError::Other(format!("import failed: {:?}", error)));
+        }
+        inserted += batch_inserted;
+        updated += batch_updated;
+        rejected_rows.extend(batch_rejected);
+    }
+    Ok((inserted, updated))
+}
+
+fn to_rejected(row: &ImportRow, error: &AppError) -> ImportRejectedRow {
+    ImportRejectedRow {
+        row: row.row,
+        values: row.values.clone(),
+        errors: error.status_and_error().1,
+    }
+}
+
+/// Rejected rows as a spreadsheet: line number, the uploaded values and the errors.
+pub fn report_file(format: ExportFormat, rejected_rows: &[ImportRejectedRow]) -> Result<Vec<u8>, AppError> {
+    let mut columns = vec!["row".to_string()];
+    columns.extend(IMPORT_COLUMNS.iter().map(|column| column.to_string()));
+    columns.push("errors".to_string());
+
+    let rows: Vec<Value> = rejected_rows
+        .iter()
+        .map(|rejected_row| {
+            let mut row = Map::new();
+            row.insert("row".to_string(), json!(rejected_row.row));
+            for (column, value) in &rejected_row.values {
+                row.insert(column.clone(), json!(value));
+            }
+            row.insert("errors".to_string(), json!(to_text(&rejected_row.errors)));
+            Value::Object(row)
+        })
+        .collect();
+
+    match format {
+        ExportFormat::Csv => export::build_csv(&columns, &rows),
+        ExportFormat::Xlsx => export::build_xlsx(&columns, &rows),
+    }
+}
+
+/// `field: message; ...` for per field errors, the message otherwise.
+fn to_text(errors: &Value) -> String {
+    match errors {
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(field, message)| format!("{}: {}", field, message.as_str().unwrap_or_default()))
+            .collect::<Vec<String>>()
+            .join("; "),
+        Value::String(message) => message.clone(),
+        other => other.to_string(),
+    }
+}
@@ -1,4 +1,6 @@
 pub mod schema;
 pub mod router;
 pub mod controller;
-pub mod repository;
\ No newline at end of file
+pub mod repository;
+pub mod service;
+pub mod import;
\ No newline at end of file
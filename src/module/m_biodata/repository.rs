@@ -3,18 +3,25 @@ use diesel::{dsl::insert_into, prelude::*, sql_query, update};
 use crate::{
     diesel_schema::m_biodata::dsl::*,
     dto::{
-        database::CountResult, enumerator::filter_match_mode::FilterMatchMode, request::{filter_request::Filter, sort_request::Sort}, response::app_error::AppError
+        database::{last_insert_id, CountResult, JsonRow, SearchRow}, enumerator::{audit_action::AuditAction, filter_match_mode::FilterMatchMode, search_mode::SearchMode}, request::{aggregate_request::Aggregation, filter_request::Filter, sort_request::Sort}, response::{aggregate_response::AggregateResponse, app_error::AppError}
     },
-    module::m_biodata::schema::MBiodata,
-    util::string_manipulation,
+    module::{
+        audit_log::service as audit_log_service,
+        m_biodata::schema::{MBiodata, AGGREGATE_COLUMNS, AUDIT_ENTITY, COLUMNS, SEARCH_COLUMNS},
+    },
+    util::{aggregate, projection, search, string_manipulation},
 };
 
 pub fn find_by_id(
     conn: &mut MysqlConnection,
     biodata_id: i64,
+    include_deleted: bool,
 ) -> Result<Option<MBiodata>, AppError> {
-    let user = m_biodata
-        .filter(id.eq(biodata_id))
+    let mut query = m_biodata.filter(id.eq(biodata_id)).into_boxed();
+    if !include_deleted {
+        query = query.filter(is_delete.eq(false));
+    }
+    let user = query
         .select(MBiodata::as_select())
         .first::<MBiodata>(conn)
         .optional()
@@ -23,62 +30,187 @@ pub fn find_by_id(
     Ok(user)
 }
 
+/// The row locked until the surrounding transaction ends, for check-and-write
+/// sequences run through `unit_of_work`.
+pub fn lock_by_id(conn: &mut MysqlConnection, biodata_id: i64) -> Result<Option<MBiodata>, AppError> {
+    let locked = m_biodata
+        .filter(id.eq(biodata_id))
+        .filter(is_delete.eq(false))
+        .select(MBiodata::as_select())
+        .for_update()
+        .first::<MBiodata>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, biodata_id)))?;
+
+    Ok(locked)
+}
+
 pub fn find_query_by_id(
     conn: &mut MysqlConnection,
     biodata_id: i64,
+    include_deleted: bool,
+    select: &str,
 ) -> Result<Option<MBiodata>, AppError> {
-    let query = "SELECT * 
+    let query = format!(
+        "SELECT {}
             FROM m_biodata 
-            WHERE id = ?";
+            WHERE id = ? AND (? OR is_delete = 0)",
+        select
+    );
 
     let user: Option<MBiodata> = sql_query(query)
         .bind::<diesel::sql_types::BigInt, _>(biodata_id)
+        .bind::<diesel::sql_types::Bool, _>(include_deleted)
         .get_result::<MBiodata>(conn)
         .optional()
         .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, biodata_id)))?;
     Ok(user)
 }
 
-pub fn find_all(conn: &mut MysqlConnection) -> Result<Vec<MBiodata>, AppError> {
-    let query = "SELECT * 
-            FROM m_biodata";
+pub fn find_all(
+    conn: &mut MysqlConnection,
+    include_deleted: bool,
+    select: &str,
+) -> Result<Vec<MBiodata>, AppError> {
+    let query = format!(
+        "SELECT {}
+            FROM m_biodata
+            WHERE ? OR is_delete = 0",
+        select
+    );
 
     let user: Vec<MBiodata> = sql_query(query)
+        .bind::<diesel::sql_types::Bool, _>(include_deleted)
         .get_results::<MBiodata>(conn)
         .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
     Ok(user)
 }
 
+/// Mark the row deleted, it stays available through `include_deleted`.
+pub fn soft_delete_by_id(conn: &mut MysqlConnection, biodata_id: i64) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_audited(conn, biodata_id)?;
+        let rows_affected = update(m_biodata.filter(id.eq(biodata_id)).filter(is_delete.eq(false)))
+            .set((
+                is_delete.eq(true),
+                deleted_by.eq(Some(0)),
+                deleted_on.eq(Some(chrono::Utc::now().naive_utc())),
+                row_version.eq(row_version + 1),
+            ))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, biodata_id)))?;
+
+        if rows_affected > 0 {
+            audit(conn, biodata_id, AuditAction::DELETE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+pub fn restore_by_id(conn: &mut MysqlConnection, biodata_id: i64) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_audited(conn, biodata_id)?;
+        let rows_affected = update(m_biodata.filter(id.eq(biodata_id)).filter(is_delete.eq(true)))
+            .set((
+                is_delete.eq(false),
+                deleted_by.eq(None::<i64>),
+                deleted_on.eq(None::<chrono::NaiveDateTime>),
+                modified_by.eq(Some(0)),
+                modified_on.eq(Some(chrono::Utc::now().naive_utc())),
+                row_version.eq(row_version + 1),
+            ))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, biodata_id)))?;
+
+        if rows_affected > 0 {
+            audit(conn, biodata_id, AuditAction::RESTORE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+/// Ids of rows soft deleted before `cutoff`.
+pub fn find_deleted_before(
+    conn: &mut MysqlConnection,
+    cutoff: chrono::NaiveDateTime,
+) -> Result<Vec<i64>, AppError> {
+    let ids = m_biodata
+        .filter(is_delete.eq(true))
+        .filter(deleted_on.lt(cutoff))
+        .select(id)
+        .load::<i64>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+
+    Ok(ids)
+}
+
+/// Whether any biodata, soft deleted or not, still uses the file as its image.
+pub fn exists_by_image_file_id(conn: &mut MysqlConnection, mfile_id: i64) -> Result<bool, AppError> {
+    let count: i64 = m_biodata
+        .filter(image_file_id.eq(mfile_id))
+        .count()
+        .get_result(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+    Ok(count > 0)
+}
+
+/// Permanently remove the row.
 pub fn delete_by_id(conn: &mut MysqlConnection, biodata_id: i64) -> Result<Option<()>, AppError> {
-    let rows_affected = diesel::delete(m_biodata.filter(id.eq(biodata_id)))
-        .execute(conn)
-        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, biodata_id)))?;
+    conn.transaction(|conn| {
+        let before = find_audited(conn, biodata_id)?;
+        let rows_affected = diesel::delete(m_biodata.filter(id.eq(biodata_id)))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, biodata_id)))?;
 
-    if rows_affected > 0 {
-        return Ok(Some(()));
-    }
-    return Ok(None);
+        if rows_affected > 0 {
+            audit(conn, biodata_id, AuditAction::PURGE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
 }
 
+/// Returns the id of the new row, assigned by the database when `id` is `0`.
 pub fn insert_biodata(
     conn: &mut MysqlConnection,
     biodata: MBiodata,
-) -> Result<Option<()>, AppError> {
-    let rows_affected = insert_into(m_biodata)
-        .values(&biodata)
-        .execute(conn)
-        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
-    if rows_affected > 0 {
-        return Ok(Some(()));
-    }
-    return Ok(None);
+) -> Result<Option<i64>, AppError> {
+    conn.transaction(|conn| {
+        let rows_affected = insert_into(m_biodata)
+            .values(&biodata)
+            .execute(conn)
+            // a duplicate key maps to DataExist
+            .map_err(AppError::from)?;
+        if rows_affected == 0 {
+            return Ok(None);
+        }
+        let mut new_id = biodata.id;
+        if new_id == 0 {
+            new_id = diesel::select(last_insert_id())
+                .get_result::<u64>(conn)
+                .map_err(|error| AppError::Other(format!("query failed: {}", error)))? as i64;
+        }
+
+        audit(conn, new_id, AuditAction::CREATE, None)?;
+        return Ok(Some(new_id));
+    })
 }
 
+/// Returns `None` when the row is gone or was updated since it was read.
 pub fn update_biodata(
     conn: &mut MysqlConnection,
     biodata: MBiodata,
 ) -> Result<Option<()>, AppError> {
-    let rows_affected = update(m_biodata.filter(id.eq(biodata.id)))
+    conn.transaction(|conn| {
+        let before = find_audited(conn, biodata.id)?;
+        let rows_affected = update(
+            m_biodata
+                .filter(id.eq(biodata.id))
+                .filter(row_version.eq(biodata.row_version)),
+        )
         .set((
             modified_by.eq(biodata.modified_by),
             modified_on.eq(biodata.modified_on),
@@ -89,15 +221,46 @@ pub fn update_biodata(
             mobile_phone.eq(biodata.mobile_phone),
             image.eq(biodata.image),
             image_path.eq(biodata.image_path),
+            row_version.eq(row_version + 1),
         ))
         .execute(conn)
-        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, biodata.id)))?;
-    if rows_affected > 0 {
-        return Ok(Some(()));
-    }
-    return Ok(None);
+        // a duplicate key maps to DataExist
+        .map_err(AppError::from)?;
+        if rows_affected > 0 {
+            audit(conn, biodata.id, AuditAction::UPDATE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+pub fn update_image(
+    conn: &mut MysqlConnection,
+    biodata_id: i64,
+    file_id: Option<i64>,
+    file_path: Option<String>,
+) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_audited(conn, biodata_id)?;
+        let rows_affected = update(m_biodata.filter(id.eq(biodata_id)))
+            .set((
+                modified_by.eq(Some(0)),
+                modified_on.eq(Some(chrono::Utc::now().naive_utc())),
+                image_file_id.eq(file_id),
+                image_path.eq(file_path),
+                row_version.eq(row_version + 1),
+            ))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, biodata_id)))?;
+        if rows_affected > 0 {
+            audit(conn, biodata_id, AuditAction::UPDATE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn pagination(
     conn: &mut MysqlConnection,
     page: i64,
@@ -105,24 +268,30 @@ pub fn pagination(
     filters: Vec<Filter>,
     sorts: Vec<Sort>,
     search: String,
+    include_deleted: bool,
+    select: &str,
 ) -> Result<(Vec<MBiodata>, i64), AppError> {
     // Build the query
-    let mut query = "SELECT *".to_string();
+    let mut query = format!("SELECT {}", select);
     let mut query_count = "SELECT COUNT(*) AS count".to_string();
     let query_table = "FROM m_biodata".to_string();
 
     // Sort
     let mut query_sort = String::new();
-    for sort in sorts {
-        query_sort = "ORDER BY".to_owned();
-        let sort_asc = if sort.desc {
-            "DESC".to_string()
-        } else {
-            "ASC".to_string()
-        };
-        let sort_id = string_manipulation::cleanse_string(&sort.id);
-        query_sort = format!("{} {} {}", query_sort, sort_id, sort_asc);
-        break;
+    let sort_keys: Vec<String> = sorts
+        .iter()
+        .map(|sort| {
+            let sort_asc = if sort.desc {
+                "DESC".to_string()
+            } else {
+                "ASC".to_string()
+            };
+            let sort_id = string_manipulation::cleanse_string(&sort.id);
+            format!("{} {}", sort_id, sort_asc)
+        })
+        .collect();
+    if !sort_keys.is_empty() {
+        query_sort = format!("ORDER BY {}", sort_keys.join(", "));
     }
 
     // Search
@@ -173,17 +342,27 @@ pub fn pagination(
         }
     }
 
+    // Soft delete
+    let mut query_deleted = String::new();
+    if !include_deleted {
+        if query_search.trim().is_empty() && query_filter.is_empty() {
+            query_deleted = "WHERE is_delete = 0".to_string();
+        } else {
+            query_deleted = "AND is_delete = 0".to_string();
+        }
+    }
+
     // Pagination
     let query_pagination = format!("LIMIT {} OFFSET {}", size, size * (page));
 
     // Final
     query = format!(
-        "{} {} {} {} {} {}",
-        query, query_table, query_search, query_filter, query_sort, query_pagination
+        "{} {} {} {} {} {} {}",
+        query, query_table, query_search, query_filter, query_deleted, query_sort, query_pagination
     );
     query_count = format!(
-        "{} {} {} {} {}",
-        query_count, query_table, query_search, query_filter, query_sort
+        "{} {} {} {} {} {}",
+        query_count, query_table, query_search, query_filter, query_deleted, query_sort
     );
     log::info!(
         "repository > find_diesel_query_biodata_page > query: {:#?}",
@@ -202,3 +381,85 @@ pub fn pagination(
         .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
     Ok((data_vec, results[0].count))
 }
+
+/// Grouped counts and aggregates for dashboards, see `util::aggregate`.
+pub fn aggregate(
+    conn: &mut MysqlConnection,
+    aggregation: Aggregation,
+    filters: Vec<Filter>,
+    include_deleted: bool,
+) -> Result<AggregateResponse, AppError> {
+    let (query, columns) =
+        aggregate::build_query("m_biodata", AGGREGATE_COLUMNS, aggregation, filters, include_deleted)?;
+    log::info!("repository > aggregate_m_biodata > query: {:#?}", query);
+
+    let json_rows = sql_query(query)
+        .load::<JsonRow>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    let rows = aggregate::to_rows(json_rows, &columns)?;
+    Ok(AggregateResponse { columns, rows })
+}
+
+/// One page of full-text matches ranked by relevance, see `util::search`.
+pub fn search(
+    conn: &mut MysqlConnection,
+    terms: &[String],
+    mode: SearchMode,
+    include_deleted: bool,
+    select: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<(MBiodata, f64)>, i64), AppError> {
+    let (query, query_count) = search::queries("m_biodata", SEARCH_COLUMNS, mode);
+    let against = search::against(terms, mode);
+    log::info!("repository > search_m_biodata > query: {:#?}, against: {:#?}", query, against);
+
+    let rows = sql_query(query)
+        .bind::<diesel::sql_types::Text, _>(&against)
+        .bind::<diesel::sql_types::Text, _>(&against)
+        .bind::<diesel::sql_types::Bool, _>(include_deleted)
+        .bind::<diesel::sql_types::BigInt, _>(limit)
+        .bind::<diesel::sql_types::BigInt, _>(offset)
+        .load::<SearchRow>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    let results = sql_query(query_count)
+        .bind::<diesel::sql_types::Text, _>(&against)
+        .bind::<diesel::sql_types::Bool, _>(include_deleted)
+        .load::<CountResult>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    if rows.is_empty() {
+        return Ok((Vec::new(), results[0].count));
+    }
+
+    let query_items = format!(
+        "SELECT {}
+            FROM m_biodata
+            WHERE id IN ({})",
+        select,
+        search::id_list(&rows)
+    );
+    let items = sql_query(query_items)
+        .load::<MBiodata>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    Ok((search::in_rank_order(&rows, items, |item| item.id), results[0].count))
+}
+
+/// The row without its binary columns, which the audit log leaves out.
+fn find_audited(conn: &mut MysqlConnection, biodata_id: i64) -> Result<Option<MBiodata>, AppError> {
+    let fields = projection::resolve(None, &COLUMNS, true)?;
+    find_query_by_id(conn, biodata_id, true, &projection::select_list(&fields, &COLUMNS))
+}
+
+/// Record a change of the row, `before` read ahead of the write.
+fn audit(
+    conn: &mut MysqlConnection,
+    biodata_id: i64,
+    action: AuditAction,
+    before: Option<MBiodata>,
+) -> Result<(), AppError> {
+    let after = match action {
+        AuditAction::PURGE => None,
+        _ => find_audited(conn, biodata_id)?,
+    };
+    audit_log_service::record(conn, AUDIT_ENTITY, biodata_id, action, before.as_ref(), after.as_ref())
+}
@@ -1,12 +1,26 @@
-use axum::{routing::{get, post}, Router};
+use axum::{middleware::from_fn, routing::{delete, get, post}, Router};
 
-use crate::module::{ m_biodata::controller::{create, delete_by_id, find_all, find_by_id, update, find_page}};
+use crate::middleware::auth_middleware::{require_admin, require_admin_for_deleted, require_user, require_user_for_writes};
+use crate::module::{ m_biodata::controller::{aggregate, search, create, delete_by_id, export, import, find_all, find_by_id, find_image, image_signed_url, update, upload_image, find_page, patch, purge, restore, bulk_create, bulk_update, bulk_delete}};
 
 
 pub fn new() -> Router {
     Router::new()
     .route("/list", get(find_all))
     .route("/pagination", get(find_page))
+    .route("/export", get(export))
+    .route("/aggregate", get(aggregate))
+    .route("/search", get(search))
+    .route("/import", post(import))
     .route("/", post(create).put(update))
-    .route("/{biodata_id}", get(find_by_id).delete(delete_by_id))
+    .route("/bulk", post(bulk_create).put(bulk_update).delete(bulk_delete))
+    .route("/{biodata_id}", get(find_by_id).patch(patch).delete(delete_by_id))
+    .route("/{biodata_id}/restore", post(restore))
+    .route("/{biodata_id}/purge", delete(purge).route_layer(from_fn(require_admin)))
+    .route("/{biodata_id}/image", get(find_image).post(upload_image))
+    .route("/{biodata_id}/image/signed-url", get(image_signed_url).route_layer(from_fn(require_user)))
+    // changes are recorded in the audit log under the caller
+    .route_layer(from_fn(require_user_for_writes))
+    // soft deleted rows are for administrators only
+    .route_layer(from_fn(require_admin_for_deleted))
 }
\ No newline at end of file
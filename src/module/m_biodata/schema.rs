@@ -3,9 +3,13 @@ use diesel::prelude::{Insertable, Queryable, QueryableByName};
 use diesel::Selectable;
 use serde::{Deserialize, Serialize};
 
-use validator::Validate;
+use validator::{Validate, ValidationError, ValidationErrors};
 
+use crate::config::id_generator;
 use crate::diesel_schema::m_biodata;
+use crate::dto::enumerator::filter_data_type::FilterDataType;
+use crate::dto::response::app_error::AppError;
+use crate::util::projection::Columns;
 use crate::util::serializer::{date_serializer, option_date_serializer};
 
 #[derive(
@@ -35,6 +39,10 @@ pub struct MBiodata {
     #[serde(with = "option_date_serializer")]
     pub deleted_on: Option<NaiveDateTime>,
     pub is_delete: bool,
+    /// `m_file` holding the profile image uploaded through `/{id}/image`
+    pub image_file_id: Option<i64>,
+    /// bumped on every update, the `ETag` of the row
+    pub row_version: i64,
 }
 
 impl MBiodata {
@@ -53,9 +61,11 @@ impl MBiodata {
             deleted_by: None,
             deleted_on: None,
             is_delete: false,
+            image_file_id: None,
+            row_version: 0,
         }
     }
-    pub fn from_create_request(request: MBiodataRequest) -> MBiodata {
+    pub fn from_create_request(request: MBiodataRequest) -> Result<MBiodata, AppError> {
         let date_now = chrono::Utc::now().naive_utc();
         let is_delete = request.is_delete.unwrap_or(false);
         let mut deleted_by: Option<i64> = None;
@@ -64,8 +74,8 @@ impl MBiodata {
             deleted_by = Some(0);
             deleted_on = Some(date_now);
         }
-        MBiodata {
-            id: request.id.unwrap(),
+        Ok(MBiodata {
+            id: id_generator::next_id()?,
             fullname: request.fullname,
             mobile_phone: request.mobile_phone,
             image: request.image,
@@ -77,7 +87,9 @@ impl MBiodata {
             deleted_by: deleted_by,
             deleted_on: deleted_on,
             is_delete: is_delete,
-        }
+            image_file_id: None,
+            row_version: 0,
+        })
     }
     pub fn from_update_request(request: MBiodataRequest, existing: MBiodata) -> MBiodata {
         let date_now = chrono::Utc::now().naive_utc();
@@ -89,11 +101,16 @@ impl MBiodata {
             deleted_on = Some(date_now);
         }
         MBiodata {
-            id: request.id.unwrap(),
+            id: existing.id,
             fullname: request.fullname,
             mobile_phone: request.mobile_phone,
             image: request.image,
-            image_path: request.image_path,
+            // an uploaded image owns image_path, see `image_file_id`
+            image_path: if existing.image_file_id.is_some() {
+                existing.image_path
+            } else {
+                request.image_path
+            },
             created_by: existing.created_by,
             created_on: existing.created_on,
             modified_by: Some(0),
@@ -101,18 +118,89 @@ impl MBiodata {
             deleted_by: deleted_by,
             deleted_on: deleted_on,
             is_delete: is_delete,
+            image_file_id: existing.image_file_id,
+            row_version: existing.row_version,
         }
     }
 }
 
 
 
-#[derive(Debug, Deserialize, Serialize, Validate)]
+/// Columns `_fields` can select, in table order.
+pub const COLUMNS: Columns = Columns {
+    all: &[
+        "id",
+        "fullname",
+        "mobile_phone",
+        "image",
+        "image_path",
+        "created_by",
+        "created_on",
+        "modified_by",
+        "modified_on",
+        "deleted_by",
+        "deleted_on",
+        "is_delete",
+        "image_file_id",
+        "row_version",
+    ],
+    nullable: &[
+        "fullname",
+        "mobile_phone",
+        "image",
+        "image_path",
+        "modified_by",
+        "modified_on",
+        "deleted_by",
+        "deleted_on",
+        "image_file_id",
+    ],
+    blobs: &["image"],
+};
+
+/// Columns `/aggregate` can group by or aggregate.
+pub const AGGREGATE_COLUMNS: &[(&str, FilterDataType)] = &[
+    ("id", FilterDataType::NUMBER),
+    ("fullname", FilterDataType::TEXT),
+    ("mobile_phone", FilterDataType::TEXT),
+    ("image_path", FilterDataType::TEXT),
+    ("created_by", FilterDataType::NUMBER),
+    ("created_on", FilterDataType::DATE),
+    ("modified_by", FilterDataType::NUMBER),
+    ("modified_on", FilterDataType::DATE),
+    ("deleted_by", FilterDataType::NUMBER),
+    ("deleted_on", FilterDataType::DATE),
+    ("is_delete", FilterDataType::BOOLEAN),
+    ("image_file_id", FilterDataType::NUMBER),
+];
+
+/// Entity name of the audit log records of this table.
+pub const AUDIT_ENTITY: &str = "m_biodata";
+
+/// Columns of the FULLTEXT index `/search` matches against.
+pub const SEARCH_COLUMNS: &[&str] = &["fullname", "mobile_phone"];
+
+/// Columns `/export` can write, binary content is left out.
+pub const EXPORT_COLUMNS: &[&str] = &[
+    "id",
+    "fullname",
+    "mobile_phone",
+    "image_path",
+    "image_file_id",
+    "created_by",
+    "created_on",
+    "modified_by",
+    "modified_on",
+    "deleted_by",
+    "deleted_on",
+    "is_delete",
+    "row_version",
+];
+
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
 pub struct MBiodataRequest {
-    #[validate(
-        range(min = 1, max = 999999, message = "must be between 1-999999 chars"),
-        required(message = "mandatory")
-    )]
+    /// ignored on create, the id is generated by the server
+    #[validate(range(min = 1, message = "must be greater than 0"))]
     pub id: Option<i64>,
     #[validate(
         length(min = 3, message = "must be greater than 3 chars"),
@@ -127,4 +215,34 @@ pub struct MBiodataRequest {
     pub image_path: Option<String>,
     #[validate(required(message = "mandatory"))]
     pub is_delete: Option<bool>,
+    /// version the client last read, checked by bulk updates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_version: Option<i64>,
+}
+
+impl MBiodataRequest {
+    /// Id of the row an update targets, mandatory there unlike on create.
+    pub fn require_id(&self) -> Result<i64, AppError> {
+        match self.id {
+            Some(value) => Ok(value),
+            None => {
+                let mut errors = ValidationErrors::new();
+                errors.add("id", ValidationError::new("required").with_message("mandatory".into()));
+                Err(AppError::InvalidRequest(errors))
+            }
+        }
+    }
+
+    /// Request document of an existing row, the base a merge patch applies to.
+    pub fn from_existing(existing: &MBiodata) -> MBiodataRequest {
+        MBiodataRequest {
+            id: Some(existing.id),
+            fullname: existing.fullname.clone(),
+            mobile_phone: existing.mobile_phone.clone(),
+            image: existing.image.clone(),
+            image_path: existing.image_path.clone(),
+            is_delete: Some(existing.is_delete),
+            row_version: Some(existing.row_version),
+        }
+    }
 }
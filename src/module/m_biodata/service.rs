@@ -0,0 +1,92 @@
+use diesel::MysqlConnection;
+use validator::Validate;
+
+use crate::{
+    dto::{request::bulk_request::BulkDeleteItem, response::app_error::AppError},
+    module::{
+        m_biodata::{
+            repository,
+            schema::{MBiodata, MBiodataRequest},
+        },
+        m_file::{repository as m_file_repository, service as m_file_service},
+    },
+    state::AppState,
+    util::{etag, unit_of_work},
+};
+
+/// Permanently delete a biodata row and the image file it owns.
+pub async fn purge_biodata(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    biodata: &MBiodata,
+) -> Result<Option<()>, AppError> {
+    // the row references the image file, remove it first
+    let result = repository::delete_by_id(conn, biodata.id)?;
+    if result.is_none() {
+        return Ok(None);
+    }
+
+    if let Some(image_file_id) = biodata.image_file_id
+        && let Some(m_file) = m_file_repository::find_by_id(conn, image_file_id, true)?
+    {
+        m_file_service::purge_file(state, conn, &m_file).await?;
+    }
+    Ok(result)
+}
+
+/// Validate and insert one biodata, the unit of work of a bulk create.
+pub fn create_biodata(
+    conn: &mut MysqlConnection,
+    m_biodata_request: MBiodataRequest,
+) -> Result<i64, AppError> {
+    m_biodata_request.validate().map_err(AppError::InvalidRequest)?;
+
+    let new_m_biodata = MBiodata::from_create_request(m_biodata_request)?;
+    match repository::insert_biodata(conn, new_m_biodata)? {
+        Some(new_id) => Ok(new_id),
+        None => Err(AppError::Other(format!("save data failed"))),
+    }
+}
+
+/// Validate and update one biodata, the unit of work of a bulk update.
+pub fn update_biodata(
+    conn: &mut MysqlConnection,
+    m_biodata_request: MBiodataRequest,
+) -> Result<i64, AppError> {
+    m_biodata_request.validate().map_err(AppError::InvalidRequest)?;
+
+    unit_of_work::run(conn, |conn| {
+        let existing = match repository::lock_by_id(conn, m_biodata_request.require_id()?)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        etag::check_row_version(existing.id, m_biodata_request.row_version, existing.row_version)?;
+
+        let new_m_biodata = MBiodata::from_update_request(m_biodata_request, existing);
+        let biodata_id = new_m_biodata.id;
+        match repository::update_biodata(conn, new_m_biodata)? {
+            Some(_) => Ok(biodata_id),
+            None => Err(AppError::PreconditionFailed("resource was modified".to_string())),
+        }
+    })
+}
+
+/// Soft delete one biodata, the unit of work of a bulk delete.
+pub fn soft_delete_biodata(conn: &mut MysqlConnection, item: BulkDeleteItem) -> Result<i64, AppError> {
+    unit_of_work::run(conn, |conn| {
+        let existing = match repository::lock_by_id(conn, item.id)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        etag::check_row_version(existing.id, item.row_version, existing.row_version)?;
+
+        match repository::soft_delete_by_id(conn, existing.id)? {
+            Some(_) => Ok(existing.id),
+            None => Err(AppError::NotFound),
+        }
+    })
+}
@@ -0,0 +1,251 @@
+use std::{collections::HashSet, io, sync::Arc};
+
+use async_zip::{tokio::write::ZipFileWriter, Compression, ZipEntryBuilder};
+use axum::body::Bytes;
+use futures_util::{stream, Stream, StreamExt};
+use tokio::{io::DuplexStream, sync::oneshot};
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    dto::{
+        enumerator::file_type::FileType,
+        request::{filter_request::Filter, sort_request::Sort},
+        response::app_error::AppError,
+    },
+    module::m_file::{repository, schema::MFile, service},
+    state::AppState,
+    util::export,
+};
+
+const PIPE_BUFFER_SIZE: usize = 64 * 1024;
+pub const PAGE_SIZE: i64 = 100;
+const SKIPPED_ENTRY_NAME: &str = "MISSING.txt";
+
+/// Rows to put into the archive, fetched one page at a time.
+#[derive(Clone)]
+pub enum ArchiveSource {
+    Ids(Vec<i64>),
+    Query {
+        filters: Vec<Filter>,
+        sorts: Vec<Sort>,
+        search: String,
+    },
+}
+
+/// Entry of the archive: unique name inside the ZIP and where to read it from.
+pub struct ArchiveEntry {
+    pub name: String,
+    pub file_path: Option<String>,
+    pub file_type: String,
+    pub available: bool,
+}
+
+/// Give every file a unique name inside the archive, `report.pdf` becoming
+/// `report (1).pdf`, `report (2).pdf`, ... on collision.
+#[derive(Default)]
+pub struct EntryNames {
+    used_names: HashSet<String>,
+}
+
+impl EntryNames {
+    pub fn unique(&mut self, original_name: &str) -> String {
+        let (stem, extension) = match original_name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => {
+                (stem.to_string(), format!(".{}", extension))
+            }
+            _ => (original_name.to_string(), String::new()),
+        };
+
+        let mut name = original_name.to_string();
+        let mut counter = 0;
+        while self.used_names.contains(&name) {
+            counter += 1;
+            name = format!("{} ({}){}", stem, counter, extension);
+        }
+        self.used_names.insert(name.clone());
+        name
+    }
+
+    pub fn entry(&mut self, file: MFile) -> ArchiveEntry {
+        let original_name = file
+            .file_name
+            .clone()
+            .filter(|value| !value.is_empty())
+            .unwrap_or(format!("{}", file.id));
+        ArchiveEntry {
+            name: self.unique(&original_name),
+            available: service::ensure_available(&file).is_ok(),
+            file_path: file.file_path,
+            file_type: file.file_type.unwrap_or_default(),
+        }
+    }
+}
+
+/// Fetch one page of rows, `page` starting at 0, and whether more pages follow.
+pub fn fetch_page(
+    state: &AppState,
+    source: &ArchiveSource,
+    page: i64,
+) -> Result<(Vec<MFile>, bool), AppError> {
+    let mut db_conn = state
+        .diesel_pool_mysql
+        .get()
+        .map_err(|error| AppError::Other(format!("get connection failed {error}")))?;
+
+    match source {
+        ArchiveSource::Ids(ids) => {
+            let mut chunks = ids.chunks(PAGE_SIZE as usize).skip(page as usize);
+            match chunks.next() {
+                Some(chunk) => Ok((
+                    repository::find_by_ids(&mut db_conn, chunk)?,
+                    chunks.next().is_some(),
+                )),
+                None => Ok((Vec::new(), false)),
+            }
+        }
+        ArchiveSource::Query {
+            filters,
+            sorts,
+            search,
+        } => {
+            let (content, _) = repository::pagination(
+                &mut db_conn,
+                page,
+                PAGE_SIZE,
+                filters.clone(),
+                export::stable_sorts(sorts.clone()),
+                search.clone(),
+                false,
+                "*",
+            )?;
+            let has_more = content.len() as i64 == PAGE_SIZE;
+            Ok((content, has_more))
+        }
+    }
+}
+
+/// Write the ZIP into one end of a pipe from a background task and return the
+/// other end as a body stream, so the response streams while only one page of
+/// rows and one file are held in memory. `first_page` and `has_more` are the
+/// caller's `fetch_page` result for `page`. Files that could not be added are
+/// listed in `MISSING.txt`; a storage or database error ends the stream with
+/// an error instead of handing out a truncated archive.
+pub fn stream(
+    state: Arc<AppState>,
+    source: ArchiveSource,
+    page: i64,
+    first_page: Vec<MFile>,
+    has_more: bool,
+) -> impl Stream<Item = Result<Bytes, io::Error>> {
+    let (reader, writer) = tokio::io::duplex(PIPE_BUFFER_SIZE);
+    let (error_sender, error_receiver) = oneshot::channel::<io::Error>();
+
+    tokio::spawn(async move {
+        if let Err(error) = write_archive(state, source, page, first_page, has_more, writer).await {
+            log::error!("archive: {}", error);
+            let _ = error_sender.send(error);
+        }
+    });
+
+    // the writer is dropped before the error is sent, the reader sees the end
+    // of the data first and the error is appended after it
+    let error = stream::once(error_receiver).filter_map(|received| async move {
+        match received {
+            Ok(error) => Some(Err(error)),
+            Err(_) => None,
+        }
+    });
+    ReaderStream::new(reader).chain(error)
+}
+
+async fn write_archive(
+    state: Arc<AppState>,
+    source: ArchiveSource,
+    page: i64,
+    first_page: Vec<MFile>,
+    has_more: bool,
+    writer: DuplexStream,
+) -> Result<(), io::Error> {
+    let mut zip_writer = ZipFileWriter::with_tokio(writer);
+    let mut names = EntryNames::default();
+    let mut skipped = Vec::new();
+    let mut files = first_page;
+    let mut has_more = has_more;
+    let mut page = page;
+
+    loop {
+        for file in files {
+            let entry = names.entry(file);
+            if !entry.available {
+                skipped.push(format!("{}: not available, scan pending", entry.name));
+                continue;
+            }
+            let file_path = match entry.file_path {
+                Some(value) => value,
+                None => {
+                    skipped.push(format!("{}: no content", entry.name));
+                    continue;
+                }
+            };
+            let contents = match state.storage.get(&file_path).await {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    log::error!("archive: missing content for {}", file_path);
+                    skipped.push(format!("{}: content missing from storage", entry.name));
+                    continue;
+                }
+                Err(error) => {
+                    return Err(io::Error::other(format!(
+                        "read {} failed: {:?}",
+                        file_path, error
+                    )));
+                }
+            };
+
+            // media formats are already compressed
+            let compression = if entry.file_type == FileType::IMAGE.to_string()
+                || entry.file_type == FileType::AUDIO.to_string()
+                || entry.file_type == FileType::VIDEO.to_string()
+            {
+                Compression::Stored
+            } else {
+                Compression::Deflate
+            };
+            let builder = ZipEntryBuilder::new(entry.name.into(), compression);
+            zip_writer
+                .write_entry_whole(builder, &contents)
+                .await
+                .map_err(|error| io::Error::other(format!("write entry failed: {}", error)))?;
+        }
+
+        if !has_more {
+            break;
+        }
+        page += 1;
+        let page_state = state.clone();
+        let page_source = source.clone();
+        (files, has_more) =
+            tokio::task::spawn_blocking(move || fetch_page(&page_state, &page_source, page))
+                .await
+                .map_err(|error| io::Error::other(format!("fetch page failed: {}", error)))?
+                .map_err(|error| io::Error::other(format!("fetch page failed: {:?}", error)))?;
+    }
+
+    if !skipped.is_empty() {
+        let builder = ZipEntryBuilder::new(
+            names.unique(SKIPPED_ENTRY_NAME).into(),
+            Compression::Deflate,
+        );
+        let mut report = skipped.join("\n");
+        report.push('\n');
+        zip_writer
+            .write_entry_whole(builder, report.as_bytes())
+            .await
+            .map_err(|error| io::Error::other(format!("write entry failed: {}", error)))?;
+    }
+    zip_writer
+        .close()
+        .await
+        .map_err(|error| io::Error::other(format!("close failed: {}", error)))?;
+    Ok(())
+}
@@ -1,42 +1,52 @@
-use std::{path::PathBuf, sync::Arc};
+use std::sync::Arc;
 
 use axum::{
-    body::{Body, Bytes},
+    body::Body,
     extract::{Extension, Json, Multipart, Path, Query},
-    http::StatusCode,
+    http::{header::{ETAG, LOCATION}, HeaderMap, HeaderName, Response, StatusCode},
     response::IntoResponse,
 };
-use chrono::{Datelike, Local};
-use tokio::{
-    fs::File,
-    io::{AsyncReadExt, AsyncWriteExt},
-};
-use validator::Validate;
+use chrono::Local;
+use diesel::MysqlConnection;
+use serde_json::Value;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 use crate::{
+    config::environment::CONFIG,
     dto::{
-        enumerator::file_type::FileType,
+        enumerator::{export_format::ExportFormat, file_type::FileType},
         request::{
-            filter_request::Filters, pagination_request::Pagination, search_request::Search,
+            aggregate_request::Aggregation, bulk_request::{BulkDeleteItem, BulkOptions}, deleted_request::IncludeDeleted, export_request::Export, fields_request::Fields, filter_request::Filters, pagination_request::Pagination, search_request::{FullTextSearch, Search},
             sort_request::Sorts,
         },
         response::{
-            app_error::AppError, app_response::AppResponse, pagination_response::PaginatedResponse,
+            aggregate_response::AggregateResponse, app_error::AppError, app_response::AppResponse,
+            bulk_response::BulkItemResult,
+            pagination_response::PaginatedResponse,
+            search_response::SearchHit,
         },
     },
     module::m_file::{
         repository,
-        schema::{MFile, MFileRequest},
+        archive,
+        schema::{MFile, MFileArchiveRequest, COLUMNS, EXPORT_COLUMNS, SEARCH_COLUMNS, MFileDownloadRequest, MFileRequest, MFileSignedUrl},
+        service,
     },
     state::AppState,
+    util::{bulk, etag, export, merge_patch, projection, search as full_text, signed_url, unit_of_work},
 };
 
 pub async fn find_by_id(
     Path(id): Path<i64>,
+    Query(_deleted): Query<IncludeDeleted>,
+    Query(_fields): Query<Fields>,
     Extension(_state): Extension<Arc<AppState>>,
-) -> Result<(StatusCode, Json<AppResponse<MFile>>), AppError> {
+    headers: HeaderMap,
+) -> Result<Response<Body>, AppError> {
     log::info!("status: {}", _state.status);
 
+    let fields = projection::resolve(_fields._fields, &COLUMNS, false)?;
+
     // get db connection
     let db_conn_result = _state.diesel_pool_mysql.get();
     let mut db_conn;
@@ -49,20 +59,32 @@ pub async fn find_by_id(
         }
     };
 
-    let result = repository::find_by_id(&mut db_conn, id);
+    let result = repository::find_query_by_id(
+        &mut db_conn,
+        id,
+        _deleted.include_deleted.unwrap_or(false),
+        &projection::select_list(&fields, &COLUMNS),
+    );
     match result {
         Ok(Some(value)) => {
+            let etag = etag::of(value.id, value.row_version);
+            if etag::is_not_modified(&headers, &etag) {
+                return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
+            }
+
             let status_code = StatusCode::OK;
             return Ok((
                 status_code,
+                [(ETAG, etag)],
                 Json(AppResponse {
                     status: status_code.as_str().to_string(),
                     message: "success".to_owned(),
                     timestamp: chrono::Utc::now().naive_utc(),
-                    data: Some(value),
+                    data: Some(projection::to_value(&value, &fields)?),
                     error: None,
                 }),
-            ));
+            )
+                .into_response());
         }
         Ok(None) => {
             return Err(AppError::NotFound);
@@ -75,9 +97,13 @@ pub async fn find_by_id(
 
 pub async fn find_all(
     Extension(_state): Extension<Arc<AppState>>,
-) -> Result<(StatusCode, Json<AppResponse<Vec<MFile>>>), AppError> {
+    Query(_deleted): Query<IncludeDeleted>,
+    Query(_fields): Query<Fields>,
+) -> Result<(StatusCode, Json<AppResponse<Vec<Value>>>), AppError> {
     log::info!("status: {}", _state.status);
 
+    let fields = projection::resolve(_fields._fields, &COLUMNS, true)?;
+
     // get db connection
     let db_conn_result = _state.diesel_pool_mysql.get();
     let mut db_conn;
@@ -90,7 +116,11 @@ pub async fn find_all(
         }
     };
 
-    let result = repository::find_all(&mut db_conn);
+    let result = repository::find_all(
+        &mut db_conn,
+        _deleted.include_deleted.unwrap_or(false),
+        &projection::select_list(&fields, &COLUMNS),
+    );
     match result {
         Ok(value) => {
             let status_code = StatusCode::OK;
@@ -100,7 +130,7 @@ pub async fn find_all(
                     status: status_code.as_str().to_string(),
                     message: "success".to_owned(),
                     timestamp: chrono::Utc::now().naive_utc(),
-                    data: Some(value),
+                    data: Some(projection::to_values(&value, &fields)?),
                     error: None,
                 }),
             ));
@@ -114,7 +144,8 @@ pub async fn find_all(
 pub async fn delete_by_id(
     Path(id): Path<i64>,
     Extension(_state): Extension<Arc<AppState>>,
-) -> Result<(StatusCode, Json<AppResponse<String>>), AppError> {
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<AppResponse<MFile>>), AppError> {
     log::info!("status: {}", _state.status);
 
     // get db connection
@@ -125,13 +156,72 @@ pub async fn delete_by_id(
             db_conn = value;
         }
         Err(error) => {
-            return Err(AppError::Other(format!("get connection failed {error}, id: {id}")).into());
+            return Err(AppError::Other(format!("get connection failed {error}, id: {id}")));
         }
     };
 
-    let result = repository::delete_by_id(&mut db_conn, id);
+    // check and delete under the row lock
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        let existing = match repository::lock_by_id(conn, id)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        etag::check_if_match(&headers, &etag::of(existing.id, existing.row_version))?;
+
+        if repository::soft_delete_by_id(conn, id)?.is_none() {
+            return Err(AppError::NotFound);
+        }
+        // the soft deleted row, still reachable with include_deleted
+        repository::find_by_id(conn, id, true)
+    });
     match result {
-        Ok(Some(_)) => {
+        Ok(tombstone) => {
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: tombstone,
+                    error: None,
+                }),
+            ));
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+pub async fn restore(
+    Path(id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MFile>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {id}")));
+        }
+    };
+
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        if repository::restore_by_id(conn, id)?.is_none() {
+            return Err(AppError::NotFound);
+        }
+        repository::find_by_id(conn, id, false)
+    });
+    match result {
+        Ok(restored) => {
             let status_code = StatusCode::OK;
             return Ok((
                 status_code,
@@ -139,11 +229,48 @@ pub async fn delete_by_id(
                     status: status_code.as_str().to_string(),
                     message: "success".to_owned(),
                     timestamp: chrono::Utc::now().naive_utc(),
-                    data: None,
+                    data: restored,
                     error: None,
                 }),
             ));
         }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+/// Permanently delete the file and its stored content.
+pub async fn purge(
+    Path(id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<StatusCode, AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {id}")));
+        }
+    };
+
+    let existing_m_file = match repository::find_by_id(&mut db_conn, id, true)? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+
+    let result = service::purge_file(&_state, &mut db_conn, &existing_m_file).await;
+    match result {
+        Ok(Some(_)) => {
+            return Ok(StatusCode::NO_CONTENT);
+        }
         Ok(None) => {
             return Err(AppError::NotFound);
         }
@@ -156,7 +283,7 @@ pub async fn delete_by_id(
 pub async fn create(
     Extension(_state): Extension<Arc<AppState>>,
     Json(m_file_request): Json<MFileRequest>,
-) -> Result<(StatusCode, Json<AppResponse<String>>), AppError> {
+) -> Result<(StatusCode, [(HeaderName, String); 2], Json<AppResponse<MFile>>), AppError> {
     log::info!("status: {}", _state.status);
 
     let _is_valid = match m_file_request.validate() {
@@ -178,30 +305,24 @@ pub async fn create(
         }
     };
 
-    let new_m_file = MFile::from_create_request(m_file_request);
-    let existing_biodata_result = repository::find_by_id(&mut db_conn, new_m_file.id);
-    match existing_biodata_result {
-        Ok(Some(_)) => {
-            return Err(AppError::DataExist);
-        }
-        Ok(None) => {}
-        Err(err) => {
-            return Err(err);
-        }
-    };
-
-    let result = repository::insert_mfile(&mut db_conn, new_m_file);
+    let mut new_m_file = MFile::from_create_request(m_file_request)?;
+    let result = repository::insert_mfile(&mut db_conn, new_m_file.clone());
 
     match result {
-        Ok(Some(_)) => {
-            let status_code = StatusCode::OK;
+        Ok(Some(new_id)) => {
+            new_m_file.id = new_id;
+            let status_code = StatusCode::CREATED;
             return Ok((
                 status_code,
+                [
+                    (LOCATION, format!("/m-file/{}", new_m_file.id)),
+                    (ETAG, etag::of(new_m_file.id, new_m_file.row_version)),
+                ],
                 Json(AppResponse {
                     status: status_code.as_str().to_string(),
                     message: "success".to_owned(),
                     timestamp: chrono::Utc::now().naive_utc(),
-                    data: None,
+                    data: Some(new_m_file),
                     error: None,
                 }),
             ));
@@ -217,8 +338,9 @@ pub async fn create(
 
 pub async fn update(
     Extension(_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
     Json(m_file_request): Json<MFileRequest>,
-) -> Result<(StatusCode, Json<AppResponse<String>>), AppError> {
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<AppResponse<MFile>>), AppError> {
     log::info!("status: {}", _state.status);
 
     let _is_valid = match m_file_request.validate() {
@@ -240,38 +362,427 @@ pub async fn update(
         }
     };
 
-    let mut _new_m_file = <MFile>::new(String::new(), String::new(), String::new());
-    let existing_biodata_result = repository::find_by_id(&mut db_conn, m_file_request.id.unwrap());
-    match existing_biodata_result {
-        Ok(None) => {
-            return Err(AppError::NotFound);
+    // read, check and write under the row lock
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        let existing = match repository::lock_by_id(conn, m_file_request.require_id()?)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        etag::check_if_match(&headers, &etag::of(existing.id, existing.row_version))?;
+
+        let new_value = <MFile>::from_update_request(m_file_request, existing);
+        match repository::update_mfile(conn, new_value.clone())? {
+            Some(_) => Ok(new_value),
+            None => Err(AppError::PreconditionFailed("resource was modified".to_string())),
         }
-        Ok(Some(value)) => {
-            _new_m_file = <MFile>::from_update_request(m_file_request, value);
+    });
+
+    match result {
+        Ok(mut _new_m_file) => {
+            _new_m_file.row_version += 1;
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                [(ETAG, etag::of(_new_m_file.id, _new_m_file.row_version))],
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(_new_m_file),
+                    error: None,
+                }),
+            ));
         }
         Err(err) => {
             return Err(err);
         }
+    }
+}
+
+/// Create many file rows, see `BulkMode` for how a failing item is handled.
+pub async fn bulk_create(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_options): Query<BulkOptions>,
+    Json(m_file_requests): Json<Vec<MFileRequest>>,
+) -> Result<(StatusCode, Json<AppResponse<Vec<BulkItemResult>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let (results, committed) = bulk::run(
+        &mut db_conn,
+        _options.mode.unwrap_or_default(),
+        m_file_requests,
+        StatusCode::CREATED,
+        service::create_mfile,
+    );
+    Ok(bulk::into_response(results, committed))
+}
+
+/// Update many file rows, the id of every item comes from its body.
+pub async fn bulk_update(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_options): Query<BulkOptions>,
+    Json(m_file_requests): Json<Vec<MFileRequest>>,
+) -> Result<(StatusCode, Json<AppResponse<Vec<BulkItemResult>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let (results, committed) = bulk::run(
+        &mut db_conn,
+        _options.mode.unwrap_or_default(),
+        m_file_requests,
+        StatusCode::OK,
+        service::update_mfile,
+    );
+    Ok(bulk::into_response(results, committed))
+}
+
+/// Soft delete many file rows by id.
+pub async fn bulk_delete(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_options): Query<BulkOptions>,
+    Json(delete_items): Json<Vec<BulkDeleteItem>>,
+) -> Result<(StatusCode, Json<AppResponse<Vec<BulkItemResult>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
     };
 
-    let result = repository::update_mfile(&mut db_conn, _new_m_file);
+    let (results, committed) = bulk::run(
+        &mut db_conn,
+        _options.mode.unwrap_or_default(),
+        delete_items,
+        StatusCode::OK,
+        service::soft_delete_mfile,
+    );
+    Ok(bulk::into_response(results, committed))
+}
+
+/// Partial update with an RFC 7386 merge patch. Only the supplied members
+/// change, validation runs on the merged result.
+pub async fn patch(
+    Path(id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<AppResponse<MFile>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if !patch.is_object() {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "body",
+            ValidationError::new("object").with_message("must be a JSON object".into()),
+        );
+        return Err(AppError::InvalidRequest(errors));
+    }
 
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {id}")));
+        }
+    };
+
+    // read, merge, check and write under the row lock
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        let existing = match repository::lock_by_id(conn, id)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        etag::check_if_match(&headers, &etag::of(existing.id, existing.row_version))?;
+
+        // merge onto the stored row, the id always comes from the path
+        let mut document = serde_json::to_value(MFileRequest::from_existing(&existing))
+            .map_err(|error| AppError::Other(format!("serialize failed: {}, id: {}", error, id)))?;
+        merge_patch::apply(&mut document, &patch);
+        document["id"] = serde_json::json!(id);
+        let m_file_request: MFileRequest = match serde_json::from_value(document) {
+            Ok(value) => value,
+            Err(error) => {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "body",
+                    ValidationError::new("type").with_message(error.to_string().into()),
+                );
+                return Err(AppError::InvalidRequest(errors));
+            }
+        };
+        if let Err(error) = m_file_request.validate() {
+            return Err(AppError::InvalidRequest(error));
+        }
+
+        let new_value = <MFile>::from_update_request(m_file_request, existing);
+        match repository::update_mfile(conn, new_value.clone())? {
+            Some(_) => Ok(new_value),
+            None => Err(AppError::PreconditionFailed("resource was modified".to_string())),
+        }
+    });
     match result {
-        Ok(Some(_)) => {
+        Ok(mut new_m_file) => {
+            new_m_file.row_version += 1;
             let status_code = StatusCode::OK;
             return Ok((
                 status_code,
+                [(ETAG, etag::of(new_m_file.id, new_m_file.row_version))],
                 Json(AppResponse {
                     status: status_code.as_str().to_string(),
                     message: "success".to_owned(),
                     timestamp: chrono::Utc::now().naive_utc(),
-                    data: None,
+                    data: Some(new_m_file),
                     error: None,
                 }),
             ));
         }
-        Ok(None) => {
-            return Err(AppError::Other(format!("save data failed")).into());
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+/// Every row matching `_filter`, `_sort` and `_q` of `find_page`, as CSV
+/// streamed page by page or as an XLSX workbook.
+pub async fn export(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_sort): Query<Sorts>,
+    Query(_filter): Query<Filters>,
+    Query(_global_search): Query<Search>,
+    Query(_deleted): Query<IncludeDeleted>,
+    Query(_export): Query<Export>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(err) = _filter.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+    if let Err(err) = _sort.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+
+    let format = export::resolve_format(_export.format, &headers);
+    let columns = export::resolve_columns(_export._columns, EXPORT_COLUMNS)?;
+    let _filters = _filter._filter.unwrap_or_default();
+    let _sorts = export::stable_sorts(_sort._sort.unwrap_or_default());
+    let _q = _global_search._q.unwrap_or_default();
+    let include_deleted = _deleted.include_deleted.unwrap_or(false);
+    log::info!(
+        "format {:?}, columns {:?}, filters {:?}, sorts {:?}, global_search {:?}",
+        format,
+        columns,
+        _filters,
+        _sorts,
+        _q
+    );
+
+    // get db connection
+    let db_conn = match _state.diesel_pool_mysql.get() {
+        Ok(value) => value,
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let select = projection::select_list(&columns, &COLUMNS);
+    let fetch_page = move |conn: &mut MysqlConnection, page: i64| {
+        let (content, _) = repository::pagination(
+            conn,
+            page,
+            export::PAGE_SIZE,
+            _filters.clone(),
+            _sorts.clone(),
+            _q.clone(),
+            include_deleted,
+            &select,
+        )?;
+        export::to_rows(content)
+    };
+    match format {
+        ExportFormat::Csv => {
+            let body = export::stream_csv(db_conn, columns, fetch_page);
+            Ok(export::response(format, "m-file", Body::from_stream(body)))
+        }
+        ExportFormat::Xlsx => {
+            let body = export::stream_xlsx(db_conn, columns, fetch_page).await?;
+            Ok(export::response(format, "m-file", Body::from_stream(body)))
+        }
+    }
+}
+
+/// Grouped counts and aggregates over the rows matching `_filter`, e.g.
+/// `_group_by=[{"id":"created_on","bucket":"DAY"}]`.
+pub async fn aggregate(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_aggregation): Query<Aggregation>,
+    Query(_filter): Query<Filters>,
+    Query(_deleted): Query<IncludeDeleted>,
+) -> Result<(StatusCode, Json<AppResponse<AggregateResponse>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(err) = _filter.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+    let _filters = _filter._filter.unwrap_or_default();
+    log::info!("aggregation {:?}, filters {:?}", _aggregation, _filters);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let result = repository::aggregate(
+        &mut db_conn,
+        _aggregation,
+        _filters,
+        _deleted.include_deleted.unwrap_or(false),
+    );
+    match result {
+        Ok(value) => {
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(value),
+                    error: None,
+                }),
+            ));
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+/// Full-text search over `SEARCH_COLUMNS`, ranked by relevance with the
+/// matching words highlighted.
+pub async fn search(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_search): Query<FullTextSearch>,
+    Query(_pagination): Query<Pagination>,
+    Query(_deleted): Query<IncludeDeleted>,
+    Query(_fields): Query<Fields>,
+) -> Result<(StatusCode, Json<AppResponse<PaginatedResponse<SearchHit>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(err) = _pagination.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+    let terms = full_text::terms(_search._q)?;
+    let mode = _search.mode.unwrap_or_default();
+    let fields = projection::resolve(_fields._fields, &COLUMNS, true)?;
+    // scoring and highlighting need the search columns whatever `_fields` asks for
+    let select = projection::select_list(&projection::with_required(&fields, SEARCH_COLUMNS), &COLUMNS);
+    let include_deleted = _deleted.include_deleted.unwrap_or(false);
+
+    let mut _page = _pagination.page.unwrap_or(0);
+    if _page < 0 {
+        _page = 0;
+    }
+    let mut _size = _pagination.size.unwrap_or(5);
+    if _size < 1 {
+        _size = 1;
+    }
+    log::info!("page {:?}, size {:?}, terms {:?}, mode {:?}", _page, _size, terms, mode);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let result = full_text::page(
+        &mut db_conn,
+        mode,
+        _page,
+        _size,
+        &terms,
+        SEARCH_COLUMNS,
+        |conn: &mut MysqlConnection, limit, offset| {
+            repository::search(conn, &terms, mode, include_deleted, &select, limit, offset)
+        },
+    );
+    match result {
+        Ok(value) => {
+            let mut total_of_pages = value.1 / _size;
+            if value.1 % _size != 0 {
+                total_of_pages = total_of_pages + 1;
+            }
+
+            let mut content = Vec::new();
+            for (item, score) in &value.0 {
+                content.push(full_text::hit(item, *score, &fields, SEARCH_COLUMNS, &terms, mode)?);
+            }
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(PaginatedResponse {
+                        content,
+                        total_of_elements: value.1,
+                        total_of_pages: total_of_pages,
+                    }),
+                    error: None,
+                }),
+            ));
         }
         Err(err) => {
             return Err(err);
@@ -285,7 +796,9 @@ pub async fn find_page(
     Query(_sort): Query<Sorts>,
     Query(_filter): Query<Filters>,
     Query(_global_search): Query<Search>,
-) -> Result<(StatusCode, Json<AppResponse<PaginatedResponse<MFile>>>), AppError> {
+    Query(_deleted): Query<IncludeDeleted>,
+    Query(_fields): Query<Fields>,
+) -> Result<(StatusCode, Json<AppResponse<PaginatedResponse<Value>>>), AppError> {
     log::info!("status: {}", _state.status);
 
     if let Err(err) = _pagination.validate() {
@@ -298,6 +811,8 @@ pub async fn find_page(
         return Err(AppError::InvalidRequest(err).into());
     };
 
+    let fields = projection::resolve(_fields._fields, &COLUMNS, true)?;
+
     let mut _page = _pagination.page.unwrap_or(0);
     if _page < 0 {
         _page = 0;
@@ -330,7 +845,16 @@ pub async fn find_page(
         }
     };
 
-    let result = repository::pagination(&mut db_conn, _page, _size, _filters, _sorts, _q);
+    let result = repository::pagination(
+        &mut db_conn,
+        _page,
+        _size,
+        _filters,
+        _sorts,
+        _q,
+        _deleted.include_deleted.unwrap_or(false),
+        &projection::select_list(&fields, &COLUMNS),
+    );
     match result {
         Ok(value) => {
             let mut total_of_pages = value.1 / _size;
@@ -340,7 +864,7 @@ pub async fn find_page(
 
             let status_code = StatusCode::OK;
             let paginated_response = PaginatedResponse {
-                content: value.0,
+                content: projection::to_values(&value.0, &fields)?,
                 total_of_elements: value.1,
                 total_of_pages: total_of_pages,
             };
@@ -364,45 +888,14 @@ pub async fn find_page(
 pub async fn upload(
     Extension(_state): Extension<Arc<AppState>>,
     mut multipart: Multipart,
-) -> Result<(StatusCode, Json<AppResponse<MFile>>), AppError> {
-    let mut data: Bytes = <Bytes>::new();
-    let mut file_name: String = String::new();
-    let mut file_type: String = String::new();
-
-    while let Some(field) = multipart.next_field().await.unwrap() {
-        let name = field.name().unwrap().to_string();
-
-        if name == "file".to_string() {
-            file_name = field.file_name().unwrap_or("").to_string();
-            file_type = field.content_type().unwrap_or("").to_string();
-            data = field.bytes().await.unwrap();
-
-            let category = match file_type.as_str() {
-                // Image types
-                "image/jpeg" | "image/png" | "image/gif" => FileType::IMAGE,
-                // Audio types
-                "audio/mpeg" | "audio/wav" | "audio/ogg" => FileType::AUDIO,
-                // Video types
-                "video/mp4" | "video/x-msvideo" | "video/x-flv" => FileType::VIDEO,
-                // Document types
-                "application/pdf"
-                | "application/msword"
-                | "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
-                    FileType::DOCUMENT
-                }
-                // Default case for unknown types
-                _ => FileType::UNKNOWN,
-            };
-            file_type = category.to_string();
-
-            continue;
+) -> Result<(StatusCode, [(HeaderName, String); 2], Json<AppResponse<MFile>>), AppError> {
+    let uploaded_file = match service::read_upload(&mut multipart).await? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::Other(format!("file is mandatory")).into());
         }
-        if name == "token".to_string() {
-            let token = field.text().await.unwrap();
-            log::info!("Token received: {}", token);
-            continue;
-        }
-    }
+    };
+    let file_type = FileType::from_content_type(&uploaded_file.content_type).to_string();
 
     // get db connection
     let db_conn_result = _state.diesel_pool_mysql.get();
@@ -416,59 +909,100 @@ pub async fn upload(
         }
     };
 
-    let today = Local::now();
-    let date_string = format!(
-        "data/{}/{}/{:02}/{:02}",
+    let new_m_file = service::create_file(
+        &_state,
+        &mut db_conn,
+        uploaded_file.file_name,
         file_type,
-        today.year(),
-        today.month(),
-        today.day()
-    );
-
-    let file_path = format!("{}/{}", date_string, file_name);
+        &uploaded_file.file_hash,
+        uploaded_file.data,
+    )
+    .await?;
 
-    // check existing file
-    let result_file_exist = std::fs::exists(file_path.clone());
-    if result_file_exist.is_err() {
-        return Err(AppError::InternalServerError);
-    }
-    if result_file_exist.unwrap() {
-        log::info!("file exist");
-        return Err(AppError::DataExist);
-    }
+    let status_code = StatusCode::CREATED;
+    Ok((
+        status_code,
+        [
+            (LOCATION, format!("/m-file/{}", new_m_file.id)),
+            (ETAG, etag::of(new_m_file.id, new_m_file.row_version)),
+        ],
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(new_m_file),
+            error: None,
+        }),
+    ))
+}
 
-    let status_create_dir = std::fs::create_dir_all(date_string);
-    if status_create_dir.is_err() {
-        log::info!("create dir failed");
-        return Err(AppError::InternalServerError);
-    }
+pub async fn scan(
+    Path(id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MFile>>), AppError> {
+    log::info!("status: {}", _state.status);
 
-    // create file
-    let file = File::create(&file_path).await.map_err(|e| {
-        log::error!("Failed to create file: {}", e);
-    });
-    if file.is_err() {
-        return Err(AppError::InternalServerError);
-    }
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {id}")));
+        }
+    };
+    let m_file = match repository::find_by_id(&mut db_conn, id, false)? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
 
-    // save data to file
-    let status_write_data = file.unwrap().write_all(&data).await.map_err(|e| {
-        log::error!("Failed to write to file: {}", e);
-    });
-    if status_write_data.is_err() {
-        return Err(AppError::InternalServerError);
-    }
+    let scanned_m_file = service::rescan_file(&_state, &mut db_conn, m_file).await?;
 
-    let new_m_file = MFile::new(file_name, file_type, file_path);
+    let status_code = StatusCode::OK;
+    Ok((
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(scanned_m_file),
+            error: None,
+        }),
+    ))
+}
 
-    let result = repository::insert_mfile(&mut db_conn, new_m_file.clone());
+pub async fn signed_url(
+    Path(id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MFileSignedUrl>>), AppError> {
+    log::info!("status: {}", _state.status);
 
-    match result {
-        Ok(_) => {}
-        Err(value) => {
-            return Err(value);
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {id}")).into());
         }
     };
+    match repository::find_by_id(&mut db_conn, id, false)? {
+        Some(value) => service::ensure_available(&value)?,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    }
+
+    let expires_on =
+        chrono::Utc::now().naive_utc() + chrono::Duration::seconds(CONFIG.storage_url_expiration);
+    let expires = expires_on.and_utc().timestamp();
+    let signature = signed_url::sign(&format!("m_file/{}", id), expires);
 
     let status_code = StatusCode::OK;
     Ok((
@@ -477,16 +1011,25 @@ pub async fn upload(
             status: status_code.as_str().to_string(),
             message: "success".to_owned(),
             timestamp: chrono::Utc::now().naive_utc(),
-            data: Some(new_m_file),
+            data: Some(MFileSignedUrl {
+                url: format!(
+                    "/m-file/download?id={}&expires={}&signature={}",
+                    id, expires, signature
+                ),
+                expires_on,
+            }),
             error: None,
         }),
     ))
 }
 
 pub async fn download(
-    Query(id): Query<i64>,
+    Query(request): Query<MFileDownloadRequest>,
     Extension(_state): Extension<Arc<AppState>>,
 ) -> impl IntoResponse {
+    let id = request.id;
+    signed_url::ensure_valid(&format!("m_file/{}", id), request.expires, &request.signature)?;
+
     // find path file by id
     let mut _file_path_string = String::new();
     let mut _file_name = String::new();
@@ -501,9 +1044,10 @@ pub async fn download(
             return Err(AppError::Other(format!("get connection failed {error}, id: {id}")).into());
         }
     };
-    let find_by_id_result = repository::find_by_id(&mut db_conn, id);
+    let find_by_id_result = repository::find_by_id(&mut db_conn, id, false);
     match find_by_id_result {
         Ok(Some(value)) => {
+            service::ensure_available(&value)?;
             _file_path_string = value.file_path.unwrap_or(String::new());
             _file_name = value.file_name.unwrap_or(String::new());
         }
@@ -515,15 +1059,8 @@ pub async fn download(
         }
     };
 
-    let file_path = PathBuf::from(_file_path_string);
-
-    let open_file_response = match File::open(&file_path).await {
-        Ok(mut file) => {
-            let mut contents = Vec::new();
-            if let Err(_) = file.read_to_end(&mut contents).await {
-                return Err(AppError::Other(format!("failed to read file")).into());
-            }
-
+    let open_file_response = match _state.storage.get(&_file_path_string).await {
+        Ok(Some(contents)) => {
             let response_builder: axum::http::Response<Body> = axum::http::Response::builder()
                 .header(
                     "Content-Disposition",
@@ -535,8 +1072,47 @@ pub async fn download(
 
             Ok(response_builder)
         }
-        Err(_) => Err(AppError::NotFound),
+        Ok(None) => Err(AppError::NotFound),
+        Err(error) => Err(error),
     };
 
     return open_file_response;
 }
+
+pub async fn archive(
+    Extension(_state): Extension<Arc<AppState>>,
+    Json(archive_request): Json<MFileArchiveRequest>,
+) -> Result<Response<Body>, AppError> {
+    log::info!("status: {}", _state.status);
+
+    let source = match archive_request.ids {
+        Some(ids) => archive::ArchiveSource::Ids(ids),
+        None => archive::ArchiveSource::Query {
+            filters: archive_request._filter.unwrap_or_default(),
+            sorts: archive_request._sort.unwrap_or_default(),
+            search: archive_request._q.unwrap_or_default(),
+        },
+    };
+    // rows are fetched page by page while the archive is written, the first
+    // page here tells whether there is anything to send at all
+    let mut page = 0;
+    let (mut first_page, mut has_more) = archive::fetch_page(&_state, &source, page)?;
+    while first_page.is_empty() && has_more {
+        page += 1;
+        (first_page, has_more) = archive::fetch_page(&_state, &source, page)?;
+    }
+    if first_page.is_empty() {
+        return Err(AppError::NotFound);
+    }
+
+    let body = archive::stream(_state.clone(), source, page, first_page, has_more);
+    let file_name = format!("files-{}.zip", Local::now().format("%Y%m%d%H%M%S"));
+    Ok(Response::builder()
+        .header("Content-Type", "application/zip")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", file_name),
+        )
+        .body(Body::from_stream(body))
+        .unwrap())
+}
@@ -0,0 +1,63 @@
+use std::path::{Component, Path};
+
+use diesel::{prelude::*, sql_query};
+
+use crate::{config::environment::CONFIG, dto::response::app_error::AppError};
+
+/// `STORAGE_LOCAL_ROOT` as the path prefix files uploaded before the storage
+/// backend were saved under, relative to the working directory like those
+/// paths are. `None` when no legacy path can start with it.
+fn legacy_prefix() -> Option<String> {
+    let root = Path::new(&CONFIG.storage_local_root);
+    let relative = if root.is_absolute() {
+        root.strip_prefix(std::env::current_dir().ok()?).ok()?
+    } else {
+        root
+    };
+
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(value) => parts.push(value.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    Some(format!("{}/", parts.join("/")))
+}
+
+/// Rewrite the paths of files uploaded before the storage backend into
+/// storage keys relative to `STORAGE_LOCAL_ROOT`, recording each rewritten
+/// row in `m_file_legacy_path`. Rows already rewritten are left alone, so it
+/// runs on every start. Returns the number of rows rewritten.
+pub fn migrate(conn: &mut MysqlConnection) -> Result<usize, AppError> {
+    let prefix = match legacy_prefix() {
+        Some(value) => value,
+        None => return Ok(0),
+    };
+    let prefix_length = prefix.chars().count() as i32;
+    let date_now = chrono::Utc::now().naive_utc();
+
+    // legacy rows are the ones stored before deduplication, without a hash
+    let record_query = "INSERT IGNORE INTO m_file_legacy_path (file_id, legacy_path, file_path, migrated_on)
+            SELECT id, file_path, SUBSTRING(file_path, ?), ? FROM m_file
+            WHERE file_hash IS NULL AND LEFT(file_path, ?) = ?";
+    let rewrite_query = "UPDATE m_file
+            JOIN m_file_legacy_path ON m_file_legacy_path.file_id = m_file.id
+            SET m_file.file_path = m_file_legacy_path.file_path
+            WHERE m_file.file_path = m_file_legacy_path.legacy_path";
+
+    conn.transaction(|conn| {
+        sql_query(record_query)
+            .bind::<diesel::sql_types::Integer, _>(prefix_length + 1)
+            .bind::<diesel::sql_types::Datetime, _>(date_now)
+            .bind::<diesel::sql_types::Integer, _>(prefix_length)
+            .bind::<diesel::sql_types::Varchar, _>(&prefix)
+            .execute(conn)?;
+        sql_query(rewrite_query).execute(conn)
+    })
+    .map_err(|error: diesel::result::Error| AppError::Other(format!("query failed: {}", error)))
+}
@@ -1,4 +1,8 @@
 pub mod schema;
 pub mod router;
 pub mod controller;
-pub mod repository;
\ No newline at end of file
+pub mod repository;
+pub mod service;
+pub mod archive;
+pub mod reconcile;
+pub mod legacy_path;
\ No newline at end of file
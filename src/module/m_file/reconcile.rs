@@ -0,0 +1,187 @@
+use std::{collections::HashSet, path::Path, sync::Arc, time::Duration};
+
+use serde::Serialize;
+
+use crate::{
+    config::environment::CONFIG,
+    dto::{
+        enumerator::{reconcile_action::ReconcileAction, storage_type::StorageType},
+        response::app_error::AppError,
+    },
+    module::m_file::repository,
+    state::AppState,
+};
+
+/// Prefix orphaned objects are moved under when quarantined.
+pub const QUARANTINE_PREFIX: &str = "quarantine/";
+
+/// Differences found between the storage backend and the `m_file` tables.
+#[derive(Debug, Serialize)]
+pub struct ReconcileReport {
+    pub action: ReconcileAction,
+    /// stored objects no `m_file` or `m_file_blob` row points at
+    pub orphan_keys: Vec<String>,
+    /// blobs whose content is gone
+    pub missing_blobs: Vec<String>,
+    /// `m_file` rows whose content is gone
+    pub missing_files: Vec<i64>,
+}
+
+/// The database backend only keeps content on existing `m_file_blob` rows, an
+/// object cannot be moved under `QUARANTINE_PREFIX` there.
+pub fn ensure_supported(action: ReconcileAction) -> Result<(), AppError> {
+    if action == ReconcileAction::Quarantine && CONFIG.storage_type == StorageType::Database {
+        return Err(AppError::Other(format!(
+            "reconcile action quarantine is not supported by the database storage"
+        )));
+    }
+    Ok(())
+}
+
+/// Refuse to start when `STORAGE_RECONCILE_ACTION` cannot run on the
+/// configured storage, the scheduled job would fail on every orphan.
+pub fn initialize() {
+    if CONFIG.storage_reconcile_interval == 0 {
+        return;
+    }
+    if let Err(error) = ensure_supported(CONFIG.storage_reconcile_action) {
+        panic!("{:?}", error);
+    }
+}
+
+/// Compare the stored objects with the rows pointing at them and apply
+/// `action` to the orphaned objects. Rows without content are only reported.
+pub async fn reconcile(state: &AppState, action: ReconcileAction) -> Result<ReconcileReport, AppError> {
+    ensure_supported(action)?;
+    let upload_prefix = upload_dir_prefix();
+    let stored_keys: HashSet<String> = state
+        .storage
+        .list("")
+        .await?
+        .into_iter()
+        .filter(|key| !key.starts_with(QUARANTINE_PREFIX))
+        .filter(|key| match &upload_prefix {
+            Some(prefix) => !key.starts_with(prefix.as_str()),
+            None => true,
+        })
+        .collect();
+
+    // rows are read after listing: a blob row is always written before its
+    // content, so an object stored meanwhile is never mistaken for an orphan
+    let (file_paths, blob_paths) = {
+        let mut db_conn = state
+            .diesel_pool_mysql
+            .get()
+            .map_err(|error| AppError::Other(format!("get connection failed {error}")))?;
+        (
+            repository::find_all_paths(&mut db_conn)?,
+            repository::find_all_blob_paths(&mut db_conn)?,
+        )
+    };
+
+    let mut known_keys: HashSet<&str> = HashSet::new();
+    let mut missing_blobs = Vec::new();
+    for (file_hash, file_path) in &blob_paths {
+        known_keys.insert(file_path);
+        if !stored_keys.contains(file_path) {
+            missing_blobs.push(file_hash.clone());
+        }
+    }
+
+    let missing_hashes: HashSet<&String> = missing_blobs.iter().collect();
+    let mut missing_files = Vec::new();
+    for (file_id, file_hash, file_path) in &file_paths {
+        if let Some(path) = file_path {
+            known_keys.insert(path);
+        }
+        let is_missing = match (file_hash, file_path) {
+            (Some(hash), _) => missing_hashes.contains(hash),
+            (None, Some(path)) => !stored_keys.contains(path),
+            (None, None) => false,
+        };
+        if is_missing {
+            missing_files.push(*file_id);
+        }
+    }
+
+    let mut orphan_keys: Vec<String> = stored_keys
+        .iter()
+        .filter(|key| !known_keys.contains(key.as_str()))
+        .cloned()
+        .collect();
+    orphan_keys.sort();
+
+    for key in &orphan_keys {
+        match action {
+            ReconcileAction::Report => {}
+            ReconcileAction::Delete => state.storage.delete(key).await?,
+            ReconcileAction::Quarantine => {
+                if let Some(contents) = state.storage.get(key).await? {
+                    state
+                        .storage
+                        .put(&format!("{}{}", QUARANTINE_PREFIX, key), contents)
+                        .await?;
+                }
+                state.storage.delete(key).await?;
+            }
+        }
+    }
+
+    Ok(ReconcileReport {
+        action,
+        orphan_keys,
+        missing_blobs,
+        missing_files,
+    })
+}
+
+/// The tus upload directory as a storage key prefix, when it lives inside
+/// the local storage root. Partial uploads there are not orphans.
+fn upload_dir_prefix() -> Option<String> {
+    let relative = Path::new(&CONFIG.storage_upload_dir)
+        .strip_prefix(&CONFIG.storage_local_root)
+        .ok()?;
+    let prefix = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some(format!("{}/", prefix))
+}
+
+pub fn log_report(report: &ReconcileReport) {
+    for key in &report.orphan_keys {
+        log::warn!("orphan object: {} ({:?})", key, report.action);
+    }
+    for file_hash in &report.missing_blobs {
+        log::warn!("blob without content, hash: {}", file_hash);
+    }
+    for file_id in &report.missing_files {
+        log::warn!("file without content, id: {}", file_id);
+    }
+    log::info!(
+        "reconcile done, orphan objects: {}, missing blobs: {}, missing files: {}",
+        report.orphan_keys.len(),
+        report.missing_blobs.len(),
+        report.missing_files.len()
+    );
+}
+
+/// Periodically reconcile storage with `STORAGE_RECONCILE_ACTION`. A zero
+/// `STORAGE_RECONCILE_INTERVAL` disables the job.
+pub async fn run_schedule(state: Arc<AppState>) {
+    if CONFIG.storage_reconcile_interval == 0 {
+        return;
+    }
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(CONFIG.storage_reconcile_interval));
+    // the first tick completes immediately, keep startup free of a full scan
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        match reconcile(&state, CONFIG.storage_reconcile_action).await {
+            Ok(report) => log_report(&report),
+            Err(error) => log::error!("reconcile failed: {:?}", error),
+        }
+    }
+}
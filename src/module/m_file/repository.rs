@@ -1,20 +1,27 @@
 use diesel::{dsl::insert_into, prelude::*, sql_query, update};
 
 use crate::{
-    diesel_schema::m_file::dsl::*,
+    diesel_schema::{m_file::dsl::*, m_file_blob},
     dto::{
-        database::CountResult, enumerator::filter_match_mode::FilterMatchMode, request::{filter_request::Filter, sort_request::Sort}, response::app_error::AppError
+        database::{last_insert_id, CountResult, JsonRow, SearchRow}, enumerator::{audit_action::AuditAction, filter_match_mode::FilterMatchMode, search_mode::SearchMode}, request::{aggregate_request::Aggregation, filter_request::Filter, sort_request::Sort}, response::{aggregate_response::AggregateResponse, app_error::AppError}
     },
-    module::m_file::schema::MFile,
-    util::string_manipulation,
+    module::{
+        audit_log::service as audit_log_service,
+        m_file::schema::{MFile, MFileBlob, AGGREGATE_COLUMNS, AUDIT_ENTITY, COLUMNS, SEARCH_COLUMNS},
+    },
+    util::{aggregate, projection, search, string_manipulation},
 };
 
 pub fn find_by_id(
     conn: &mut MysqlConnection,
     mfile_id: i64,
+    include_deleted: bool,
 ) -> Result<Option<MFile>, AppError> {
-    let user = m_file
-        .filter(id.eq(mfile_id))
+    let mut query = m_file.filter(id.eq(mfile_id)).into_boxed();
+    if !include_deleted {
+        query = query.filter(is_delete.eq(false));
+    }
+    let user = query
         .select(MFile::as_select())
         .first::<MFile>(conn)
         .optional()
@@ -23,62 +30,187 @@ pub fn find_by_id(
     Ok(user)
 }
 
+/// The row locked until the surrounding transaction ends, for check-and-write
+/// sequences run through `unit_of_work`.
+pub fn lock_by_id(conn: &mut MysqlConnection, mfile_id: i64) -> Result<Option<MFile>, AppError> {
+    let locked = m_file
+        .filter(id.eq(mfile_id))
+        .filter(is_delete.eq(false))
+        .select(MFile::as_select())
+        .for_update()
+        .first::<MFile>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+    Ok(locked)
+}
+
 pub fn find_query_by_id(
     conn: &mut MysqlConnection,
     mfile_id: i64,
+    include_deleted: bool,
+    select: &str,
 ) -> Result<Option<MFile>, AppError> {
-    let query = "SELECT * 
+    let query = format!(
+        "SELECT {}
             FROM m_file 
-            WHERE id = ?";
+            WHERE id = ? AND (? OR is_delete = 0)",
+        select
+    );
 
     let user: Option<MFile> = sql_query(query)
         .bind::<diesel::sql_types::BigInt, _>(mfile_id)
+        .bind::<diesel::sql_types::Bool, _>(include_deleted)
         .get_result::<MFile>(conn)
         .optional()
         .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
     Ok(user)
 }
 
-pub fn find_all(conn: &mut MysqlConnection) -> Result<Vec<MFile>, AppError> {
-    let query = "SELECT * 
-            FROM m_file";
+pub fn find_all(
+    conn: &mut MysqlConnection,
+    include_deleted: bool,
+    select: &str,
+) -> Result<Vec<MFile>, AppError> {
+    let query = format!(
+        "SELECT {}
+            FROM m_file
+            WHERE ? OR is_delete = 0",
+        select
+    );
 
     let user: Vec<MFile> = sql_query(query)
+        .bind::<diesel::sql_types::Bool, _>(include_deleted)
         .get_results::<MFile>(conn)
         .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
     Ok(user)
 }
 
+pub fn find_by_ids(conn: &mut MysqlConnection, mfile_ids: &[i64]) -> Result<Vec<MFile>, AppError> {
+    let files = m_file
+        .filter(id.eq_any(mfile_ids))
+        .filter(is_delete.eq(false))
+        .select(MFile::as_select())
+        .load::<MFile>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+
+    Ok(files)
+}
+
+/// Mark the row deleted, it stays available through `include_deleted`.
+pub fn soft_delete_by_id(conn: &mut MysqlConnection, mfile_id: i64) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_audited(conn, mfile_id)?;
+        let rows_affected = update(m_file.filter(id.eq(mfile_id)).filter(is_delete.eq(false)))
+            .set((
+                is_delete.eq(true),
+                deleted_by.eq(Some(0)),
+                deleted_on.eq(Some(chrono::Utc::now().naive_utc())),
+                row_version.eq(row_version + 1),
+            ))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+        if rows_affected > 0 {
+            audit(conn, mfile_id, AuditAction::DELETE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+pub fn restore_by_id(conn: &mut MysqlConnection, mfile_id: i64) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_audited(conn, mfile_id)?;
+        let rows_affected = update(m_file.filter(id.eq(mfile_id)).filter(is_delete.eq(true)))
+            .set((
+                is_delete.eq(false),
+                deleted_by.eq(None::<i64>),
+                deleted_on.eq(None::<chrono::NaiveDateTime>),
+                modified_by.eq(Some(0)),
+                modified_on.eq(Some(chrono::Utc::now().naive_utc())),
+                row_version.eq(row_version + 1),
+            ))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+        if rows_affected > 0 {
+            audit(conn, mfile_id, AuditAction::RESTORE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+/// Ids of rows soft deleted before `cutoff`.
+pub fn find_deleted_before(
+    conn: &mut MysqlConnection,
+    cutoff: chrono::NaiveDateTime,
+) -> Result<Vec<i64>, AppError> {
+    let ids = m_file
+        .filter(is_delete.eq(true))
+        .filter(deleted_on.lt(cutoff))
+        .select(id)
+        .load::<i64>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+
+    Ok(ids)
+}
+
+/// Permanently remove the row.
 pub fn delete_by_id(conn: &mut MysqlConnection, mfile_id: i64) -> Result<Option<()>, AppError> {
-    let rows_affected = diesel::delete(m_file.filter(id.eq(mfile_id)))
-        .execute(conn)
-        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+    conn.transaction(|conn| {
+        let before = find_audited(conn, mfile_id)?;
+        let rows_affected = diesel::delete(m_file.filter(id.eq(mfile_id)))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
 
-    if rows_affected > 0 {
-        return Ok(Some(()));
-    }
-    return Ok(None);
+        if rows_affected > 0 {
+            audit(conn, mfile_id, AuditAction::PURGE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
 }
 
+/// Returns the id of the new row, assigned by the database when `id` is `0`.
 pub fn insert_mfile(
     conn: &mut MysqlConnection,
     mfile: MFile,
-) -> Result<Option<()>, AppError> {
-    let rows_affected = insert_into(m_file)
-        .values(&mfile)
-        .execute(conn)
-        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
-    if rows_affected > 0 {
-        return Ok(Some(()));
-    }
-    return Ok(None);
+) -> Result<Option<i64>, AppError> {
+    conn.transaction(|conn| {
+        let rows_affected = insert_into(m_file)
+            .values(&mfile)
+            .execute(conn)
+            // a duplicate key maps to DataExist
+            .map_err(AppError::from)?;
+        if rows_affected == 0 {
+            return Ok(None);
+        }
+        let mut new_id = mfile.id;
+        if new_id == 0 {
+            new_id = diesel::select(last_insert_id())
+                .get_result::<u64>(conn)
+                .map_err(|error| AppError::Other(format!("query failed: {}", error)))? as i64;
+        }
+
+        audit(conn, new_id, AuditAction::CREATE, None)?;
+        return Ok(Some(new_id));
+    })
 }
 
+/// Returns `None` when the row is gone or was updated since it was read.
 pub fn update_mfile(
     conn: &mut MysqlConnection,
     mfile: MFile,
 ) -> Result<Option<()>, AppError> {
-    let rows_affected = update(m_file.filter(id.eq(mfile.id)))
+    conn.transaction(|conn| {
+        let before = find_audited(conn, mfile.id)?;
+        let rows_affected = update(
+            m_file
+                .filter(id.eq(mfile.id))
+                .filter(row_version.eq(mfile.row_version)),
+        )
         .set((
             modified_by.eq(mfile.modified_by),
             modified_on.eq(mfile.modified_on),
@@ -89,15 +221,140 @@ pub fn update_mfile(
             file_type.eq(mfile.file_type),
             file.eq(mfile.file),
             file_path.eq(mfile.file_path),
+            file_hash.eq(mfile.file_hash),
+            file_size.eq(mfile.file_size),
+            scan_status.eq(mfile.scan_status),
+            row_version.eq(row_version + 1),
         ))
         .execute(conn)
-        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile.id)))?;
-    if rows_affected > 0 {
-        return Ok(Some(()));
-    }
-    return Ok(None);
+        // a duplicate key maps to DataExist
+        .map_err(AppError::from)?;
+        if rows_affected > 0 {
+            audit(conn, mfile.id, AuditAction::UPDATE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
 }
 
+pub fn update_scan_status(
+    conn: &mut MysqlConnection,
+    mfile_id: i64,
+    status: &str,
+) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_audited(conn, mfile_id)?;
+        let rows_affected = update(m_file.filter(id.eq(mfile_id)))
+            .set((scan_status.eq(status), row_version.eq(row_version + 1)))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+        if rows_affected > 0 {
+            audit(conn, mfile_id, AuditAction::UPDATE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+pub fn find_blob_by_hash(
+    conn: &mut MysqlConnection,
+    blob_hash: &str,
+) -> Result<Option<MFileBlob>, AppError> {
+    let blob = m_file_blob::table
+        .filter(m_file_blob::file_hash.eq(blob_hash))
+        .select(MFileBlob::as_select())
+        .first::<MFileBlob>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, hash: {}", error, blob_hash)))?;
+
+    Ok(blob)
+}
+
+/// Id, hash and path of a file.
+pub type FilePathRow = (i64, Option<String>, Option<String>);
+
+/// Id, hash and path of every file, without loading the content columns.
+pub fn find_all_paths(conn: &mut MysqlConnection) -> Result<Vec<FilePathRow>, AppError> {
+    let paths = m_file
+        .select((id, file_hash, file_path))
+        .load::<FilePathRow>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+
+    Ok(paths)
+}
+
+/// Hash and path of every blob, without loading the content.
+pub fn find_all_blob_paths(conn: &mut MysqlConnection) -> Result<Vec<(String, String)>, AppError> {
+    let paths = m_file_blob::table
+        .select((m_file_blob::file_hash, m_file_blob::file_path))
+        .load::<(String, String)>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+
+    Ok(paths)
+}
+
+/// Insert the blob row or, when the hash is already known, take one more
+/// reference to it.
+pub fn acquire_blob(
+    conn: &mut MysqlConnection,
+    blob_hash: &str,
+    blob_path: &str,
+    blob_size: i64,
+) -> Result<(), AppError> {
+    let date_now = chrono::Utc::now().naive_utc();
+    let query = "INSERT INTO m_file_blob (file_hash, file_path, file_size, ref_count, created_on)
+            VALUES (?, ?, ?, 1, ?)
+            ON DUPLICATE KEY UPDATE ref_count = ref_count + 1, modified_on = ?";
+
+    sql_query(query)
+        .bind::<diesel::sql_types::Varchar, _>(blob_hash)
+        .bind::<diesel::sql_types::Varchar, _>(blob_path)
+        .bind::<diesel::sql_types::BigInt, _>(blob_size)
+        .bind::<diesel::sql_types::Datetime, _>(date_now)
+        .bind::<diesel::sql_types::Datetime, _>(date_now)
+        .execute(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}, hash: {}", error, blob_hash)))?;
+    Ok(())
+}
+
+/// Drop one reference to the blob and return the remaining reference count.
+/// The row is locked while counting and removed with its last reference, so
+/// an `acquire_blob` running meanwhile either waits or inserts a new row.
+pub fn release_blob(conn: &mut MysqlConnection, blob_hash: &str) -> Result<Option<i64>, AppError> {
+    conn.transaction(|conn| {
+        let current = m_file_blob::table
+            .filter(m_file_blob::file_hash.eq(blob_hash))
+            .select(m_file_blob::ref_count)
+            .for_update()
+            .first::<i64>(conn)
+            .optional()
+            .map_err(|error| AppError::Other(format!("query failed: {}, hash: {}", error, blob_hash)))?;
+        let remaining = match current {
+            Some(value) => (value - 1).max(0),
+            None => {
+                return Ok(None);
+            }
+        };
+
+        if remaining == 0 {
+            diesel::delete(m_file_blob::table.filter(m_file_blob::file_hash.eq(blob_hash)))
+                .execute(conn)
+                .map_err(|error| AppError::Other(format!("query failed: {}, hash: {}", error, blob_hash)))?;
+            return Ok(Some(0));
+        }
+
+        update(m_file_blob::table.filter(m_file_blob::file_hash.eq(blob_hash)))
+            .set((
+                m_file_blob::ref_count.eq(remaining),
+                m_file_blob::modified_on.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, hash: {}", error, blob_hash)))?;
+        return Ok(Some(remaining));
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn pagination(
     conn: &mut MysqlConnection,
     page: i64,
@@ -105,31 +362,37 @@ pub fn pagination(
     filters: Vec<Filter>,
     sorts: Vec<Sort>,
     search: String,
+    include_deleted: bool,
+    select: &str,
 ) -> Result<(Vec<MFile>, i64), AppError> {
     // Build the query
-    let mut query = "SELECT *".to_string();
+    let mut query = format!("SELECT {}", select);
     let mut query_count = "SELECT COUNT(*) AS count".to_string();
     let query_table = "FROM m_file".to_string();
 
     // Sort
     let mut query_sort = String::new();
-    for sort in sorts {
-        query_sort = "ORDER BY".to_owned();
-        let sort_asc = if sort.desc {
-            "DESC".to_string()
-        } else {
-            "ASC".to_string()
-        };
-        let sort_id = string_manipulation::cleanse_string(&sort.id);
-        query_sort = format!("{} {} {}", query_sort, sort_id, sort_asc);
-        break;
+    let sort_keys: Vec<String> = sorts
+        .iter()
+        .map(|sort| {
+            let sort_asc = if sort.desc {
+                "DESC".to_string()
+            } else {
+                "ASC".to_string()
+            };
+            let sort_id = string_manipulation::cleanse_string(&sort.id);
+            format!("{} {}", sort_id, sort_asc)
+        })
+        .collect();
+    if !sort_keys.is_empty() {
+        query_sort = format!("ORDER BY {}", sort_keys.join(", "));
     }
 
     // Search
     let mut query_search = String::new();
     if search != String::new() {
         query_search = format!(
-            "WHERE fullname LIKE '%{}%'",
+            "WHERE file_name LIKE '%{}%'",
             string_manipulation::cleanse_string(&search)
         );
     }
@@ -173,17 +436,27 @@ pub fn pagination(
         }
     }
 
+    // Soft delete
+    let mut query_deleted = String::new();
+    if !include_deleted {
+        if query_search.trim().is_empty() && query_filter.is_empty() {
+            query_deleted = "WHERE is_delete = 0".to_string();
+        } else {
+            query_deleted = "AND is_delete = 0".to_string();
+        }
+    }
+
     // Pagination
     let query_pagination = format!("LIMIT {} OFFSET {}", size, size * (page));
 
     // Final
     query = format!(
-        "{} {} {} {} {} {}",
-        query, query_table, query_search, query_filter, query_sort, query_pagination
+        "{} {} {} {} {} {} {}",
+        query, query_table, query_search, query_filter, query_deleted, query_sort, query_pagination
     );
     query_count = format!(
-        "{} {} {} {} {}",
-        query_count, query_table, query_search, query_filter, query_sort
+        "{} {} {} {} {} {}",
+        query_count, query_table, query_search, query_filter, query_deleted, query_sort
     );
     log::info!(
         "repository > find_diesel_query_mfile_page > query: {:#?}",
@@ -202,3 +475,85 @@ pub fn pagination(
         .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
     Ok((data_vec, results[0].count))
 }
+
+/// Grouped counts and aggregates for dashboards, see `util::aggregate`.
+pub fn aggregate(
+    conn: &mut MysqlConnection,
+    aggregation: Aggregation,
+    filters: Vec<Filter>,
+    include_deleted: bool,
+) -> Result<AggregateResponse, AppError> {
+    let (query, columns) =
+        aggregate::build_query("m_file", AGGREGATE_COLUMNS, aggregation, filters, include_deleted)?;
+    log::info!("repository > aggregate_m_file > query: {:#?}", query);
+
+    let json_rows = sql_query(query)
+        .load::<JsonRow>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    let rows = aggregate::to_rows(json_rows, &columns)?;
+    Ok(AggregateResponse { columns, rows })
+}
+
+/// One page of full-text matches ranked by relevance, see `util::search`.
+pub fn search(
+    conn: &mut MysqlConnection,
+    terms: &[String],
+    mode: SearchMode,
+    include_deleted: bool,
+    select: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<(MFile, f64)>, i64), AppError> {
+    let (query, query_count) = search::queries("m_file", SEARCH_COLUMNS, mode);
+    let against = search::against(terms, mode);
+    log::info!("repository > search_m_file > query: {:#?}, against: {:#?}", query, against);
+
+    let rows = sql_query(query)
+        .bind::<diesel::sql_types::Text, _>(&against)
+        .bind::<diesel::sql_types::Text, _>(&against)
+        .bind::<diesel::sql_types::Bool, _>(include_deleted)
+        .bind::<diesel::sql_types::BigInt, _>(limit)
+        .bind::<diesel::sql_types::BigInt, _>(offset)
+        .load::<SearchRow>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    let results = sql_query(query_count)
+        .bind::<diesel::sql_types::Text, _>(&against)
+        .bind::<diesel::sql_types::Bool, _>(include_deleted)
+        .load::<CountResult>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    if rows.is_empty() {
+        return Ok((Vec::new(), results[0].count));
+    }
+
+    let query_items = format!(
+        "SELECT {}
+            FROM m_file
+            WHERE id IN ({})",
+        select,
+        search::id_list(&rows)
+    );
+    let items = sql_query(query_items)
+        .load::<MFile>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    Ok((search::in_rank_order(&rows, items, |item| item.id), results[0].count))
+}
+
+/// The row without its binary columns, which the audit log leaves out.
+fn find_audited(conn: &mut MysqlConnection, mfile_id: i64) -> Result<Option<MFile>, AppError> {
+    let fields = projection::resolve(None, &COLUMNS, true)?;
+    find_query_by_id(conn, mfile_id, true, &projection::select_list(&fields, &COLUMNS))
+}
+
+/// Record a change of the row, `before` read ahead of the write.
+fn audit(
+    conn: &mut MysqlConnection,
+    mfile_id: i64,
+    action: AuditAction,
+    before: Option<MFile>,
+) -> Result<(), AppError> {
+    let after = match action {
+        AuditAction::PURGE => None,
+        _ => find_audited(conn, mfile_id)?,
+    };
+    audit_log_service::record(conn, AUDIT_ENTITY, mfile_id, action, before.as_ref(), after.as_ref())
+}
@@ -1,14 +1,36 @@
-use axum::{routing::{get, post}, Router};
+use axum::{middleware::from_fn, routing::{delete, get, post}, Router};
 
-use crate::module::m_file::controller::{create, delete_by_id, download, find_all, find_by_id, find_page, update, upload};
+use crate::middleware::auth_middleware::{require_admin, require_admin_for_deleted, require_user, require_user_for_writes};
+use crate::module::{m_file::controller::{aggregate, search, archive, bulk_create, export, bulk_delete, bulk_update, create, delete_by_id, download, patch, purge, restore, scan, find_all, find_by_id, find_page, signed_url, update, upload}, m_file_upload, m_file_variant, m_file_version};
 
 
 pub fn new() -> Router {
     Router::new()
     .route("/list", get(find_all))
     .route("/pagination", get(find_page))
+    .route("/export", get(export))
+    .route("/aggregate", get(aggregate))
+    .route("/search", get(search))
     .route("/", post(create).put(update))
-    .route("/{biodata_id}", get(find_by_id).delete(delete_by_id))
+    .route("/bulk", post(bulk_create).put(bulk_update).delete(bulk_delete))
+    .route("/{file_id}", get(find_by_id).patch(patch).delete(delete_by_id))
+    .route("/{file_id}/restore", post(restore))
+    .route("/{file_id}/purge", delete(purge).route_layer(from_fn(require_admin)))
+    .route("/{file_id}/signed-url", get(signed_url).route_layer(from_fn(require_user)))
+    .route("/{file_id}/scan", post(scan))
+    .route("/{file_id}/variant/{name}", get(m_file_variant::controller::download))
+    .route("/{file_id}/variant/{name}/signed-url", get(m_file_variant::controller::signed_url).route_layer(from_fn(require_user)))
+    .route("/{file_id}/version", get(m_file_version::controller::find_by_file_id).post(m_file_version::controller::upload))
+    .route("/{file_id}/version/{version}", get(m_file_version::controller::find_by_version))
+    .route("/{file_id}/version/{version}/signed-url", get(m_file_version::controller::signed_url).route_layer(from_fn(require_user)))
+    .route("/{file_id}/version/{version}/download", get(m_file_version::controller::download))
+    .route("/{file_id}/version/{version}/restore", post(m_file_version::controller::restore))
     .route("/download", get(download))
     .route("/upload", post(upload))
+    .route("/archive", post(archive))
+    .nest("/tus", m_file_upload::router::new())
+    // changes are recorded in the audit log under the caller
+    .route_layer(from_fn(require_user_for_writes))
+    // soft deleted rows are for administrators only
+    .route_layer(from_fn(require_admin_for_deleted))
 }
\ No newline at end of file
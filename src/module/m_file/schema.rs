@@ -3,9 +3,14 @@ use diesel::prelude::{Insertable, Queryable, QueryableByName};
 use diesel::Selectable;
 use serde::{Deserialize, Serialize};
 
-use validator::Validate;
+use validator::{Validate, ValidationError, ValidationErrors};
 
-use crate::diesel_schema::m_file;
+use crate::config::id_generator;
+use crate::diesel_schema::{m_file, m_file_blob};
+use crate::dto::response::app_error::AppError;
+use crate::dto::enumerator::{filter_data_type::FilterDataType, scan_status::ScanStatus};
+use crate::dto::request::{filter_request::Filter, sort_request::Sort};
+use crate::util::projection::Columns;
 use crate::util::serializer::{date_serializer, option_date_serializer};
 
 #[derive(
@@ -24,6 +29,7 @@ pub struct MFile {
     pub file_name: Option<String>,
     pub file_type: Option<String>,
     pub file: Option<Vec<u8>>,
+    /// storage key, resolved by the configured `StorageBackend`
     pub file_path: Option<String>,
     pub created_by: i64,
     #[serde(with = "date_serializer")]
@@ -35,13 +41,20 @@ pub struct MFile {
     #[serde(with = "option_date_serializer")]
     pub deleted_on: Option<NaiveDateTime>,
     pub is_delete: bool,
+    /// SHA-256 of the content, hex encoded
+    pub file_hash: Option<String>,
+    pub file_size: Option<i64>,
+    /// `ScanStatus`, only clean files are served
+    pub scan_status: String,
+    /// bumped on every update, the `ETag` of the row
+    pub row_version: i64,
 }
 
 impl MFile {
-    pub fn new(file_name: String, file_type: String, file_path: String) -> MFile {
+    pub fn new(file_name: String, file_type: String, file_path: String) -> Result<MFile, AppError> {
         let date_now = chrono::Utc::now().naive_utc();
-        MFile {
-            id: date_now.and_utc().timestamp(),
+        Ok(MFile {
+            id: id_generator::next_id()?,
             file_name: Some(file_name),
             file_type: Some(file_type),
             file: None,
@@ -53,9 +66,13 @@ impl MFile {
             deleted_by: None,
             deleted_on: None,
             is_delete: false,
-        }
+            file_hash: None,
+            file_size: None,
+            scan_status: ScanStatus::PENDING.to_string(),
+            row_version: 0,
+        })
     }
-    pub fn from_create_request(request: MFileRequest) -> MFile {
+    pub fn from_create_request(request: MFileRequest) -> Result<MFile, AppError> {
         let date_now = chrono::Utc::now().naive_utc();
         let is_delete = request.is_delete.unwrap_or(false);
         let mut deleted_by: Option<i64> = None;
@@ -64,12 +81,13 @@ impl MFile {
             deleted_by = Some(0);
             deleted_on = Some(date_now);
         }
-        MFile {
-            id: request.id.unwrap(),
+        Ok(MFile {
+            id: id_generator::next_id()?,
             file_name: request.file_name,
             file_type: request.file_type,
             file: request.file,
-            file_path: request.file_path,
+            // storage keys only come from uploads, never from the client
+            file_path: None,
             created_by: 0,
             created_on: date_now,
             modified_by: None,
@@ -77,7 +95,11 @@ impl MFile {
             deleted_by: deleted_by,
             deleted_on: deleted_on,
             is_delete: is_delete,
-        }
+            file_hash: None,
+            file_size: None,
+            scan_status: ScanStatus::PENDING.to_string(),
+            row_version: 0,
+        })
     }
     pub fn from_update_request(request: MFileRequest, existing: MFile) -> MFile {
         let date_now = chrono::Utc::now().naive_utc();
@@ -89,11 +111,12 @@ impl MFile {
             deleted_on = Some(date_now);
         }
         MFile {
-            id: request.id.unwrap(),
+            id: existing.id,
             file_name: request.file_name,
             file_type: request.file_type,
             file: request.file,
-            file_path: request.file_path,
+            // the storage key only changes through an upload of a new version
+            file_path: existing.file_path,
             created_by: existing.created_by,
             created_on: existing.created_on,
             modified_by: Some(0),
@@ -101,18 +124,120 @@ impl MFile {
             deleted_by: deleted_by,
             deleted_on: deleted_on,
             is_delete: is_delete,
+            file_hash: existing.file_hash,
+            file_size: existing.file_size,
+            scan_status: existing.scan_status,
+            row_version: existing.row_version,
         }
     }
 }
 
 
+/// Content-addressed blob shared by every `MFile` row with the same hash.
+#[derive(Debug, Serialize, Clone, Queryable, QueryableByName, Insertable, Selectable)]
+#[diesel(table_name = m_file_blob)]
+pub struct MFileBlob {
+    pub file_hash: String,
+    pub file_path: String,
+    pub file_size: i64,
+    pub ref_count: i64,
+    #[serde(skip_serializing)]
+    pub file: Option<Vec<u8>>,
+    #[serde(with = "date_serializer")]
+    pub created_on: NaiveDateTime,
+    #[serde(with = "option_date_serializer")]
+    pub modified_on: Option<NaiveDateTime>,
+}
+
+impl MFileBlob {
+    /// Storage key of a blob, sharded by the leading hash characters.
+    pub fn storage_key(hash: &str) -> String {
+        format!("blob/{}/{}/{}", &hash[0..2], &hash[2..4], hash)
+    }
+}
+
+/// Columns `_fields` can select, in table order.
+pub const COLUMNS: Columns = Columns {
+    all: &[
+        "id",
+        "file_name",
+        "file_type",
+        "file",
+        "file_path",
+        "created_by",
+        "created_on",
+        "modified_by",
+        "modified_on",
+        "deleted_by",
+        "deleted_on",
+        "is_delete",
+        "file_hash",
+        "file_size",
+        "scan_status",
+        "row_version",
+    ],
+    nullable: &[
+        "file_name",
+        "file_type",
+        "file",
+        "file_path",
+        "modified_by",
+        "modified_on",
+        "deleted_by",
+        "deleted_on",
+        "file_hash",
+        "file_size",
+    ],
+    blobs: &["file"],
+};
+
+/// Columns `/aggregate` can group by or aggregate.
+pub const AGGREGATE_COLUMNS: &[(&str, FilterDataType)] = &[
+    ("id", FilterDataType::NUMBER),
+    ("file_name", FilterDataType::TEXT),
+    ("file_type", FilterDataType::TEXT),
+    ("file_path", FilterDataType::TEXT),
+    ("file_hash", FilterDataType::TEXT),
+    ("file_size", FilterDataType::NUMBER),
+    ("scan_status", FilterDataType::TEXT),
+    ("created_by", FilterDataType::NUMBER),
+    ("created_on", FilterDataType::DATE),
+    ("modified_by", FilterDataType::NUMBER),
+    ("modified_on", FilterDataType::DATE),
+    ("deleted_by", FilterDataType::NUMBER),
+    ("deleted_on", FilterDataType::DATE),
+    ("is_delete", FilterDataType::BOOLEAN),
+];
+
+/// Entity name of the audit log records of this table.
+pub const AUDIT_ENTITY: &str = "m_file";
+
+/// Columns of the FULLTEXT index `/search` matches against.
+pub const SEARCH_COLUMNS: &[&str] = &["file_name"];
+
+/// Columns `/export` can write, binary content is left out.
+pub const EXPORT_COLUMNS: &[&str] = &[
+    "id",
+    "file_name",
+    "file_type",
+    "file_path",
+    "file_hash",
+    "file_size",
+    "scan_status",
+    "created_by",
+    "created_on",
+    "modified_by",
+    "modified_on",
+    "deleted_by",
+    "deleted_on",
+    "is_delete",
+    "row_version",
+];
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct MFileRequest {
-    #[validate(
-        range(min = 1, max = 999999, message = "must be between 1-999999 chars"),
-        required(message = "mandatory")
-    )]
+    /// ignored on create, the id is generated by the server
+    #[validate(range(min = 1, message = "must be greater than 0"))]
     pub id: Option<i64>,
     #[validate(
         length(min = 3, message = "must be greater than 3 chars"),
@@ -123,8 +248,63 @@ pub struct MFileRequest {
     pub file_type: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file: Option<Vec<u8>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub file_path: Option<String>,
     #[validate(required(message = "mandatory"))]
     pub is_delete: Option<bool>,
+    /// version the client last read, checked by bulk updates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_version: Option<i64>,
+}
+
+impl MFileRequest {
+    /// Id of the row an update targets, mandatory there unlike on create.
+    pub fn require_id(&self) -> Result<i64, AppError> {
+        match self.id {
+            Some(value) => Ok(value),
+            None => {
+                let mut errors = ValidationErrors::new();
+                errors.add("id", ValidationError::new("required").with_message("mandatory".into()));
+                Err(AppError::InvalidRequest(errors))
+            }
+        }
+    }
+
+    /// Request document of an existing row, the base a merge patch applies to.
+    pub fn from_existing(existing: &MFile) -> MFileRequest {
+        MFileRequest {
+            id: Some(existing.id),
+            file_name: existing.file_name.clone(),
+            file_type: existing.file_type.clone(),
+            file: existing.file.clone(),
+            is_delete: Some(existing.is_delete),
+            row_version: Some(existing.row_version),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MFileDownloadRequest {
+    pub id: i64,
+    pub expires: i64,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MFileSignedUrl {
+    pub url: String,
+    #[serde(with = "date_serializer")]
+    pub expires_on: NaiveDateTime,
+}
+
+/// Files to put into an archive: explicit `ids`, or every row matching the
+/// same `_filter`, `_sort` and `_q` accepted by pagination.
+#[derive(Debug, Deserialize)]
+pub struct MFileArchiveRequest {
+    #[serde(default)]
+    pub ids: Option<Vec<i64>>,
+    #[serde(default)]
+    pub _filter: Option<Vec<Filter>>,
+    #[serde(default)]
+    pub _sort: Option<Vec<Sort>>,
+    #[serde(default)]
+    pub _q: Option<String>,
 }
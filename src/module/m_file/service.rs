@@ -0,0 +1,405 @@
+use std::{path::PathBuf, sync::Arc};
+
+use axum::{body::Bytes, extract::Multipart};
+use diesel::MysqlConnection;
+use sha2::{Digest, Sha256};
+use validator::Validate;
+
+use crate::{
+    config::{scanner::main::ScanResult, storage::main::StorageEngine},
+    dto::{
+        enumerator::{file_type::FileType, scan_status::ScanStatus},
+        request::bulk_request::BulkDeleteItem,
+        response::app_error::AppError,
+    },
+    module::{
+        m_biodata::repository as m_biodata_repository,
+        m_file::{
+            repository,
+            schema::{MFile, MFileBlob, MFileRequest},
+        },
+        m_file_variant::service as variant_service,
+        m_file_version::service as version_service,
+    },
+    state::AppState,
+    util::{etag, unit_of_work},
+};
+
+/// File part of a multipart upload, hashed while it was streamed in.
+pub struct UploadedFile {
+    pub file_name: String,
+    pub content_type: String,
+    pub file_hash: String,
+    pub data: Bytes,
+}
+
+/// Content of a new file, either held in memory or spooled to a local file
+/// too large to buffer (e.g. a completed tus upload).
+pub enum FileContent {
+    Memory(Bytes),
+    Spooled(PathBuf),
+}
+
+/// Read the `file` field of a multipart request.
+pub async fn read_upload(multipart: &mut Multipart) -> Result<Option<UploadedFile>, AppError> {
+    let mut uploaded_file = None;
+
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|error| AppError::Other(format!("read upload failed: {}", error)))?
+    {
+        let name = field.name().unwrap_or("").to_string();
+
+        if name == "file" {
+            let file_name = field.file_name().unwrap_or("").to_string();
+            let content_type = field.content_type().unwrap_or("").to_string();
+
+            // hash the content while streaming it in
+            let mut hasher = Sha256::new();
+            let mut buffer: Vec<u8> = Vec::new();
+            while let Some(chunk) = field
+                .chunk()
+                .await
+                .map_err(|error| AppError::Other(format!("read upload failed: {}", error)))?
+            {
+                hasher.update(&chunk);
+                buffer.extend_from_slice(&chunk);
+            }
+
+            uploaded_file = Some(UploadedFile {
+                file_name,
+                content_type,
+                file_hash: format!("{:x}", hasher.finalize()),
+                data: Bytes::from(buffer),
+            });
+            continue;
+        }
+        if name == "token" {
+            let token = field
+                .text()
+                .await
+                .map_err(|error| AppError::Other(format!("read upload failed: {}", error)))?;
+            log::info!("Token received: {}", token);
+            continue;
+        }
+    }
+
+    Ok(uploaded_file)
+}
+
+/// Guess the MIME type served for a stored file from its name.
+pub fn content_type_from_name(file_name: &str) -> &'static str {
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Store `data` under its content hash. Identical content uploaded before is
+/// reused and only gains a reference. Returns the storage key of the blob.
+pub async fn acquire_blob(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    file_hash: &str,
+    data: Bytes,
+) -> Result<String, AppError> {
+    let file_size = data.len() as i64;
+    acquire_blob_content(state, conn, file_hash, file_size, &FileContent::Memory(data)).await
+}
+
+/// `acquire_blob` for content of either origin, spooled content is streamed
+/// into storage.
+pub async fn acquire_blob_content(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    file_hash: &str,
+    file_size: i64,
+    content: &FileContent,
+) -> Result<String, AppError> {
+    let storage_key = MFileBlob::storage_key(file_hash);
+    repository::acquire_blob(conn, file_hash, &storage_key, file_size)?;
+
+    let blob_exist = state.storage.exists(&storage_key).await?;
+    if blob_exist {
+        return Ok(storage_key);
+    }
+
+    let stored = match content {
+        FileContent::Memory(data) => state.storage.put(&storage_key, data.clone()).await,
+        FileContent::Spooled(path) => state.storage.put_file(&storage_key, path).await,
+    };
+    if let Err(error) = stored {
+        log::error!("Failed to store blob {}: {:?}", file_hash, error);
+        release_blob(state, conn, file_hash).await?;
+        return Err(error);
+    }
+
+    Ok(storage_key)
+}
+
+/// Drop one reference to a blob, removing its content once nothing points at it.
+pub async fn release_blob(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    file_hash: &str,
+) -> Result<(), AppError> {
+    // the content is removed before the lock on the deleted row is released:
+    // an upload of the same content waits on that lock in `acquire_blob` and
+    // then finds the content gone and stores it again
+    unit_of_work::begin(conn)?;
+    let released = repository::release_blob(conn, file_hash);
+    let deleted = match released {
+        Ok(Some(0)) => match state.storage.as_ref() {
+            // the content lives on the blob row removed above
+            StorageEngine::Database(_) => Ok(()),
+            storage => storage.delete(&MFileBlob::storage_key(file_hash)).await,
+        },
+        _ => Ok(()),
+    };
+    unit_of_work::finish(conn, released)?;
+    deleted
+}
+
+/// Store the content of a new upload and insert the `MFile` row pointing at it.
+/// The row stays pending until the content passes the virus scan; infected
+/// uploads are removed again and rejected. Clean images additionally get their
+/// size variants generated in the background.
+pub async fn create_file(
+    state: &Arc<AppState>,
+    conn: &mut MysqlConnection,
+    file_name: String,
+    file_type: String,
+    file_hash: &str,
+    data: Bytes,
+) -> Result<MFile, AppError> {
+    let file_size = data.len() as i64;
+    create_file_content(state, conn, file_name, file_type, file_hash, file_size, FileContent::Memory(data)).await
+}
+
+/// `create_file` for content of either origin. Spooled content is streamed
+/// into storage and the scanner and only read whole for image variants.
+pub async fn create_file_content(
+    state: &Arc<AppState>,
+    conn: &mut MysqlConnection,
+    file_name: String,
+    file_type: String,
+    file_hash: &str,
+    file_size: i64,
+    content: FileContent,
+) -> Result<MFile, AppError> {
+    let is_image = file_type == FileType::IMAGE.to_string();
+    let file_path = acquire_blob_content(state, conn, file_hash, file_size, &content).await?;
+
+    let mut new_m_file = match MFile::new(file_name, file_type, file_path) {
+        Ok(value) => value,
+        Err(error) => {
+            let _ = release_blob(state, conn, file_hash).await;
+            return Err(error);
+        }
+    };
+    new_m_file.file_hash = Some(file_hash.to_string());
+    new_m_file.file_size = Some(file_size);
+
+    match repository::insert_mfile(conn, new_m_file.clone()) {
+        Ok(Some(new_id)) => {
+            new_m_file.id = new_id;
+        }
+        result => {
+            let _ = release_blob(state, conn, file_hash).await;
+            return Err(result
+                .err()
+                .unwrap_or(AppError::Other(format!("save data failed"))));
+        }
+    }
+
+    if let Err(error) = version_service::record_version(state, conn, &new_m_file).await {
+        log::error!("Failed to record version, id: {}: {:?}", new_m_file.id, error);
+    }
+
+    scan_file(state, conn, &mut new_m_file, &content).await?;
+
+    if is_image && new_m_file.scan_status == ScanStatus::CLEAN.to_string() {
+        let data = match content {
+            FileContent::Memory(data) => Some(data),
+            // the image is decoded in memory anyway, skip sources beyond that
+            FileContent::Spooled(_) if file_size > variant_service::MAX_SOURCE_SIZE => None,
+            FileContent::Spooled(path) => tokio::fs::read(&path).await.ok().map(Bytes::from),
+        };
+        match data {
+            Some(data) => variant_service::spawn_generate(state.clone(), new_m_file.id, data),
+            None => log::warn!("variants skipped, id: {}, size: {}", new_m_file.id, file_size),
+        }
+    }
+
+    Ok(new_m_file)
+}
+
+/// Scan the content of `m_file` and mark it clean. Infected files are deleted
+/// and rejected; when the scanner is unavailable the file stays pending so a
+/// later rescan can release it.
+pub async fn scan_file(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    m_file: &mut MFile,
+    content: &FileContent,
+) -> Result<(), AppError> {
+    let result = match content {
+        FileContent::Memory(data) => state.scanner.scan(data.clone()).await,
+        FileContent::Spooled(path) => state.scanner.scan_file(path).await,
+    };
+    match result {
+        Ok(ScanResult::Clean) => {
+            repository::update_scan_status(conn, m_file.id, &ScanStatus::CLEAN.to_string())?;
+            m_file.scan_status = ScanStatus::CLEAN.to_string();
+            m_file.row_version += 1;
+            Ok(())
+        }
+        Ok(ScanResult::Infected(signature)) => {
+            log::warn!("infected upload rejected, id: {}, signature: {}", m_file.id, signature);
+            purge_file(state, conn, m_file).await?;
+            Err(AppError::UnprocessableEntity(format!(
+                "file rejected by virus scan: {}",
+                signature
+            )))
+        }
+        Err(error) => {
+            log::error!("scan failed, file kept pending, id: {}: {:?}", m_file.id, error);
+            Ok(())
+        }
+    }
+}
+
+/// Scan a file that was left pending again, e.g. after the scanner was down.
+pub async fn rescan_file(
+    state: &Arc<AppState>,
+    conn: &mut MysqlConnection,
+    mut m_file: MFile,
+) -> Result<MFile, AppError> {
+    if m_file.scan_status == ScanStatus::CLEAN.to_string() {
+        return Ok(m_file);
+    }
+    // only content stored through an upload is scanned and released, rows
+    // created through JSON have none and stay pending
+    let file_path = match (&m_file.file_hash, &m_file.file_path) {
+        (Some(_), Some(file_path)) => file_path.clone(),
+        _ => {
+            return Err(AppError::UnprocessableEntity(format!(
+                "file has no uploaded content to scan, id: {}",
+                m_file.id
+            )));
+        }
+    };
+    let data = match state.storage.get(&file_path).await? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+
+    scan_file(state, conn, &mut m_file, &FileContent::Memory(data.clone())).await?;
+    if m_file.scan_status != ScanStatus::CLEAN.to_string() {
+        return Err(AppError::Other(format!("scan failed, id: {}", m_file.id)));
+    }
+
+    if m_file.file_type == Some(FileType::IMAGE.to_string()) {
+        variant_service::spawn_generate(state.clone(), m_file.id, data);
+    }
+    Ok(m_file)
+}
+
+/// Refuse to serve files that have not passed the virus scan yet.
+pub fn ensure_available(m_file: &MFile) -> Result<(), AppError> {
+    if m_file.scan_status != ScanStatus::CLEAN.to_string() {
+        return Err(AppError::Forbidden(format!(
+            "file is not available, scan status: {}",
+            m_file.scan_status
+        )));
+    }
+    Ok(())
+}
+
+/// Permanently delete an `MFile` row together with its variants and
+/// versions, releasing the blobs. Refused with a conflict while the file is
+/// still the image of a biodata, before anything is deleted.
+pub async fn purge_file(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    m_file: &MFile,
+) -> Result<Option<()>, AppError> {
+    if m_biodata_repository::exists_by_image_file_id(conn, m_file.id)? {
+        return Err(AppError::Conflict(format!(
+            "file is still the image of a biodata, id: {}",
+            m_file.id
+        )));
+    }
+    let result = repository::delete_by_id(conn, m_file.id)?;
+    if result.is_none() {
+        return Ok(None);
+    }
+
+    variant_service::delete_by_file_id(state, conn, m_file.id).await?;
+    version_service::delete_by_file_id(state, conn, m_file.id).await?;
+    if let Some(file_hash) = &m_file.file_hash {
+        release_blob(state, conn, file_hash).await?;
+    }
+    Ok(result)
+}
+
+/// Validate and insert one file row, the unit of work of a bulk create.
+pub fn create_mfile(conn: &mut MysqlConnection, m_file_request: MFileRequest) -> Result<i64, AppError> {
+    m_file_request.validate().map_err(AppError::InvalidRequest)?;
+
+    let new_m_file = MFile::from_create_request(m_file_request)?;
+    match repository::insert_mfile(conn, new_m_file)? {
+        Some(new_id) => Ok(new_id),
+        None => Err(AppError::Other(format!("save data failed"))),
+    }
+}
+
+/// Validate and update one file row, the unit of work of a bulk update.
+pub fn update_mfile(conn: &mut MysqlConnection, m_file_request: MFileRequest) -> Result<i64, AppError> {
+    m_file_request.validate().map_err(AppError::InvalidRequest)?;
+
+    unit_of_work::run(conn, |conn| {
+        let existing = match repository::lock_by_id(conn, m_file_request.require_id()?)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        etag::check_row_version(existing.id, m_file_request.row_version, existing.row_version)?;
+
+        let new_m_file = MFile::from_update_request(m_file_request, existing);
+        let file_id = new_m_file.id;
+        match repository::update_mfile(conn, new_m_file)? {
+            Some(_) => Ok(file_id),
+            None => Err(AppError::PreconditionFailed("resource was modified".to_string())),
+        }
+    })
+}
+
+/// Soft delete one file row, the unit of work of a bulk delete.
+pub fn soft_delete_mfile(conn: &mut MysqlConnection, item: BulkDeleteItem) -> Result<i64, AppError> {
+    unit_of_work::run(conn, |conn| {
+        let existing = match repository::lock_by_id(conn, item.id)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        etag::check_row_version(existing.id, item.row_version, existing.row_version)?;
+
+        match repository::soft_delete_by_id(conn, existing.id)? {
+            Some(_) => Ok(existing.id),
+            None => Err(AppError::NotFound),
+        }
+    })
+}
@@ -0,0 +1,482 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Extension, Path},
+    http::{HeaderMap, HeaderValue, StatusCode},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::NaiveDateTime;
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::{
+    config::environment::CONFIG,
+    dto::{enumerator::file_type::FileType, response::app_error::AppError},
+    module::{
+        m_file::service::{self, FileContent},
+        m_file_upload::{repository, schema::MFileUpload},
+    },
+    state::AppState,
+    util::unit_of_work,
+};
+
+const TUS_VERSION: &str = "1.0.0";
+const TUS_EXTENSION: &str = "creation,expiration,termination";
+const OFFSET_CONTENT_TYPE: &str = "application/offset+octet-stream";
+
+fn header_value(value: impl ToString) -> HeaderValue {
+    HeaderValue::from_str(&value.to_string()).unwrap()
+}
+
+fn tus_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("Tus-Resumable", header_value(TUS_VERSION));
+    headers
+}
+
+fn http_date(date: NaiveDateTime) -> String {
+    date.and_utc().format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn upload_file_path(upload_id: &str) -> PathBuf {
+    PathBuf::from(&CONFIG.storage_upload_dir).join(upload_id)
+}
+
+fn header_i64(headers: &HeaderMap, name: &str) -> Option<i64> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .filter(|value| *value >= 0)
+}
+
+/// Returns a 412 response when the client speaks another tus version.
+fn check_tus_version(headers: &HeaderMap) -> Option<(StatusCode, HeaderMap)> {
+    let client_version = headers
+        .get("Tus-Resumable")
+        .and_then(|value| value.to_str().ok());
+    if client_version == Some(TUS_VERSION) {
+        return None;
+    }
+    let mut response_headers = tus_headers();
+    response_headers.insert("Tus-Version", header_value(TUS_VERSION));
+    Some((StatusCode::PRECONDITION_FAILED, response_headers))
+}
+
+/// Parse `Upload-Metadata` (`key base64value,key base64value`) into file name
+/// and content type.
+fn parse_metadata(headers: &HeaderMap) -> (Option<String>, Option<String>) {
+    let mut file_name = None;
+    let mut content_type = None;
+    let metadata = headers
+        .get("Upload-Metadata")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+
+    for pair in metadata.split(',') {
+        let mut parts = pair.trim().splitn(2, ' ');
+        let key = parts.next().unwrap_or("");
+        let value = parts
+            .next()
+            .and_then(|value| STANDARD.decode(value).ok())
+            .and_then(|value| String::from_utf8(value).ok());
+        match key {
+            "filename" | "name" => file_name = value,
+            "filetype" | "type" => content_type = value,
+            _ => {}
+        }
+    }
+    (file_name, content_type)
+}
+
+pub async fn options() -> (StatusCode, HeaderMap) {
+    let mut headers = tus_headers();
+    headers.insert("Tus-Version", header_value(TUS_VERSION));
+    headers.insert("Tus-Extension", header_value(TUS_EXTENSION));
+    headers.insert("Tus-Max-Size", header_value(CONFIG.storage_upload_max_size));
+    (StatusCode::NO_CONTENT, headers)
+}
+
+pub async fn create(
+    Extension(_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    if let Some(response) = check_tus_version(&headers) {
+        return Ok(response);
+    }
+
+    let upload_length = match header_i64(&headers, "Upload-Length") {
+        Some(value) => value,
+        None => return Ok((StatusCode::BAD_REQUEST, tus_headers())),
+    };
+    if upload_length > CONFIG.storage_upload_max_size {
+        return Ok((StatusCode::PAYLOAD_TOO_LARGE, tus_headers()));
+    }
+
+    let (file_name, content_type) = parse_metadata(&headers);
+    let new_upload = MFileUpload::new(
+        file_name,
+        content_type,
+        upload_length,
+        CONFIG.storage_upload_expiration,
+    );
+
+    // get db connection
+    let mut db_conn = _state
+        .diesel_pool_mysql
+        .get()
+        .map_err(|error| AppError::Other(format!("get connection failed {error}")))?;
+
+    tokio::fs::create_dir_all(&CONFIG.storage_upload_dir)
+        .await
+        .map_err(|error| AppError::Other(format!("create dir failed: {}", error)))?;
+    tokio::fs::File::create(upload_file_path(&new_upload.id))
+        .await
+        .map_err(|error| AppError::Other(format!("create file failed: {}", error)))?;
+
+    repository::insert_upload(&mut db_conn, new_upload.clone())?;
+
+    let mut response_headers = tus_headers();
+    response_headers.insert(
+        "Location",
+        header_value(format!("/m-file/tus/{}", new_upload.id)),
+    );
+    response_headers.insert("Upload-Offset", header_value(0));
+    response_headers.insert("Upload-Expires", header_value(http_date(new_upload.expires_on)));
+    Ok((StatusCode::CREATED, response_headers))
+}
+
+pub async fn head_upload(
+    Path(upload_id): Path<String>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    // get db connection
+    let mut db_conn = _state
+        .diesel_pool_mysql
+        .get()
+        .map_err(|error| AppError::Other(format!("get connection failed {error}, id: {upload_id}")))?;
+
+    let upload = match repository::find_by_id(&mut db_conn, &upload_id)? {
+        Some(value) => value,
+        None => return Ok((StatusCode::NOT_FOUND, tus_headers())),
+    };
+
+    let mut response_headers = tus_headers();
+    response_headers.insert("Cache-Control", header_value("no-store"));
+    response_headers.insert("Upload-Offset", header_value(upload.upload_offset));
+    response_headers.insert("Upload-Length", header_value(upload.upload_length));
+    response_headers.insert("Upload-Expires", header_value(http_date(upload.expires_on)));
+    if let Some(file_id) = upload.file_id {
+        response_headers.insert("Upload-File-Id", header_value(file_id));
+    }
+    Ok((StatusCode::OK, response_headers))
+}
+
+pub async fn patch_upload(
+    Path(upload_id): Path<String>,
+    Extension(_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    if let Some(response) = check_tus_version(&headers) {
+        return Ok(response);
+    }
+    let content_type = headers
+        .get("Content-Type")
+        .and_then(|value| value.to_str().ok());
+    if content_type != Some(OFFSET_CONTENT_TYPE) {
+        return Ok((StatusCode::UNSUPPORTED_MEDIA_TYPE, tus_headers()));
+    }
+    let client_offset = match header_i64(&headers, "Upload-Offset") {
+        Some(value) => value,
+        None => return Ok((StatusCode::BAD_REQUEST, tus_headers())),
+    };
+
+    // the offset is taken under a short lock, the connection goes back to the
+    // pool before the body streams in
+    let upload = {
+        let mut db_conn = _state
+            .diesel_pool_mysql
+            .get()
+            .map_err(|error| AppError::Other(format!("get connection failed {error}, id: {upload_id}")))?;
+        match unit_of_work::run(&mut db_conn, |conn| check_offset(conn, &upload_id, client_offset))? {
+            Ok(value) => value,
+            Err(status_code) => return Ok((status_code, tus_headers())),
+        }
+    };
+
+    // a complete upload without a file is a PATCH at the final offset after a
+    // failed completion, there is nothing left to receive
+    let upload = if upload.is_complete() {
+        upload
+    } else {
+        let chunk_path = chunk_file_path(&upload_id);
+        let appended = match receive_chunk(&upload, &chunk_path, body).await {
+            Ok(Ok(written)) => {
+                let mut db_conn = _state
+                    .diesel_pool_mysql
+                    .get()
+                    .map_err(|error| AppError::Other(format!("get connection failed {error}, id: {upload_id}")))?;
+                unit_of_work::begin(&mut db_conn)?;
+                let appended = append_chunk(&mut db_conn, &upload_id, client_offset, &chunk_path, written).await;
+                unit_of_work::finish(&mut db_conn, appended)
+            }
+            received => received.map(|result| result.map(|_| upload)),
+        };
+        if let Err(error) = tokio::fs::remove_file(&chunk_path).await
+            && error.kind() != std::io::ErrorKind::NotFound
+        {
+            log::error!("Failed to remove chunk file {}: {}", upload_id, error);
+        }
+        match appended? {
+            Ok(value) => value,
+            Err(status_code) => return Ok((status_code, tus_headers())),
+        }
+    };
+
+    let mut response_headers = tus_headers();
+    response_headers.insert("Upload-Offset", header_value(upload.upload_offset));
+    response_headers.insert("Upload-Expires", header_value(http_date(upload.expires_on)));
+
+    if upload.is_complete() && upload.file_id.is_none() {
+        let mut db_conn = _state
+            .diesel_pool_mysql
+            .get()
+            .map_err(|error| AppError::Other(format!("get connection failed {error}, id: {upload_id}")))?;
+        let file_id = complete_upload(&_state, &mut db_conn, &upload).await?;
+        response_headers.insert("Upload-File-Id", header_value(file_id));
+    }
+
+    Ok((StatusCode::NO_CONTENT, response_headers))
+}
+
+/// Where the body of one PATCH request is received before it is appended.
+fn chunk_file_path(upload_id: &str) -> PathBuf {
+    PathBuf::from(&CONFIG.storage_upload_dir).join(format!(
+        "{}.{}.chunk",
+        upload_id,
+        uuid::Uuid::new_v4()
+    ))
+}
+
+/// Lock the upload and check that a PATCH may append at `client_offset`.
+/// Returns the upload, or the status the request is refused with. A complete
+/// upload without a file is returned so its completion can be retried.
+fn check_offset(
+    conn: &mut diesel::MysqlConnection,
+    upload_id: &str,
+    client_offset: i64,
+) -> Result<Result<MFileUpload, StatusCode>, AppError> {
+    let upload = match repository::lock_by_id(conn, upload_id)? {
+        Some(value) => value,
+        None => return Ok(Err(StatusCode::NOT_FOUND)),
+    };
+    if upload.expires_on < chrono::Utc::now().naive_utc() {
+        return Ok(Err(StatusCode::GONE));
+    }
+    if client_offset != upload.upload_offset {
+        return Ok(Err(StatusCode::CONFLICT));
+    }
+    if upload.is_complete() && upload.file_id.is_some() {
+        return Ok(Err(StatusCode::CONFLICT));
+    }
+    Ok(Ok(upload))
+}
+
+/// Stream the request body into `chunk_path`, at most the bytes the upload
+/// still misses. Returns the number of bytes received; an interrupted body
+/// keeps what arrived so far, the client resumes from it.
+async fn receive_chunk(
+    upload: &MFileUpload,
+    chunk_path: &PathBuf,
+    body: Body,
+) -> Result<Result<i64, StatusCode>, AppError> {
+    let file = tokio::fs::File::create(chunk_path)
+        .await
+        .map_err(|error| AppError::Other(format!("create file failed: {}", error)))?;
+    let mut file = tokio::io::BufWriter::new(file);
+
+    let remaining = upload.upload_length - upload.upload_offset;
+    let mut written: i64 = 0;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk: Bytes = match chunk {
+            Ok(value) => value,
+            Err(error) => {
+                log::info!("upload {} interrupted at {}: {}", upload.id, upload.upload_offset + written, error);
+                break;
+            }
+        };
+        if written + chunk.len() as i64 > remaining {
+            return Ok(Err(StatusCode::PAYLOAD_TOO_LARGE));
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|error| AppError::Other(format!("write file failed: {}", error)))?;
+        written += chunk.len() as i64;
+    }
+    file.flush()
+        .await
+        .map_err(|error| AppError::Other(format!("write file failed: {}", error)))?;
+    Ok(Ok(written))
+}
+
+/// Lock the upload again and, when no other request appended meanwhile, add
+/// the received chunk to the upload file and move the offset past it.
+async fn append_chunk(
+    conn: &mut diesel::MysqlConnection,
+    upload_id: &str,
+    client_offset: i64,
+    chunk_path: &PathBuf,
+    written: i64,
+) -> Result<Result<MFileUpload, StatusCode>, AppError> {
+    let mut upload = match repository::lock_by_id(conn, upload_id)? {
+        Some(value) => value,
+        None => return Ok(Err(StatusCode::NOT_FOUND)),
+    };
+    if upload.upload_offset != client_offset {
+        return Ok(Err(StatusCode::CONFLICT));
+    }
+
+    // drop any bytes beyond the recorded offset that a previously interrupted
+    // append may have left behind
+    let file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(upload_file_path(upload_id))
+        .await
+        .map_err(|error| AppError::Other(format!("open file failed: {}", error)))?;
+    file.set_len(upload.upload_offset as u64)
+        .await
+        .map_err(|error| AppError::Other(format!("truncate file failed: {}", error)))?;
+    let mut file = tokio::io::BufWriter::new(file);
+    tokio::io::AsyncSeekExt::seek(&mut file, std::io::SeekFrom::End(0))
+        .await
+        .map_err(|error| AppError::Other(format!("seek file failed: {}", error)))?;
+    let mut chunk = tokio::fs::File::open(chunk_path)
+        .await
+        .map_err(|error| AppError::Other(format!("open file failed: {}", error)))?;
+    tokio::io::copy(&mut chunk, &mut file)
+        .await
+        .map_err(|error| AppError::Other(format!("write file failed: {}", error)))?;
+    file.flush()
+        .await
+        .map_err(|error| AppError::Other(format!("write file failed: {}", error)))?;
+
+    let new_offset = upload.upload_offset + written;
+    if repository::update_offset(conn, upload_id, upload.upload_offset, new_offset)?.is_none() {
+        return Ok(Err(StatusCode::CONFLICT));
+    }
+
+    upload.upload_offset = new_offset;
+    Ok(Ok(upload))
+}
+
+/// Turn a fully received upload into a regular `MFile` record. The content is
+/// hashed and stored straight from the upload file, never held in memory.
+/// Runs outside any transaction like the multipart upload, the blob row has to
+/// be committed before its content is stored. When another request completed
+/// the same upload meanwhile, its file is kept and this one purged again.
+async fn complete_upload(
+    state: &Arc<AppState>,
+    conn: &mut diesel::MysqlConnection,
+    upload: &MFileUpload,
+) -> Result<i64, AppError> {
+    let upload_path = upload_file_path(&upload.id);
+    let mut file = tokio::fs::File::open(&upload_path)
+        .await
+        .map_err(|error| AppError::Other(format!("open file failed: {}", error)))?;
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .await
+            .map_err(|error| AppError::Other(format!("read file failed: {}", error)))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    let file_hash = format!("{:x}", hasher.finalize());
+
+    let file_name = upload.file_name.clone().unwrap_or(upload.id.clone());
+    let file_type =
+        FileType::from_content_type(upload.content_type.as_deref().unwrap_or("")).to_string();
+    let new_m_file = service::create_file_content(
+        state,
+        conn,
+        file_name,
+        file_type,
+        &file_hash,
+        upload.upload_length,
+        FileContent::Spooled(upload_path.clone()),
+    )
+    .await?;
+
+    if repository::update_file_id(conn, &upload.id, new_m_file.id)?.is_none() {
+        service::purge_file(state, conn, &new_m_file).await?;
+        return match repository::find_by_id(conn, &upload.id)?.and_then(|value| value.file_id) {
+            Some(file_id) => Ok(file_id),
+            None => Err(AppError::NotFound),
+        };
+    }
+    if let Err(error) = tokio::fs::remove_file(&upload_path).await {
+        log::error!("Failed to remove upload file {}: {}", upload.id, error);
+    }
+
+    Ok(new_m_file.id)
+}
+
+pub async fn delete_by_id(
+    Path(upload_id): Path<String>,
+    Extension(_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    if let Some(response) = check_tus_version(&headers) {
+        return Ok(response);
+    }
+
+    // get db connection
+    let mut db_conn = _state
+        .diesel_pool_mysql
+        .get()
+        .map_err(|error| AppError::Other(format!("get connection failed {error}, id: {upload_id}")))?;
+
+    if repository::delete_by_id(&mut db_conn, &upload_id)?.is_none() {
+        return Ok((StatusCode::NOT_FOUND, tus_headers()));
+    }
+    let _ = tokio::fs::remove_file(upload_file_path(&upload_id)).await;
+
+    Ok((StatusCode::NO_CONTENT, tus_headers()))
+}
+
+/// Remove uploads whose `expires_on` has passed together with their partial data.
+pub async fn remove_expired(state: &AppState) -> Result<usize, AppError> {
+    let mut db_conn = state
+        .diesel_pool_mysql
+        .get()
+        .map_err(|error| AppError::Other(format!("get connection failed {error}")))?;
+
+    let expired = repository::find_expired(&mut db_conn, chrono::Utc::now().naive_utc())?;
+    for upload in &expired {
+        let _ = tokio::fs::remove_file(upload_file_path(&upload.id)).await;
+        repository::delete_by_id(&mut db_conn, &upload.id)?;
+    }
+    Ok(expired.len())
+}
+
+/// Periodically expire abandoned uploads.
+pub async fn run_expiration(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(300));
+    loop {
+        interval.tick().await;
+        match remove_expired(&state).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("removed {} expired uploads", count),
+            Err(error) => log::error!("remove expired uploads failed: {:?}", error),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::NaiveDateTime;
+use diesel::{dsl::insert_into, prelude::*, update};
+
+use crate::{
+    diesel_schema::m_file_upload::dsl::*, dto::response::app_error::AppError,
+    module::m_file_upload::schema::MFileUpload,
+};
+
+pub fn find_by_id(
+    conn: &mut MysqlConnection,
+    upload_id: &str,
+) -> Result<Option<MFileUpload>, AppError> {
+    let upload = m_file_upload
+        .filter(id.eq(upload_id))
+        .select(MFileUpload::as_select())
+        .first::<MFileUpload>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, upload_id)))?;
+
+    Ok(upload)
+}
+
+/// The upload locked until the surrounding transaction ends. Requests only
+/// hold it to check or move the offset, never while a body streams in.
+pub fn lock_by_id(
+    conn: &mut MysqlConnection,
+    upload_id: &str,
+) -> Result<Option<MFileUpload>, AppError> {
+    let upload = m_file_upload
+        .filter(id.eq(upload_id))
+        .select(MFileUpload::as_select())
+        .for_update()
+        .first::<MFileUpload>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, upload_id)))?;
+
+    Ok(upload)
+}
+
+pub fn find_expired(
+    conn: &mut MysqlConnection,
+    date_now: NaiveDateTime,
+) -> Result<Vec<MFileUpload>, AppError> {
+    let uploads = m_file_upload
+        .filter(expires_on.lt(date_now))
+        .select(MFileUpload::as_select())
+        .load::<MFileUpload>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+
+    Ok(uploads)
+}
+
+pub fn insert_upload(
+    conn: &mut MysqlConnection,
+    upload: MFileUpload,
+) -> Result<Option<()>, AppError> {
+    let rows_affected = insert_into(m_file_upload)
+        .values(&upload)
+        .execute(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    if rows_affected > 0 {
+        return Ok(Some(()));
+    }
+    return Ok(None);
+}
+
+/// Move the offset forward only when it still equals `previous_offset`, so two
+/// concurrent PATCH requests cannot both append at the same position.
+pub fn update_offset(
+    conn: &mut MysqlConnection,
+    upload_id: &str,
+    previous_offset: i64,
+    new_offset: i64,
+) -> Result<Option<()>, AppError> {
+    let rows_affected = update(
+        m_file_upload
+            .filter(id.eq(upload_id))
+            .filter(upload_offset.eq(previous_offset)),
+    )
+    .set((
+        upload_offset.eq(new_offset),
+        modified_on.eq(Some(chrono::Utc::now().naive_utc())),
+    ))
+    .execute(conn)
+    .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, upload_id)))?;
+    if rows_affected > 0 {
+        return Ok(Some(()));
+    }
+    return Ok(None);
+}
+
+/// Record the file a completed upload produced, unless another request
+/// already recorded one.
+pub fn update_file_id(
+    conn: &mut MysqlConnection,
+    upload_id: &str,
+    mfile_id: i64,
+) -> Result<Option<()>, AppError> {
+    let rows_affected = update(
+        m_file_upload
+            .filter(id.eq(upload_id))
+            .filter(file_id.is_null()),
+    )
+    .set((
+        file_id.eq(Some(mfile_id)),
+        modified_on.eq(Some(chrono::Utc::now().naive_utc())),
+    ))
+    .execute(conn)
+    .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, upload_id)))?;
+    if rows_affected > 0 {
+        return Ok(Some(()));
+    }
+    return Ok(None);
+}
+
+pub fn delete_by_id(conn: &mut MysqlConnection, upload_id: &str) -> Result<Option<()>, AppError> {
+    let rows_affected = diesel::delete(m_file_upload.filter(id.eq(upload_id)))
+        .execute(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, upload_id)))?;
+
+    if rows_affected > 0 {
+        return Ok(Some(()));
+    }
+    return Ok(None);
+}
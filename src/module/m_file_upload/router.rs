@@ -0,0 +1,10 @@
+use axum::{routing::{head, post}, Router};
+
+use crate::module::m_file_upload::controller::{create, delete_by_id, head_upload, options, patch_upload};
+
+
+pub fn new() -> Router {
+    Router::new()
+    .route("/", post(create).options(options))
+    .route("/{upload_id}", head(head_upload).patch(patch_upload).delete(delete_by_id))
+}
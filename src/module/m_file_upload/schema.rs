@@ -0,0 +1,61 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::{Insertable, Queryable, QueryableByName};
+use diesel::Selectable;
+use serde::{Deserialize, Serialize};
+
+use crate::diesel_schema::m_file_upload;
+use crate::util::serializer::{date_serializer, option_date_serializer};
+
+/// In-progress resumable (tus) upload. The received bytes live in the upload
+/// directory until `upload_offset` reaches `upload_length`.
+#[derive(
+    Debug,
+    Deserialize,
+    Serialize,
+    Clone,
+    Queryable,
+    QueryableByName,
+    Insertable,
+    Selectable
+)]
+#[diesel(table_name = m_file_upload)]
+pub struct MFileUpload {
+    pub id: String,
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub upload_length: i64,
+    pub upload_offset: i64,
+    pub file_id: Option<i64>,
+    #[serde(with = "date_serializer")]
+    pub created_on: NaiveDateTime,
+    #[serde(with = "option_date_serializer")]
+    pub modified_on: Option<NaiveDateTime>,
+    #[serde(with = "date_serializer")]
+    pub expires_on: NaiveDateTime,
+}
+
+impl MFileUpload {
+    pub fn new(
+        file_name: Option<String>,
+        content_type: Option<String>,
+        upload_length: i64,
+        expiration: i64,
+    ) -> MFileUpload {
+        let date_now = chrono::Utc::now().naive_utc();
+        MFileUpload {
+            id: uuid::Uuid::new_v4().to_string(),
+            file_name,
+            content_type,
+            upload_length,
+            upload_offset: 0,
+            file_id: None,
+            created_on: date_now,
+            modified_on: None,
+            expires_on: date_now + chrono::Duration::seconds(expiration),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.upload_offset >= self.upload_length
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Extension, Json, Path, Query},
+    http::{Response, StatusCode},
+};
+
+use crate::{
+    config::environment::CONFIG,
+    dto::{
+        request::signed_url_request::SignedUrlRequest,
+        response::{app_error::AppError, app_response::AppResponse},
+    },
+    module::{
+        m_file::{repository as m_file_repository, schema::MFileSignedUrl, service as m_file_service},
+        m_file_variant::repository,
+    },
+    state::AppState,
+    util::signed_url,
+};
+
+pub async fn signed_url(
+    Path((file_id, name)): Path<(i64, String)>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MFileSignedUrl>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {file_id}")));
+        }
+    };
+    match m_file_repository::find_by_id(&mut db_conn, file_id, false)? {
+        Some(value) => m_file_service::ensure_available(&value)?,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    }
+    if repository::find_by_file_id_and_name(&mut db_conn, file_id, &name)?.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let expires_on =
+        chrono::Utc::now().naive_utc() + chrono::Duration::seconds(CONFIG.storage_url_expiration);
+    let expires = expires_on.and_utc().timestamp();
+    let signature = signed_url::sign(&format!("m_file/{}/variant/{}", file_id, name), expires);
+
+    let status_code = StatusCode::OK;
+    Ok((
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(MFileSignedUrl {
+                url: format!(
+                    "/m-file/{}/variant/{}?expires={}&signature={}",
+                    file_id, name, expires, signature
+                ),
+                expires_on,
+            }),
+            error: None,
+        }),
+    ))
+}
+
+pub async fn download(
+    Path((file_id, name)): Path<(i64, String)>,
+    Query(request): Query<SignedUrlRequest>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<Response<Body>, AppError> {
+    let resource = format!("m_file/{}/variant/{}", file_id, name);
+    signed_url::ensure_valid(&resource, request.expires, &request.signature)?;
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!(
+                "get connection failed {error}, id: {file_id}"
+            )));
+        }
+    };
+
+    // variants of deleted files are not served
+    if m_file_repository::find_by_id(&mut db_conn, file_id, false)?.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let variant = match repository::find_by_file_id_and_name(&mut db_conn, file_id, &name)? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+
+    // cached no longer than the link is valid
+    let max_age = (request.expires - chrono::Utc::now().timestamp()).max(0);
+    match _state.storage.get(&variant.file_path).await? {
+        Some(contents) => Ok(Response::builder()
+            .header("Content-Type", variant.content_type)
+            .header("Cache-Control", format!("private, max-age={}", max_age))
+            .body(contents.into())
+            .unwrap()),
+        None => Err(AppError::NotFound),
+    }
+}
@@ -0,0 +1,57 @@
+use diesel::{dsl::insert_into, prelude::*};
+
+use crate::{
+    diesel_schema::m_file_variant::dsl::*, dto::response::app_error::AppError,
+    module::m_file_variant::schema::MFileVariant,
+};
+
+pub fn find_by_file_id_and_name(
+    conn: &mut MysqlConnection,
+    mfile_id: i64,
+    variant_name: &str,
+) -> Result<Option<MFileVariant>, AppError> {
+    let variant = m_file_variant
+        .filter(file_id.eq(mfile_id))
+        .filter(name.eq(variant_name))
+        .select(MFileVariant::as_select())
+        .first::<MFileVariant>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+    Ok(variant)
+}
+
+pub fn find_by_file_id(
+    conn: &mut MysqlConnection,
+    mfile_id: i64,
+) -> Result<Vec<MFileVariant>, AppError> {
+    let variants = m_file_variant
+        .filter(file_id.eq(mfile_id))
+        .select(MFileVariant::as_select())
+        .load::<MFileVariant>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+    Ok(variants)
+}
+
+pub fn insert_variant(
+    conn: &mut MysqlConnection,
+    variant: MFileVariant,
+) -> Result<Option<()>, AppError> {
+    let rows_affected = insert_into(m_file_variant)
+        .values(&variant)
+        .execute(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    if rows_affected > 0 {
+        return Ok(Some(()));
+    }
+    return Ok(None);
+}
+
+pub fn delete_by_file_id(conn: &mut MysqlConnection, mfile_id: i64) -> Result<usize, AppError> {
+    let rows_affected = diesel::delete(m_file_variant.filter(file_id.eq(mfile_id)))
+        .execute(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+    Ok(rows_affected)
+}
@@ -0,0 +1,55 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::{Insertable, Queryable, QueryableByName};
+use diesel::Selectable;
+use serde::{Deserialize, Serialize};
+
+use crate::diesel_schema::m_file_variant;
+use crate::util::serializer::date_serializer;
+
+/// Resized copy of an image `MFile`, stored as a regular blob.
+#[derive(
+    Debug,
+    Deserialize,
+    Serialize,
+    Clone,
+    Queryable,
+    QueryableByName,
+    Insertable,
+    Selectable
+)]
+#[diesel(table_name = m_file_variant)]
+pub struct MFileVariant {
+    pub file_id: i64,
+    pub name: String,
+    pub file_hash: String,
+    pub file_path: String,
+    pub file_size: i64,
+    pub content_type: String,
+    pub width: i32,
+    pub height: i32,
+    #[serde(with = "date_serializer")]
+    pub created_on: NaiveDateTime,
+}
+
+/// Size variant configured through `STORAGE_IMAGE_VARIANTS` (`name:max_edge`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantSpec {
+    pub name: String,
+    pub max_edge: u32,
+}
+
+impl VariantSpec {
+    pub fn parse_list(value: &str) -> Vec<VariantSpec> {
+        value
+            .split(',')
+            .filter_map(|item| {
+                let (name, max_edge) = item.trim().split_once(':')?;
+                Some(VariantSpec {
+                    name: name.trim().to_string(),
+                    max_edge: max_edge.trim().parse::<u32>().ok()?,
+                })
+            })
+            .filter(|spec| !spec.name.is_empty() && spec.max_edge > 0)
+            .collect()
+    }
+}
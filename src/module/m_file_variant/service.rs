@@ -0,0 +1,155 @@
+use std::{io::Cursor, sync::Arc};
+
+use axum::body::Bytes;
+use diesel::MysqlConnection;
+use image::{
+    imageops::FilterType, metadata::Orientation, DynamicImage, ImageDecoder, ImageFormat,
+    ImageReader,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::environment::CONFIG,
+    dto::response::app_error::AppError,
+    module::{
+        m_file::service as m_file_service,
+        m_file_variant::{
+            repository,
+            schema::{MFileVariant, VariantSpec},
+        },
+    },
+    state::AppState,
+};
+
+/// Largest source variants are rendered from, the image is decoded in memory.
+pub const MAX_SOURCE_SIZE: i64 = 256 * 1024 * 1024;
+
+struct RenderedVariant {
+    name: String,
+    data: Vec<u8>,
+    content_type: String,
+    width: u32,
+    height: u32,
+}
+
+/// Generate the configured variants of an image upload in the background.
+pub fn spawn_generate(state: Arc<AppState>, file_id: i64, data: Bytes) {
+    tokio::spawn(async move {
+        match generate(&state, file_id, data).await {
+            Ok(count) => log::info!("generated {} variants, id: {}", count, file_id),
+            Err(error) => log::error!("generate variants failed, id: {}: {:?}", file_id, error),
+        }
+    });
+}
+
+pub async fn generate(state: &AppState, file_id: i64, data: Bytes) -> Result<usize, AppError> {
+    let specs = VariantSpec::parse_list(&CONFIG.storage_image_variants);
+    if specs.is_empty() {
+        return Ok(0);
+    }
+
+    // decoding and resizing is CPU bound, keep it off the async workers
+    let rendered = tokio::task::spawn_blocking(move || render_variants(&data, &specs))
+        .await
+        .map_err(|error| AppError::Other(format!("render variants failed: {}", error)))??;
+
+    let mut db_conn = state
+        .diesel_pool_mysql
+        .get()
+        .map_err(|error| AppError::Other(format!("get connection failed {error}, id: {file_id}")))?;
+
+    let count = rendered.len();
+    for variant in rendered {
+        let file_hash = format!("{:x}", Sha256::digest(&variant.data));
+        let file_size = variant.data.len() as i64;
+        let file_path = m_file_service::acquire_blob(
+            state,
+            &mut db_conn,
+            &file_hash,
+            Bytes::from(variant.data),
+        )
+        .await?;
+
+        let new_variant = MFileVariant {
+            file_id,
+            name: variant.name,
+            file_hash: file_hash.clone(),
+            file_path,
+            file_size,
+            content_type: variant.content_type,
+            width: variant.width as i32,
+            height: variant.height as i32,
+            created_on: chrono::Utc::now().naive_utc(),
+        };
+        if let Err(error) = repository::insert_variant(&mut db_conn, new_variant) {
+            let _ = m_file_service::release_blob(state, &mut db_conn, &file_hash).await;
+            return Err(error);
+        }
+    }
+
+    Ok(count)
+}
+
+/// Decode once, honour the EXIF orientation and encode every variant. The
+/// encoders never write the source metadata, so EXIF (GPS, camera, ...) is
+/// stripped from the variants.
+fn render_variants(data: &[u8], specs: &[VariantSpec]) -> Result<Vec<RenderedVariant>, AppError> {
+    let reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|error| AppError::Other(format!("read image failed: {}", error)))?;
+    let source_format = reader.format();
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|error| AppError::Other(format!("decode image failed: {}", error)))?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder)
+        .map_err(|error| AppError::Other(format!("decode image failed: {}", error)))?;
+    image.apply_orientation(orientation);
+
+    let (output_format, content_type) = match source_format {
+        Some(ImageFormat::Jpeg) => (ImageFormat::Jpeg, "image/jpeg"),
+        _ => (ImageFormat::Png, "image/png"),
+    };
+
+    let mut rendered = Vec::new();
+    for spec in specs {
+        let mut resized = if image.width() > spec.max_edge || image.height() > spec.max_edge {
+            image.resize(spec.max_edge, spec.max_edge, FilterType::Lanczos3)
+        } else {
+            image.clone()
+        };
+        if output_format == ImageFormat::Jpeg {
+            // JPEG has no alpha channel
+            resized = DynamicImage::ImageRgb8(resized.to_rgb8());
+        }
+
+        let mut buffer = Cursor::new(Vec::new());
+        resized
+            .write_to(&mut buffer, output_format)
+            .map_err(|error| AppError::Other(format!("encode image failed: {}", error)))?;
+
+        rendered.push(RenderedVariant {
+            name: spec.name.clone(),
+            data: buffer.into_inner(),
+            content_type: content_type.to_string(),
+            width: resized.width(),
+            height: resized.height(),
+        });
+    }
+
+    Ok(rendered)
+}
+
+/// Remove every variant of a file and release their blobs.
+pub async fn delete_by_file_id(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    file_id: i64,
+) -> Result<(), AppError> {
+    let variants = repository::find_by_file_id(conn, file_id)?;
+    repository::delete_by_file_id(conn, file_id)?;
+    for variant in variants {
+        m_file_service::release_blob(state, conn, &variant.file_hash).await?;
+    }
+    Ok(())
+}
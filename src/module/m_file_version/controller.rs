@@ -0,0 +1,309 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Extension, Json, Multipart, Path, Query},
+    http::{header::LOCATION, HeaderName, Response, StatusCode},
+};
+
+use crate::{
+    config::environment::CONFIG,
+    dto::{
+        enumerator::file_type::FileType,
+        response::{app_error::AppError, app_response::AppResponse},
+    },
+    module::{
+        m_file::{repository as m_file_repository, schema::MFileSignedUrl, service as m_file_service},
+        m_file_version::{
+            repository,
+            schema::{MFileVersion, MFileVersionDownloadRequest},
+            service,
+        },
+    },
+    state::AppState,
+    util::signed_url,
+};
+
+pub async fn find_by_file_id(
+    Path(file_id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<Vec<MFileVersion>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {file_id}")));
+        }
+    };
+    if m_file_repository::find_by_id(&mut db_conn, file_id, false)?.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let versions = repository::find_by_file_id(&mut db_conn, file_id)?;
+
+    let status_code = StatusCode::OK;
+    Ok((
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(versions),
+            error: None,
+        }),
+    ))
+}
+
+pub async fn find_by_version(
+    Path((file_id, version)): Path<(i64, i32)>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MFileVersion>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {file_id}")));
+        }
+    };
+    if m_file_repository::find_by_id(&mut db_conn, file_id, false)?.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let file_version = match repository::find_by_file_id_and_version(&mut db_conn, file_id, version)? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+
+    let status_code = StatusCode::OK;
+    Ok((
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(file_version),
+            error: None,
+        }),
+    ))
+}
+
+pub async fn upload(
+    Path(file_id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<AppResponse<MFileVersion>>), AppError> {
+    let uploaded_file = match m_file_service::read_upload(&mut multipart).await? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::Other(format!("file is mandatory")));
+        }
+    };
+    let file_type = FileType::from_content_type(&uploaded_file.content_type).to_string();
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {file_id}")));
+        }
+    };
+    let existing = match m_file_repository::find_by_id(&mut db_conn, file_id, false)? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+
+    let new_version = service::upload_version(
+        &_state,
+        &mut db_conn,
+        existing,
+        uploaded_file.file_name,
+        file_type,
+        &uploaded_file.file_hash,
+        uploaded_file.data,
+    )
+    .await?;
+
+    let status_code = StatusCode::CREATED;
+    Ok((
+        status_code,
+        [(
+            LOCATION,
+            format!("/m-file/{}/version/{}", file_id, new_version.version),
+        )],
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(new_version),
+            error: None,
+        }),
+    ))
+}
+
+pub async fn restore(
+    Path((file_id, version)): Path<(i64, i32)>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<AppResponse<MFileVersion>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {file_id}")));
+        }
+    };
+    let existing = match m_file_repository::find_by_id(&mut db_conn, file_id, false)? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+    let file_version = match repository::find_by_file_id_and_version(&mut db_conn, file_id, version)? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+
+    let new_version = service::restore_version(&_state, &mut db_conn, existing, file_version).await?;
+
+    let status_code = StatusCode::CREATED;
+    Ok((
+        status_code,
+        [(
+            LOCATION,
+            format!("/m-file/{}/version/{}", file_id, new_version.version),
+        )],
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(new_version),
+            error: None,
+        }),
+    ))
+}
+
+pub async fn signed_url(
+    Path((file_id, version)): Path<(i64, i32)>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MFileSignedUrl>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {file_id}")));
+        }
+    };
+    match m_file_repository::find_by_id(&mut db_conn, file_id, false)? {
+        Some(value) => m_file_service::ensure_available(&value)?,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    }
+    if repository::find_by_file_id_and_version(&mut db_conn, file_id, version)?.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let expires_on =
+        chrono::Utc::now().naive_utc() + chrono::Duration::seconds(CONFIG.storage_url_expiration);
+    let expires = expires_on.and_utc().timestamp();
+    let signature = signed_url::sign(&format!("m_file/{}/version/{}", file_id, version), expires);
+
+    let status_code = StatusCode::OK;
+    Ok((
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: "success".to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(MFileSignedUrl {
+                url: format!(
+                    "/m-file/{}/version/{}/download?expires={}&signature={}",
+                    file_id, version, expires, signature
+                ),
+                expires_on,
+            }),
+            error: None,
+        }),
+    ))
+}
+
+pub async fn download(
+    Path((file_id, version)): Path<(i64, i32)>,
+    Query(request): Query<MFileVersionDownloadRequest>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<Response<Body>, AppError> {
+    let resource = format!("m_file/{}/version/{}", file_id, version);
+    signed_url::ensure_valid(&resource, request.expires, &request.signature)?;
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {file_id}")));
+        }
+    };
+    match m_file_repository::find_by_id(&mut db_conn, file_id, false)? {
+        Some(value) => m_file_service::ensure_available(&value)?,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    }
+    let file_version = match repository::find_by_file_id_and_version(&mut db_conn, file_id, version)? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+
+    match _state.storage.get(&file_version.file_path).await? {
+        Some(contents) => Ok(Response::builder()
+            .header(
+                "Content-Disposition",
+                format!(
+                    "attachment; filename=\"{}\"",
+                    file_version.file_name.unwrap_or_default()
+                ),
+            )
+            .header("Content-Type", "application/octet-stream")
+            .body(contents.into())
+            .unwrap()),
+        None => Err(AppError::NotFound),
+    }
+}
@@ -0,0 +1,4 @@
+pub mod schema;
+pub mod controller;
+pub mod repository;
+pub mod service;
\ No newline at end of file
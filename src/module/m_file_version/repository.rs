@@ -0,0 +1,111 @@
+use diesel::{dsl::insert_into, prelude::*};
+
+use crate::{
+    diesel_schema::{m_file, m_file_version::dsl::*},
+    dto::response::app_error::AppError,
+    module::m_file_version::schema::MFileVersion,
+};
+
+pub fn find_by_file_id(
+    conn: &mut MysqlConnection,
+    mfile_id: i64,
+) -> Result<Vec<MFileVersion>, AppError> {
+    let versions = m_file_version
+        .filter(file_id.eq(mfile_id))
+        .order(version.desc())
+        .select(MFileVersion::as_select())
+        .load::<MFileVersion>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+    Ok(versions)
+}
+
+pub fn find_by_file_id_and_version(
+    conn: &mut MysqlConnection,
+    mfile_id: i64,
+    file_version: i32,
+) -> Result<Option<MFileVersion>, AppError> {
+    let found = m_file_version
+        .filter(file_id.eq(mfile_id))
+        .filter(version.eq(file_version))
+        .select(MFileVersion::as_select())
+        .first::<MFileVersion>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+    Ok(found)
+}
+
+pub fn find_latest_version(conn: &mut MysqlConnection, mfile_id: i64) -> Result<Option<i32>, AppError> {
+    let latest = m_file_version
+        .filter(file_id.eq(mfile_id))
+        .select(diesel::dsl::max(version))
+        .first::<Option<i32>>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+    Ok(latest)
+}
+
+/// Versions beyond the `keep` newest ones.
+pub fn find_expired(
+    conn: &mut MysqlConnection,
+    mfile_id: i64,
+    keep: i64,
+) -> Result<Vec<MFileVersion>, AppError> {
+    let versions = m_file_version
+        .filter(file_id.eq(mfile_id))
+        .order(version.desc())
+        .offset(keep)
+        .limit(i64::MAX)
+        .select(MFileVersion::as_select())
+        .load::<MFileVersion>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+    Ok(versions)
+}
+
+/// Insert `file_version` as the next version of its file. The number is taken
+/// while the `m_file` row is locked, so concurrent uploads of the same file
+/// get consecutive versions instead of colliding on `(file_id, version)`.
+pub fn insert_next_version(
+    conn: &mut MysqlConnection,
+    mut file_version: MFileVersion,
+) -> Result<MFileVersion, AppError> {
+    conn.transaction(|conn| {
+        m_file::table
+            .filter(m_file::id.eq(file_version.file_id))
+            .select(m_file::id)
+            .for_update()
+            .first::<i64>(conn)
+            .optional()
+            .map_err(|error| {
+                AppError::Other(format!("query failed: {}, id: {}", error, file_version.file_id))
+            })?;
+
+        let latest = find_latest_version(conn, file_version.file_id)?.unwrap_or(0);
+        file_version.version = latest + 1;
+        insert_into(m_file_version)
+            .values(&file_version)
+            .execute(conn)?;
+        Ok(file_version)
+    })
+}
+
+pub fn delete_by_file_id_and_version(
+    conn: &mut MysqlConnection,
+    mfile_id: i64,
+    file_version: i32,
+) -> Result<Option<()>, AppError> {
+    let rows_affected = diesel::delete(
+        m_file_version
+            .filter(file_id.eq(mfile_id))
+            .filter(version.eq(file_version)),
+    )
+    .execute(conn)
+    .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, mfile_id)))?;
+
+    if rows_affected > 0 {
+        return Ok(Some(()));
+    }
+    return Ok(None);
+}
@@ -0,0 +1,38 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::{Insertable, Queryable, QueryableByName};
+use diesel::Selectable;
+use serde::{Deserialize, Serialize};
+
+use crate::diesel_schema::m_file_version;
+use crate::util::serializer::date_serializer;
+
+/// Snapshot of the content an `MFile` pointed at. Each version holds one
+/// reference to its blob.
+#[derive(
+    Debug,
+    Deserialize,
+    Serialize,
+    Clone,
+    Queryable,
+    QueryableByName,
+    Insertable,
+    Selectable
+)]
+#[diesel(table_name = m_file_version)]
+pub struct MFileVersion {
+    pub file_id: i64,
+    pub version: i32,
+    pub file_name: Option<String>,
+    pub file_hash: String,
+    pub file_path: String,
+    pub file_size: i64,
+    pub created_by: i64,
+    #[serde(with = "date_serializer")]
+    pub created_on: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MFileVersionDownloadRequest {
+    pub expires: i64,
+    pub signature: String,
+}
@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use diesel::MysqlConnection;
+
+use crate::{
+    config::{environment::CONFIG, scanner::main::ScanResult},
+    dto::{
+        enumerator::{file_type::FileType, scan_status::ScanStatus},
+        response::app_error::AppError,
+    },
+    module::{
+        m_file::{repository as m_file_repository, schema::MFile, service as m_file_service},
+        m_file_variant::service as variant_service,
+        m_file_version::{repository, schema::MFileVersion},
+    },
+    state::AppState,
+};
+
+/// Record the current content of `m_file` as its next version and drop the
+/// versions that fall outside the retention window.
+pub async fn record_version(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    m_file: &MFile,
+) -> Result<Option<MFileVersion>, AppError> {
+    let (file_hash, file_path, file_size) =
+        match (&m_file.file_hash, &m_file.file_path, m_file.file_size) {
+            (Some(hash), Some(path), Some(size)) => (hash.clone(), path.clone(), size),
+            // content not managed by the blob store, nothing to keep
+            _ => return Ok(None),
+        };
+
+    // the version holds its own reference to the blob
+    m_file_repository::acquire_blob(conn, &file_hash, &file_path, file_size)?;
+
+    let new_version = MFileVersion {
+        file_id: m_file.id,
+        // numbered by insert_next_version
+        version: 0,
+        file_name: m_file.file_name.clone(),
+        file_hash: file_hash.clone(),
+        file_path,
+        file_size,
+        created_by: m_file.modified_by.unwrap_or(m_file.created_by),
+        created_on: chrono::Utc::now().naive_utc(),
+    };
+    let new_version = match repository::insert_next_version(conn, new_version) {
+        Ok(value) => value,
+        Err(error) => {
+            let _ = m_file_service::release_blob(state, conn, &file_hash).await;
+            return Err(error);
+        }
+    };
+
+    prune(state, conn, m_file.id).await?;
+    Ok(Some(new_version))
+}
+
+/// Keep only the `STORAGE_VERSION_RETENTION` newest versions of a file.
+pub async fn prune(state: &AppState, conn: &mut MysqlConnection, file_id: i64) -> Result<(), AppError> {
+    let keep = CONFIG.storage_version_retention.max(1);
+    let expired = repository::find_expired(conn, file_id, keep)?;
+    for file_version in expired {
+        repository::delete_by_file_id_and_version(conn, file_id, file_version.version)?;
+        m_file_service::release_blob(state, conn, &file_version.file_hash).await?;
+    }
+    Ok(())
+}
+
+/// Scan content before it replaces the current one. Unlike a first upload the
+/// file is already served, so content that cannot be scanned is refused too.
+async fn ensure_clean(state: &AppState, data: Bytes) -> Result<(), AppError> {
+    match state.scanner.scan(data).await? {
+        ScanResult::Clean => Ok(()),
+        ScanResult::Infected(signature) => Err(AppError::UnprocessableEntity(format!(
+            "file rejected by virus scan: {}",
+            signature
+        ))),
+    }
+}
+
+/// Replace the content of an existing file with a new upload.
+pub async fn upload_version(
+    state: &Arc<AppState>,
+    conn: &mut MysqlConnection,
+    existing: MFile,
+    file_name: String,
+    file_type: String,
+    file_hash: &str,
+    data: Bytes,
+) -> Result<MFileVersion, AppError> {
+    ensure_clean(state, data.clone()).await?;
+
+    let file_size = data.len() as i64;
+    let file_path = m_file_service::acquire_blob(state, conn, file_hash, data.clone()).await?;
+
+    let mut updated = existing.clone();
+    updated.file_name = Some(file_name);
+    updated.file_type = Some(file_type);
+    updated.scan_status = ScanStatus::CLEAN.to_string();
+    switch_content(state, conn, existing, updated, file_hash, file_path, file_size, data).await
+}
+
+/// Point a file back at the content of one of its versions. The restore is
+/// recorded as a new version so the history stays linear. The content is
+/// scanned again, it may predate the scanner or its latest signatures.
+pub async fn restore_version(
+    state: &Arc<AppState>,
+    conn: &mut MysqlConnection,
+    existing: MFile,
+    file_version: MFileVersion,
+) -> Result<MFileVersion, AppError> {
+    let data = match state.storage.get(&file_version.file_path).await? {
+        Some(value) => value,
+        None => {
+            return Err(AppError::NotFound);
+        }
+    };
+    ensure_clean(state, data.clone()).await?;
+
+    m_file_repository::acquire_blob(
+        conn,
+        &file_version.file_hash,
+        &file_version.file_path,
+        file_version.file_size,
+    )?;
+
+    let mut updated = existing.clone();
+    updated.file_name = file_version.file_name;
+    updated.scan_status = ScanStatus::CLEAN.to_string();
+    switch_content(
+        state,
+        conn,
+        existing,
+        updated,
+        &file_version.file_hash,
+        file_version.file_path,
+        file_version.file_size,
+        data,
+    )
+    .await
+}
+
+/// Swap the content of `existing` for a blob the caller already holds one
+/// reference to, then record the result as a new version. Variants are
+/// regenerated from `data` when the file is an image.
+#[allow(clippy::too_many_arguments)]
+async fn switch_content(
+    state: &Arc<AppState>,
+    conn: &mut MysqlConnection,
+    existing: MFile,
+    mut updated: MFile,
+    file_hash: &str,
+    file_path: String,
+    file_size: i64,
+    data: Bytes,
+) -> Result<MFileVersion, AppError> {
+    // files uploaded before versioning existed get their content kept first
+    if repository::find_latest_version(conn, existing.id)?.is_none() {
+        record_version(state, conn, &existing).await?;
+    }
+
+    updated.file_hash = Some(file_hash.to_string());
+    updated.file_path = Some(file_path);
+    updated.file_size = Some(file_size);
+    updated.modified_by = Some(0);
+    updated.modified_on = Some(chrono::Utc::now().naive_utc());
+
+    match m_file_repository::update_mfile(conn, updated.clone()) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            let _ = m_file_service::release_blob(state, conn, file_hash).await;
+            return Err(AppError::Other(format!("save data failed")));
+        }
+        Err(error) => {
+            let _ = m_file_service::release_blob(state, conn, file_hash).await;
+            return Err(error);
+        }
+    }
+    if let Some(previous_hash) = &existing.file_hash {
+        m_file_service::release_blob(state, conn, previous_hash).await?;
+    }
+
+    variant_service::delete_by_file_id(state, conn, updated.id).await?;
+    if updated.file_type == Some(FileType::IMAGE.to_string()) {
+        variant_service::spawn_generate(state.clone(), updated.id, data);
+    }
+
+    match record_version(state, conn, &updated).await? {
+        Some(value) => Ok(value),
+        None => Err(AppError::Other(format!("save version failed, id: {}", updated.id))),
+    }
+}
+
+/// Remove every version of a file and release their blobs.
+pub async fn delete_by_file_id(
+    state: &AppState,
+    conn: &mut MysqlConnection,
+    file_id: i64,
+) -> Result<(), AppError> {
+    let versions = repository::find_by_file_id(conn, file_id)?;
+    for file_version in versions {
+        repository::delete_by_file_id_and_version(conn, file_id, file_version.version)?;
+        m_file_service::release_blob(state, conn, &file_version.file_hash).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,390 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Json, Path, Query},
+    http::{header::LOCATION, HeaderName, StatusCode},
+};
+use validator::Validate;
+
+use crate::{
+    dto::{
+        request::{
+            deleted_request::IncludeDeleted, filter_request::Filters, pagination_request::Pagination, search_request::Search,
+            sort_request::Sorts,
+        },
+        response::{app_error::AppError, app_response::AppResponse, pagination_response::PaginatedResponse},
+    },
+    module::m_role::{
+        repository,
+        schema::{MRole, MRoleRequest},
+    },
+    state::AppState,
+    util::unit_of_work,
+};
+
+pub async fn find_by_id(
+    Path(role_id): Path<i64>,
+    Query(_deleted): Query<IncludeDeleted>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MRole>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {role_id}")));
+        }
+    };
+
+    let result = repository::find_by_id(&mut db_conn, role_id, _deleted.include_deleted.unwrap_or(false));
+    match result {
+        Ok(Some(value)) => {
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(value),
+                    error: None,
+                }),
+            ));
+        }
+        Ok(None) => {
+            return Err(AppError::NotFound);
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+pub async fn find_all(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_deleted): Query<IncludeDeleted>,
+) -> Result<(StatusCode, Json<AppResponse<Vec<MRole>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let result = repository::find_all(&mut db_conn, _deleted.include_deleted.unwrap_or(false));
+    match result {
+        Ok(value) => {
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(value),
+                    error: None,
+                }),
+            ));
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+pub async fn delete_by_id(
+    Path(role_id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MRole>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {role_id}")));
+        }
+    };
+
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        if repository::soft_delete_by_id(conn, role_id)?.is_none() {
+            return Err(AppError::NotFound);
+        }
+        // the soft deleted row, still reachable with include_deleted
+        repository::find_by_id(conn, role_id, true)
+    });
+    match result {
+        Ok(tombstone) => {
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: tombstone,
+                    error: None,
+                }),
+            ));
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+pub async fn restore(
+    Path(role_id): Path<i64>,
+    Extension(_state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<AppResponse<MRole>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}, id: {role_id}")));
+        }
+    };
+
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        if repository::restore_by_id(conn, role_id)?.is_none() {
+            return Err(AppError::NotFound);
+        }
+        repository::find_by_id(conn, role_id, false)
+    });
+    match result {
+        Ok(restored) => {
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: restored,
+                    error: None,
+                }),
+            ));
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+pub async fn create(
+    Extension(_state): Extension<Arc<AppState>>,
+    Json(m_role_request): Json<MRoleRequest>,
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<AppResponse<MRole>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(error) = m_role_request.validate() {
+        return Err(AppError::InvalidRequest(error));
+    }
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let mut new_m_role = MRole::from_create_request(m_role_request)?;
+    let result = repository::insert_role(&mut db_conn, new_m_role.clone());
+    match result {
+        Ok(Some(new_id)) => {
+            new_m_role.id = new_id;
+            let status_code = StatusCode::CREATED;
+            return Ok((
+                status_code,
+                [(LOCATION, format!("/m-role/{}", new_m_role.id))],
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(new_m_role),
+                    error: None,
+                }),
+            ));
+        }
+        Ok(None) => {
+            return Err(AppError::Other("save data failed".to_string()));
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+pub async fn update(
+    Extension(_state): Extension<Arc<AppState>>,
+    Json(m_role_request): Json<MRoleRequest>,
+) -> Result<(StatusCode, Json<AppResponse<MRole>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(error) = m_role_request.validate() {
+        return Err(AppError::InvalidRequest(error));
+    }
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    // read and write under the row lock
+    let result = unit_of_work::run(&mut db_conn, |conn| {
+        let existing = match repository::lock_by_id(conn, m_role_request.require_id()?)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+
+        let new_value = MRole::from_update_request(m_role_request, existing);
+        match repository::update_role(conn, new_value.clone())? {
+            Some(_) => Ok(new_value),
+            None => Err(AppError::NotFound),
+        }
+    });
+    match result {
+        Ok(new_m_role) => {
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(new_m_role),
+                    error: None,
+                }),
+            ));
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
+
+pub async fn find_page(
+    Extension(_state): Extension<Arc<AppState>>,
+    Query(_pagination): Query<Pagination>,
+    Query(_sort): Query<Sorts>,
+    Query(_filter): Query<Filters>,
+    Query(_global_search): Query<Search>,
+    Query(_deleted): Query<IncludeDeleted>,
+) -> Result<(StatusCode, Json<AppResponse<PaginatedResponse<MRole>>>), AppError> {
+    log::info!("status: {}", _state.status);
+
+    if let Err(err) = _pagination.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+    if let Err(err) = _filter.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+    if let Err(err) = _sort.validate() {
+        return Err(AppError::InvalidRequest(err));
+    };
+
+    let mut _page = _pagination.page.unwrap_or(0);
+    if _page < 0 {
+        _page = 0;
+    }
+    let mut _size = _pagination.size.unwrap_or(5);
+    if _size < 1 {
+        _size = 1;
+    }
+    let _filters = _filter._filter.unwrap_or_default();
+    let _sorts = _sort._sort.unwrap_or_default();
+    let _q = _global_search._q.unwrap_or_default();
+    log::info!(
+        "page {:?}, size {:?}, filters {:?}, sorts {:?}, global_search {:?}",
+        _page,
+        _size,
+        _filters,
+        _sorts,
+        _q
+    );
+
+    // get db connection
+    let db_conn_result = _state.diesel_pool_mysql.get();
+    let mut db_conn;
+    match db_conn_result {
+        Ok(value) => {
+            db_conn = value;
+        }
+        Err(error) => {
+            return Err(AppError::Other(format!("get connection failed {error}")));
+        }
+    };
+
+    let result = repository::pagination(
+        &mut db_conn,
+        _page,
+        _size,
+        _filters,
+        _sorts,
+        _q,
+        _deleted.include_deleted.unwrap_or(false),
+    );
+    match result {
+        Ok(value) => {
+            let mut total_of_pages = value.1 / _size;
+            if value.1 % _size != 0 {
+                total_of_pages = total_of_pages + 1;
+            }
+
+            let status_code = StatusCode::OK;
+            return Ok((
+                status_code,
+                Json(AppResponse {
+                    status: status_code.as_str().to_string(),
+                    message: "success".to_owned(),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    data: Some(PaginatedResponse {
+                        content: value.0,
+                        total_of_elements: value.1,
+                        total_of_pages: total_of_pages,
+                    }),
+                    error: None,
+                }),
+            ));
+        }
+        Err(err) => {
+            return Err(err);
+        }
+    }
+}
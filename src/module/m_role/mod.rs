@@ -0,0 +1,4 @@
+pub mod schema;
+pub mod router;
+pub mod controller;
+pub mod repository;
\ No newline at end of file
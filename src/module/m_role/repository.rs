@@ -0,0 +1,265 @@
+use diesel::{dsl::insert_into, prelude::*, sql_query, update};
+
+use crate::{
+    diesel_schema::m_role::dsl::*,
+    dto::{
+        database::{last_insert_id, CountResult}, enumerator::{audit_action::AuditAction, filter_match_mode::FilterMatchMode}, request::{filter_request::Filter, sort_request::Sort}, response::app_error::AppError
+    },
+    module::{
+        audit_log::service as audit_log_service,
+        m_role::schema::{MRole, AUDIT_ENTITY},
+    },
+    util::string_manipulation,
+};
+
+pub fn find_by_id(
+    conn: &mut MysqlConnection,
+    role_id: i64,
+    include_deleted: bool,
+) -> Result<Option<MRole>, AppError> {
+    let mut query = m_role.filter(id.eq(role_id)).into_boxed();
+    if !include_deleted {
+        query = query.filter(is_delete.eq(false));
+    }
+    let role = query
+        .select(MRole::as_select())
+        .first::<MRole>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, role_id)))?;
+
+    Ok(role)
+}
+
+/// The active role with `role_code`.
+pub fn find_by_code(conn: &mut MysqlConnection, role_code: &str) -> Result<Option<MRole>, AppError> {
+    let role = m_role
+        .filter(code.eq(role_code))
+        .filter(is_delete.eq(false))
+        .select(MRole::as_select())
+        .first::<MRole>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, code: {}", error, role_code)))?;
+
+    Ok(role)
+}
+
+/// The row locked until the surrounding transaction ends, for check-and-write
+/// sequences run through `unit_of_work`.
+pub fn lock_by_id(conn: &mut MysqlConnection, role_id: i64) -> Result<Option<MRole>, AppError> {
+    let locked = m_role
+        .filter(id.eq(role_id))
+        .filter(is_delete.eq(false))
+        .select(MRole::as_select())
+        .for_update()
+        .first::<MRole>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, role_id)))?;
+
+    Ok(locked)
+}
+
+pub fn find_all(conn: &mut MysqlConnection, include_deleted: bool) -> Result<Vec<MRole>, AppError> {
+    let mut query = m_role.into_boxed();
+    if !include_deleted {
+        query = query.filter(is_delete.eq(false));
+    }
+    let roles = query
+        .order(id.asc())
+        .select(MRole::as_select())
+        .load::<MRole>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+
+    Ok(roles)
+}
+
+/// Mark the row deleted, it stays available through `include_deleted`.
+pub fn soft_delete_by_id(conn: &mut MysqlConnection, role_id: i64) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_by_id(conn, role_id, true)?;
+        let rows_affected = update(m_role.filter(id.eq(role_id)).filter(is_delete.eq(false)))
+            .set((
+                is_delete.eq(true),
+                deleted_by.eq(Some(0)),
+                deleted_on.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, role_id)))?;
+
+        if rows_affected > 0 {
+            audit(conn, role_id, AuditAction::DELETE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+pub fn restore_by_id(conn: &mut MysqlConnection, role_id: i64) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_by_id(conn, role_id, true)?;
+        let rows_affected = update(m_role.filter(id.eq(role_id)).filter(is_delete.eq(true)))
+            .set((
+                is_delete.eq(false),
+                deleted_by.eq(None::<i64>),
+                deleted_on.eq(None::<chrono::NaiveDateTime>),
+                modified_by.eq(Some(0)),
+                modified_on.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, role_id)))?;
+
+        if rows_affected > 0 {
+            audit(conn, role_id, AuditAction::RESTORE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+/// Returns the id of the new row, assigned by the database when `id` is `0`.
+pub fn insert_role(conn: &mut MysqlConnection, role: MRole) -> Result<Option<i64>, AppError> {
+    conn.transaction(|conn| {
+        let rows_affected = insert_into(m_role)
+            .values(&role)
+            .execute(conn)
+            // a duplicate code maps to DataExist
+            .map_err(AppError::from)?;
+        if rows_affected == 0 {
+            return Ok(None);
+        }
+        let mut new_id = role.id;
+        if new_id == 0 {
+            new_id = diesel::select(last_insert_id())
+                .get_result::<u64>(conn)
+                .map_err(|error| AppError::Other(format!("query failed: {}", error)))? as i64;
+        }
+
+        audit(conn, new_id, AuditAction::CREATE, None)?;
+        return Ok(Some(new_id));
+    })
+}
+
+pub fn update_role(conn: &mut MysqlConnection, role: MRole) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_by_id(conn, role.id, true)?;
+        let rows_affected = update(m_role.filter(id.eq(role.id)))
+            .set((
+                modified_by.eq(role.modified_by),
+                modified_on.eq(role.modified_on),
+                deleted_by.eq(role.deleted_by),
+                deleted_on.eq(role.deleted_on),
+                is_delete.eq(role.is_delete),
+                code.eq(role.code),
+                name.eq(role.name),
+                level.eq(role.level),
+            ))
+            .execute(conn)
+            // a duplicate code maps to DataExist
+            .map_err(AppError::from)?;
+        if rows_affected > 0 {
+            audit(conn, role.id, AuditAction::UPDATE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+pub fn pagination(
+    conn: &mut MysqlConnection,
+    page: i64,
+    size: i64,
+    filters: Vec<Filter>,
+    sorts: Vec<Sort>,
+    search: String,
+    include_deleted: bool,
+) -> Result<(Vec<MRole>, i64), AppError> {
+    // Build the query
+    let mut query = "SELECT *".to_string();
+    let mut query_count = "SELECT COUNT(*) AS count".to_string();
+    let query_table = "FROM m_role".to_string();
+
+    // Sort
+    let mut query_sort = String::new();
+    if let Some(sort) = sorts.first() {
+        let sort_asc = if sort.desc {
+            "DESC".to_string()
+        } else {
+            "ASC".to_string()
+        };
+        let sort_id = string_manipulation::cleanse_string(&sort.id);
+        query_sort = format!("ORDER BY {} {}", sort_id, sort_asc);
+    }
+
+    // Search
+    let mut query_search = String::new();
+    if search != String::new() {
+        let search = string_manipulation::cleanse_string(&search);
+        query_search = format!("WHERE (code LIKE '%{}%' OR name LIKE '%{}%')", search, search);
+    }
+
+    // Filter
+    let mut query_filter = "".to_string();
+    for filter in filters {
+        let filter_id = string_manipulation::cleanse_string(&filter.id);
+        let filter_value = string_manipulation::cleanse_string(&filter.value);
+        let filter_query_temp = match filter.match_mode {
+            FilterMatchMode::CONTAINS => format!("{} LIKE '%{}%'", filter_id, filter_value),
+            FilterMatchMode::SW => format!("{} LIKE '{}%'", filter_id, filter_value),
+            FilterMatchMode::EW => format!("{} LIKE '%{}'", filter_id, filter_value),
+            FilterMatchMode::BETWEEN => continue,
+            FilterMatchMode::EQUALS => format!("{} = '{}'", filter_id, filter_value),
+            FilterMatchMode::NOT => format!("{} <> '{}'", filter_id, filter_value),
+            FilterMatchMode::LT => format!("{} < '{}'", filter_id, filter_value),
+            FilterMatchMode::GT => format!("{} > '{}'", filter_id, filter_value),
+        };
+
+        if query_search.is_empty() && query_filter.is_empty() {
+            query_filter = format!("WHERE {}", filter_query_temp);
+        } else {
+            query_filter = format!("{} AND {}", query_filter, filter_query_temp);
+        }
+    }
+
+    // Soft delete
+    let mut query_deleted = String::new();
+    if !include_deleted {
+        if query_search.is_empty() && query_filter.is_empty() {
+            query_deleted = "WHERE is_delete = 0".to_string();
+        } else {
+            query_deleted = "AND is_delete = 0".to_string();
+        }
+    }
+
+    // Pagination
+    let query_pagination = format!("LIMIT {} OFFSET {}", size, size * (page));
+
+    // Final
+    query = format!(
+        "{} {} {} {} {} {} {}",
+        query, query_table, query_search, query_filter, query_deleted, query_sort, query_pagination
+    );
+    query_count = format!(
+        "{} {} {} {} {}",
+        query_count, query_table, query_search, query_filter, query_deleted
+    );
+    log::info!("repository > find_m_role_page > query: {:#?}", query);
+    log::info!("repository > find_m_role_page > query_count: {:#?}", query_count);
+
+    let data_vec: Vec<MRole> = sql_query(query)
+        .get_results::<MRole>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    let results = sql_query(query_count)
+        .load::<CountResult>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    Ok((data_vec, results[0].count))
+}
+
+/// Record a change of the row, `before` read ahead of the write.
+fn audit(
+    conn: &mut MysqlConnection,
+    role_id: i64,
+    action: AuditAction,
+    before: Option<MRole>,
+) -> Result<(), AppError> {
+    let after = find_by_id(conn, role_id, true)?;
+    audit_log_service::record(conn, AUDIT_ENTITY, role_id, action, before.as_ref(), after.as_ref())
+}
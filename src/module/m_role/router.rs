@@ -0,0 +1,17 @@
+use axum::{middleware::from_fn, routing::{get, post}, Router};
+
+use crate::middleware::auth_middleware::require_admin;
+use crate::module::m_role::controller::{create, delete_by_id, find_all, find_by_id, find_page, restore, update};
+
+
+pub fn new() -> Router {
+    Router::new()
+    .route("/list", get(find_all))
+    .route("/pagination", get(find_page))
+    .route("/", post(create).put(update))
+    .route("/{role_id}", get(find_by_id).delete(delete_by_id))
+    .route("/{role_id}/restore", post(restore))
+    // accounts and roles decide who is an administrator, the first one is
+    // seeded with the `create-admin` command
+    .route_layer(from_fn(require_admin))
+}
@@ -0,0 +1,128 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::{Insertable, Queryable, QueryableByName};
+use diesel::Selectable;
+use serde::{Deserialize, Serialize};
+
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::config::id_generator;
+use crate::diesel_schema::m_role;
+use crate::dto::response::app_error::AppError;
+use crate::util::serializer::{date_serializer, option_date_serializer};
+
+#[derive(
+    Debug,
+    Deserialize,
+    Serialize,
+    Clone,
+    Queryable,
+    QueryableByName,
+    Insertable,
+    Selectable
+)]
+#[diesel(table_name = m_role)]
+pub struct MRole {
+    pub id: i64,
+    pub code: Option<String>,
+    pub name: Option<String>,
+    pub level: Option<i8>,
+    pub created_by: i64,
+    #[serde(with = "date_serializer")]
+    pub created_on: NaiveDateTime,
+    pub modified_by: Option<i64>,
+    #[serde(with = "option_date_serializer")]
+    pub modified_on: Option<NaiveDateTime>,
+    pub deleted_by: Option<i64>,
+    #[serde(with = "option_date_serializer")]
+    pub deleted_on: Option<NaiveDateTime>,
+    pub is_delete: bool,
+}
+
+impl MRole {
+    pub fn from_create_request(request: MRoleRequest) -> Result<MRole, AppError> {
+        let date_now = chrono::Utc::now().naive_utc();
+        let is_delete = request.is_delete.unwrap_or(false);
+        let mut deleted_by: Option<i64> = None;
+        let mut deleted_on: Option<NaiveDateTime> = None;
+        if is_delete {
+            deleted_by = Some(0);
+            deleted_on = Some(date_now);
+        }
+        Ok(MRole {
+            id: id_generator::next_id()?,
+            code: request.code,
+            name: request.name,
+            level: request.level,
+            created_by: 0,
+            created_on: date_now,
+            modified_by: None,
+            modified_on: None,
+            deleted_by: deleted_by,
+            deleted_on: deleted_on,
+            is_delete: is_delete,
+        })
+    }
+    pub fn from_update_request(request: MRoleRequest, existing: MRole) -> MRole {
+        let date_now = chrono::Utc::now().naive_utc();
+        let is_delete = request.is_delete.unwrap_or(false);
+        let mut deleted_by: Option<i64> = None;
+        let mut deleted_on: Option<NaiveDateTime> = None;
+        if is_delete {
+            deleted_by = Some(0);
+            deleted_on = Some(date_now);
+        }
+        MRole {
+            id: existing.id,
+            code: request.code,
+            name: request.name,
+            level: request.level,
+            created_by: existing.created_by,
+            created_on: existing.created_on,
+            modified_by: Some(0),
+            modified_on: Some(date_now),
+            deleted_by: deleted_by,
+            deleted_on: deleted_on,
+            is_delete: is_delete,
+        }
+    }
+}
+
+/// Entity name of the audit log records of this table.
+pub const AUDIT_ENTITY: &str = "m_role";
+
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct MRoleRequest {
+    /// ignored on create, the id is generated by the server
+    #[validate(range(min = 1, message = "must be greater than 0"))]
+    pub id: Option<i64>,
+    /// unique, e.g. `ROLE_ADMIN`
+    #[validate(
+        length(min = 1, max = 20, message = "must be between 1 and 20 chars"),
+        required(message = "mandatory")
+    )]
+    pub code: Option<String>,
+    #[validate(
+        length(min = 1, max = 20, message = "must be between 1 and 20 chars"),
+        required(message = "mandatory")
+    )]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 0, message = "must not be negative"))]
+    pub level: Option<i8>,
+    #[validate(required(message = "mandatory"))]
+    pub is_delete: Option<bool>,
+}
+
+impl MRoleRequest {
+    /// Id of the row an update targets, mandatory there unlike on create.
+    pub fn require_id(&self) -> Result<i64, AppError> {
+        match self.id {
+            Some(value) => Ok(value),
+            None => {
+                let mut errors = ValidationErrors::new();
+                errors.add("id", ValidationError::new("required").with_message("mandatory".into()));
+                Err(AppError::InvalidRequest(errors))
+            }
+        }
+    }
+}
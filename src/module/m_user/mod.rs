@@ -0,0 +1,5 @@
+pub mod schema;
+pub mod router;
+pub mod controller;
+pub mod repository;
+pub mod service;
\ No newline at end of file
@@ -0,0 +1,348 @@
+use diesel::{dsl::insert_into, mysql::Mysql, prelude::*, sql_query, update};
+
+use crate::{
+    diesel_schema::{m_biodata, m_role, m_user},
+    dto::{
+        database::{last_insert_id, CountResult, IdResult}, enumerator::{audit_action::AuditAction, filter_match_mode::FilterMatchMode}, request::{filter_request::Filter, sort_request::Sort}, response::app_error::AppError
+    },
+    module::{
+        audit_log::service as audit_log_service,
+        m_role::schema::MRole,
+        m_user::schema::{MUser, MUserBiodata, MUserResponse, AUDIT_ENTITY, FILTER_COLUMNS},
+    },
+};
+
+type UserRow = (MUser, Option<MUserBiodata>, Option<MRole>);
+
+/// `m_user` left joined with its biodata and role.
+macro_rules! joined {
+    () => {
+        m_user::table
+            .left_join(m_biodata::table)
+            .left_join(m_role::table)
+            .select((
+                MUser::as_select(),
+                Option::<MUserBiodata>::as_select(),
+                Option::<MRole>::as_select(),
+            ))
+            .into_boxed::<Mysql>()
+    };
+}
+
+pub fn find_by_id(
+    conn: &mut MysqlConnection,
+    user_id: i64,
+    include_deleted: bool,
+) -> Result<Option<MUser>, AppError> {
+    let mut query = m_user::table.filter(m_user::id.eq(user_id)).into_boxed();
+    if !include_deleted {
+        query = query.filter(m_user::is_delete.eq(false));
+    }
+    let user = query
+        .select(MUser::as_select())
+        .first::<MUser>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, user_id)))?;
+
+    Ok(user)
+}
+
+/// The user with its biodata and role.
+pub fn find_response_by_id(
+    conn: &mut MysqlConnection,
+    user_id: i64,
+    include_deleted: bool,
+) -> Result<Option<MUserResponse>, AppError> {
+    let mut query = joined!().filter(m_user::id.eq(user_id));
+    if !include_deleted {
+        query = query.filter(m_user::is_delete.eq(false));
+    }
+    let row = query
+        .first::<UserRow>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, user_id)))?;
+
+    Ok(row.map(to_response))
+}
+
+/// The active user signing in with `email`.
+pub fn find_response_by_email(conn: &mut MysqlConnection, email: &str) -> Result<Option<MUserResponse>, AppError> {
+    let row = joined!()
+        .filter(m_user::email.eq(email))
+        .filter(m_user::is_delete.eq(false))
+        .first::<UserRow>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+
+    Ok(row.map(to_response))
+}
+
+/// The row locked until the surrounding transaction ends, for check-and-write
+/// sequences run through `unit_of_work`.
+pub fn lock_by_id(conn: &mut MysqlConnection, user_id: i64) -> Result<Option<MUser>, AppError> {
+    let locked = m_user::table
+        .filter(m_user::id.eq(user_id))
+        .filter(m_user::is_delete.eq(false))
+        .select(MUser::as_select())
+        .for_update()
+        .first::<MUser>(conn)
+        .optional()
+        .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, user_id)))?;
+
+    Ok(locked)
+}
+
+pub fn find_all(conn: &mut MysqlConnection, include_deleted: bool) -> Result<Vec<MUserResponse>, AppError> {
+    let mut query = joined!();
+    if !include_deleted {
+        query = query.filter(m_user::is_delete.eq(false));
+    }
+    let rows = query
+        .order(m_user::id.asc())
+        .load::<UserRow>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+
+    Ok(rows.into_iter().map(to_response).collect())
+}
+
+/// Mark the row deleted, it stays available through `include_deleted`.
+pub fn soft_delete_by_id(conn: &mut MysqlConnection, user_id: i64) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_by_id(conn, user_id, true)?;
+        let rows_affected = update(m_user::table.filter(m_user::id.eq(user_id)).filter(m_user::is_delete.eq(false)))
+            .set((
+                m_user::is_delete.eq(true),
+                m_user::deleted_by.eq(Some(0)),
+                m_user::deleted_on.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, user_id)))?;
+
+        if rows_affected > 0 {
+            audit(conn, user_id, AuditAction::DELETE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+pub fn restore_by_id(conn: &mut MysqlConnection, user_id: i64) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_by_id(conn, user_id, true)?;
+        let rows_affected = update(m_user::table.filter(m_user::id.eq(user_id)).filter(m_user::is_delete.eq(true)))
+            .set((
+                m_user::is_delete.eq(false),
+                m_user::deleted_by.eq(None::<i64>),
+                m_user::deleted_on.eq(None::<chrono::NaiveDateTime>),
+                m_user::modified_by.eq(Some(0)),
+                m_user::modified_on.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .execute(conn)
+            .map_err(|error| AppError::Other(format!("query failed: {}, id: {}", error, user_id)))?;
+
+        if rows_affected > 0 {
+            audit(conn, user_id, AuditAction::RESTORE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+/// Returns the id of the new row, assigned by the database when `id` is `0`.
+pub fn insert_user(conn: &mut MysqlConnection, user: MUser) -> Result<Option<i64>, AppError> {
+    conn.transaction(|conn| {
+        let rows_affected = insert_into(m_user::table)
+            .values(&user)
+            .execute(conn)
+            // a duplicate email maps to DataExist
+            .map_err(AppError::from)?;
+        if rows_affected == 0 {
+            return Ok(None);
+        }
+        let mut new_id = user.id;
+        if new_id == 0 {
+            new_id = diesel::select(last_insert_id())
+                .get_result::<u64>(conn)
+                .map_err(|error| AppError::Other(format!("query failed: {}", error)))? as i64;
+        }
+
+        audit(conn, new_id, AuditAction::CREATE, None)?;
+        return Ok(Some(new_id));
+    })
+}
+
+pub fn update_user(conn: &mut MysqlConnection, user: MUser) -> Result<Option<()>, AppError> {
+    conn.transaction(|conn| {
+        let before = find_by_id(conn, user.id, true)?;
+        let rows_affected = update(m_user::table.filter(m_user::id.eq(user.id)))
+            .set((
+                m_user::modified_by.eq(user.modified_by),
+                m_user::modified_on.eq(user.modified_on),
+                m_user::deleted_by.eq(user.deleted_by),
+                m_user::deleted_on.eq(user.deleted_on),
+                m_user::is_delete.eq(user.is_delete),
+                m_user::biodata_id.eq(user.biodata_id),
+                m_user::role_id.eq(user.role_id),
+                m_user::email.eq(user.email),
+                m_user::password.eq(user.password),
+                m_user::is_locked.eq(user.is_locked),
+            ))
+            .execute(conn)
+            // a duplicate email maps to DataExist
+            .map_err(AppError::from)?;
+        if rows_affected > 0 {
+            audit(conn, user.id, AuditAction::UPDATE, before)?;
+            return Ok(Some(()));
+        }
+        return Ok(None);
+    })
+}
+
+/// Page of users over the joined tables. `_filter` and `_sort` ids go
+/// through `FILTER_COLUMNS`, so `role.code` or `biodata.fullname` work, and
+/// values are bound rather than cleansed, so emails keep their `@` and `.`.
+pub fn pagination(
+    conn: &mut MysqlConnection,
+    page: i64,
+    size: i64,
+    filters: Vec<Filter>,
+    sorts: Vec<Sort>,
+    search: String,
+    include_deleted: bool,
+) -> Result<(Vec<MUserResponse>, i64), AppError> {
+    let query_table = "FROM m_user u
+            LEFT JOIN m_biodata b ON b.id = u.biodata_id
+            LEFT JOIN m_role r ON r.id = u.role_id";
+    let (query_where, values) = where_clause(filters, search, include_deleted)?;
+
+    // Sort, the id keeps pages stable
+    let mut query_sort = "ORDER BY u.id ASC".to_string();
+    if let Some(sort) = sorts.first() {
+        let sort_asc = if sort.desc { "DESC" } else { "ASC" };
+        query_sort = format!("ORDER BY {} {}, u.id ASC", column_of("_sort", &sort.id)?, sort_asc);
+    }
+
+    let query = format!(
+        "SELECT u.id AS id {} {} {} LIMIT {} OFFSET {}",
+        query_table,
+        query_where,
+        query_sort,
+        size,
+        size * page
+    );
+    let query_count = format!("SELECT COUNT(*) AS count {} {}", query_table, query_where);
+    log::info!("repository > find_m_user_page > query: {:#?}, values: {:?}", query, values);
+
+    let mut query_ids = sql_query(query).into_boxed::<Mysql>();
+    let mut query_total = sql_query(query_count).into_boxed::<Mysql>();
+    for value in values {
+        query_ids = query_ids.bind::<diesel::sql_types::Text, _>(value.clone());
+        query_total = query_total.bind::<diesel::sql_types::Text, _>(value);
+    }
+    let ids: Vec<i64> = query_ids
+        .load::<IdResult>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?
+        .into_iter()
+        .map(|row| row.id)
+        .collect();
+    let results = query_total
+        .load::<CountResult>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+
+    let mut rows = joined!()
+        .filter(m_user::id.eq_any(&ids))
+        .load::<UserRow>(conn)
+        .map_err(|error| AppError::Other(format!("query failed: {}", error)))?;
+    rows.sort_by_key(|(user, _, _)| ids.iter().position(|id| *id == user.id));
+    Ok((rows.into_iter().map(to_response).collect(), results[0].count))
+}
+
+/// `WHERE` of the joined pagination query and the values of its `?`.
+fn where_clause(
+    filters: Vec<Filter>,
+    search: String,
+    include_deleted: bool,
+) -> Result<(String, Vec<String>), AppError> {
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+
+    // Search
+    if !search.is_empty() {
+        conditions.push("(u.email LIKE CONCAT('%', ?, '%') OR b.fullname LIKE CONCAT('%', ?, '%'))".to_string());
+        values.push(search.clone());
+        values.push(search);
+    }
+
+    // Filter
+    for filter in filters {
+        let column = column_of("_filter", &filter.id)?;
+        let condition = match filter.match_mode {
+            FilterMatchMode::CONTAINS => format!("{} LIKE CONCAT('%', ?, '%')", column),
+            FilterMatchMode::SW => format!("{} LIKE CONCAT(?, '%')", column),
+            FilterMatchMode::EW => format!("{} LIKE CONCAT('%', ?)", column),
+            FilterMatchMode::BETWEEN => continue,
+            FilterMatchMode::EQUALS => format!("{} = ?", column),
+            FilterMatchMode::NOT => format!("{} <> ?", column),
+            FilterMatchMode::LT => format!("{} < ?", column),
+            FilterMatchMode::GT => format!("{} > ?", column),
+        };
+        conditions.push(condition);
+        values.push(filter.value);
+    }
+
+    // Soft delete
+    if !include_deleted {
+        conditions.push("u.is_delete = 0".to_string());
+    }
+
+    if conditions.is_empty() {
+        return Ok((String::new(), values));
+    }
+    Ok((format!("WHERE {}", conditions.join(" AND ")), values))
+}
+
+fn column_of(field: &'static str, id: &str) -> Result<&'static str, AppError> {
+    match FILTER_COLUMNS.iter().find(|(name, _)| *name == id) {
+        Some((_, column)) => Ok(column),
+        None => {
+            let allowed: Vec<&str> = FILTER_COLUMNS.iter().map(|(name, _)| *name).collect();
+            let mut errors = validator::ValidationErrors::new();
+            errors.add(
+                field,
+                validator::ValidationError::new("column")
+                    .with_message(format!("unknown column {}, allowed: {}", id, allowed.join(",")).into()),
+            );
+            Err(AppError::InvalidRequest(errors))
+        }
+    }
+}
+
+fn to_response((user, biodata, role): UserRow) -> MUserResponse {
+    MUserResponse { user, biodata, role }
+}
+
+/// Record a change of the row, `before` read ahead of the write. The
+/// password hash is not serialized, a new one is only marked as changed.
+fn audit(
+    conn: &mut MysqlConnection,
+    user_id: i64,
+    action: AuditAction,
+    before: Option<MUser>,
+) -> Result<(), AppError> {
+    let after = find_by_id(conn, user_id, true)?;
+    let mut redacted = Vec::new();
+    if let (Some(before), Some(after)) = (&before, &after)
+        && before.password != after.password
+    {
+        redacted.push("password");
+    }
+    audit_log_service::record_redacted(
+        conn,
+        AUDIT_ENTITY,
+        user_id,
+        action,
+        before.as_ref(),
+        after.as_ref(),
+        &redacted,
+    )
+}
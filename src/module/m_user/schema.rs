@@ -0,0 +1,200 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::{Insertable, Queryable, QueryableByName};
+use diesel::Selectable;
+use serde::{Deserialize, Serialize};
+
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::config::id_generator;
+use crate::diesel_schema::{m_biodata, m_user};
+use crate::dto::response::app_error::AppError;
+use crate::module::m_role::schema::MRole;
+use crate::util::serializer::{date_serializer, option_date_serializer};
+
+#[derive(
+    Debug,
+    Deserialize,
+    Serialize,
+    Clone,
+    Queryable,
+    QueryableByName,
+    Insertable,
+    Selectable
+)]
+#[diesel(table_name = m_user)]
+pub struct MUser {
+    pub id: i64,
+    pub biodata_id: Option<i64>,
+    pub role_id: Option<i64>,
+    pub email: Option<String>,
+    /// argon2 hash, never part of a response or the audit log
+    #[serde(skip)]
+    pub password: Option<String>,
+    pub is_locked: Option<bool>,
+    #[serde(with = "option_date_serializer")]
+    pub last_login: Option<NaiveDateTime>,
+    pub login_attempt: Option<i32>,
+    pub created_by: i64,
+    #[serde(with = "date_serializer")]
+    pub created_on: NaiveDateTime,
+    pub modified_by: Option<i64>,
+    #[serde(with = "option_date_serializer")]
+    pub modified_on: Option<NaiveDateTime>,
+    pub deleted_by: Option<i64>,
+    #[serde(with = "option_date_serializer")]
+    pub deleted_on: Option<NaiveDateTime>,
+    pub is_delete: bool,
+}
+
+impl MUser {
+    pub fn from_create_request(request: MUserRequest, password_hash: String) -> Result<MUser, AppError> {
+        let date_now = chrono::Utc::now().naive_utc();
+        let is_delete = request.is_delete.unwrap_or(false);
+        let mut deleted_by: Option<i64> = None;
+        let mut deleted_on: Option<NaiveDateTime> = None;
+        if is_delete {
+            deleted_by = Some(0);
+            deleted_on = Some(date_now);
+        }
+        Ok(MUser {
+            id: id_generator::next_id()?,
+            biodata_id: request.biodata_id,
+            role_id: request.role_id,
+            email: request.email,
+            password: Some(password_hash),
+            is_locked: Some(request.is_locked.unwrap_or(false)),
+            last_login: None,
+            login_attempt: Some(0),
+            created_by: 0,
+            created_on: date_now,
+            modified_by: None,
+            modified_on: None,
+            deleted_by: deleted_by,
+            deleted_on: deleted_on,
+            is_delete: is_delete,
+        })
+    }
+    /// `password_hash` replaces the stored one when given.
+    pub fn from_update_request(request: MUserRequest, existing: MUser, password_hash: Option<String>) -> MUser {
+        let date_now = chrono::Utc::now().naive_utc();
+        let is_delete = request.is_delete.unwrap_or(false);
+        let mut deleted_by: Option<i64> = None;
+        let mut deleted_on: Option<NaiveDateTime> = None;
+        if is_delete {
+            deleted_by = Some(0);
+            deleted_on = Some(date_now);
+        }
+        MUser {
+            id: existing.id,
+            biodata_id: request.biodata_id,
+            role_id: request.role_id,
+            email: request.email,
+            password: password_hash.or(existing.password),
+            is_locked: request.is_locked.or(existing.is_locked),
+            last_login: existing.last_login,
+            login_attempt: existing.login_attempt,
+            created_by: existing.created_by,
+            created_on: existing.created_on,
+            modified_by: Some(0),
+            modified_on: Some(date_now),
+            deleted_by: deleted_by,
+            deleted_on: deleted_on,
+            is_delete: is_delete,
+        }
+    }
+}
+
+/// The biodata a user response embeds, without the image content.
+#[derive(Debug, Serialize, Clone, Queryable, Selectable)]
+#[diesel(table_name = m_biodata)]
+pub struct MUserBiodata {
+    pub id: i64,
+    pub fullname: Option<String>,
+    pub mobile_phone: Option<String>,
+    pub image_path: Option<String>,
+}
+
+/// A user with its biodata and role, read through the `m_user` joins.
+#[derive(Debug, Serialize, Clone)]
+pub struct MUserResponse {
+    #[serde(flatten)]
+    pub user: MUser,
+    pub biodata: Option<MUserBiodata>,
+    pub role: Option<MRole>,
+}
+
+/// Entity name of the audit log records of this table.
+pub const AUDIT_ENTITY: &str = "m_user";
+
+/// Ids `_filter` and `_sort` accept, with the column of the joined query
+/// (`m_user u`, `m_biodata b`, `m_role r`) each one reads.
+pub const FILTER_COLUMNS: &[(&str, &str)] = &[
+    ("id", "u.id"),
+    ("email", "u.email"),
+    ("biodata_id", "u.biodata_id"),
+    ("role_id", "u.role_id"),
+    ("is_locked", "u.is_locked"),
+    ("last_login", "u.last_login"),
+    ("login_attempt", "u.login_attempt"),
+    ("created_on", "u.created_on"),
+    ("modified_on", "u.modified_on"),
+    ("deleted_on", "u.deleted_on"),
+    ("is_delete", "u.is_delete"),
+    ("biodata.fullname", "b.fullname"),
+    ("biodata.mobile_phone", "b.mobile_phone"),
+    ("role.code", "r.code"),
+    ("role.name", "r.name"),
+    ("role.level", "r.level"),
+];
+
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct MUserRequest {
+    /// ignored on create, the id is generated by the server
+    #[validate(range(min = 1, message = "must be greater than 0"))]
+    pub id: Option<i64>,
+    /// unique
+    #[validate(
+        email(message = "must be an email"),
+        length(max = 100, message = "must be at most 100 chars"),
+        required(message = "mandatory")
+    )]
+    pub email: Option<String>,
+    /// plain text, mandatory on create, replaces the stored one on update when given
+    #[serde(skip_serializing)]
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 chars"))]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1, message = "must be greater than 0"))]
+    pub biodata_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1, message = "must be greater than 0"))]
+    pub role_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_locked: Option<bool>,
+    #[validate(required(message = "mandatory"))]
+    pub is_delete: Option<bool>,
+}
+
+impl MUserRequest {
+    /// Id of the row an update targets, mandatory there unlike on create.
+    pub fn require_id(&self) -> Result<i64, AppError> {
+        match self.id {
+            Some(value) => Ok(value),
+            None => Err(required("id")),
+        }
+    }
+
+    /// Password of a new user, optional on update only.
+    pub fn require_password(&self) -> Result<&str, AppError> {
+        match &self.password {
+            Some(value) => Ok(value),
+            None => Err(required("password")),
+        }
+    }
+}
+
+fn required(field: &'static str) -> AppError {
+    let mut errors = ValidationErrors::new();
+    errors.add(field, ValidationError::new("required").with_message("mandatory".into()));
+    AppError::InvalidRequest(errors)
+}
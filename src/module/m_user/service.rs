@@ -0,0 +1,138 @@
+use diesel::MysqlConnection;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::{
+    dto::response::app_error::AppError,
+    middleware::auth_middleware::ADMIN_ROLE,
+    module::{
+        m_biodata::repository as m_biodata_repository,
+        m_role::{
+            repository as m_role_repository,
+            schema::{MRole, MRoleRequest},
+        },
+        m_user::{
+            repository,
+            schema::{MUser, MUserRequest, MUserResponse},
+        },
+    },
+    util::{password, unit_of_work},
+};
+
+/// Validate, hash the password and insert one user, returned with its
+/// biodata and role.
+pub async fn create_user(
+    conn: &mut MysqlConnection,
+    m_user_request: MUserRequest,
+) -> Result<MUserResponse, AppError> {
+    m_user_request.validate().map_err(AppError::InvalidRequest)?;
+    let password_hash = password::hash_blocking(m_user_request.require_password()?.to_string()).await?;
+
+    unit_of_work::run(conn, |conn| {
+        check_references(conn, &m_user_request)?;
+
+        let new_m_user = MUser::from_create_request(m_user_request, password_hash)?;
+        let new_id = match repository::insert_user(conn, new_m_user)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::Other("save data failed".to_string()));
+            }
+        };
+        match repository::find_response_by_id(conn, new_id, true)? {
+            Some(value) => Ok(value),
+            None => Err(AppError::Other("save data failed".to_string())),
+        }
+    })
+}
+
+/// Validate and update one user under the row lock. The stored password is
+/// kept unless the request carries a new one.
+pub async fn update_user(
+    conn: &mut MysqlConnection,
+    m_user_request: MUserRequest,
+) -> Result<MUserResponse, AppError> {
+    m_user_request.validate().map_err(AppError::InvalidRequest)?;
+    let user_id = m_user_request.require_id()?;
+    let password_hash = match &m_user_request.password {
+        Some(value) => Some(password::hash_blocking(value.clone()).await?),
+        None => None,
+    };
+
+    unit_of_work::run(conn, |conn| {
+        let existing = match repository::lock_by_id(conn, user_id)? {
+            Some(value) => value,
+            None => {
+                return Err(AppError::NotFound);
+            }
+        };
+        check_references(conn, &m_user_request)?;
+
+        let new_m_user = MUser::from_update_request(m_user_request, existing, password_hash);
+        if repository::update_user(conn, new_m_user)?.is_none() {
+            return Err(AppError::NotFound);
+        }
+        match repository::find_response_by_id(conn, user_id, true)? {
+            Some(value) => Ok(value),
+            None => Err(AppError::NotFound),
+        }
+    })
+}
+
+/// Create an administrator, the `create-admin` command seeding the first one
+/// since the user endpoints themselves require an administrator. The
+/// `ADMIN_ROLE` role is created when it does not exist yet.
+pub async fn create_admin(
+    conn: &mut MysqlConnection,
+    email: String,
+    plain_password: String,
+) -> Result<MUserResponse, AppError> {
+    let role_id = unit_of_work::run(conn, |conn| {
+        if let Some(role) = m_role_repository::find_by_code(conn, ADMIN_ROLE)? {
+            return Ok(role.id);
+        }
+        let new_m_role = MRole::from_create_request(MRoleRequest {
+            id: None,
+            code: Some(ADMIN_ROLE.to_string()),
+            name: Some("Administrator".to_string()),
+            level: None,
+            is_delete: Some(false),
+        })?;
+        match m_role_repository::insert_role(conn, new_m_role)? {
+            Some(value) => Ok(value),
+            None => Err(AppError::Other("save data failed".to_string())),
+        }
+    })?;
+
+    create_user(
+        conn,
+        MUserRequest {
+            id: None,
+            email: Some(email),
+            password: Some(plain_password),
+            biodata_id: None,
+            role_id: Some(role_id),
+            is_locked: None,
+            is_delete: Some(false),
+        },
+    )
+    .await
+}
+
+/// The biodata and role a request points at must exist and not be deleted.
+fn check_references(conn: &mut MysqlConnection, m_user_request: &MUserRequest) -> Result<(), AppError> {
+    let mut errors = ValidationErrors::new();
+    if let Some(biodata_id) = m_user_request.biodata_id
+        && m_biodata_repository::find_by_id(conn, biodata_id, false)?.is_none()
+    {
+        errors.add("biodata_id", ValidationError::new("reference").with_message("not found".into()));
+    }
+    if let Some(role_id) = m_user_request.role_id
+        && m_role_repository::find_by_id(conn, role_id, false)?.is_none()
+    {
+        errors.add("role_id", ValidationError::new("reference").with_message("not found".into()));
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::InvalidRequest(errors))
+}
@@ -1,6 +1,13 @@
+pub mod audit_log;
+pub mod auth;
 pub mod health;
 pub mod hello_world;
 pub mod m_biodata;
 pub mod m_file;
+pub mod m_file_upload;
+pub mod m_file_variant;
+pub mod m_file_version;
+pub mod m_role;
+pub mod m_user;
 
 pub mod redis_pubsub;
\ No newline at end of file
@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
 use diesel::{r2d2, MysqlConnection};
+
+use crate::config::{scanner::main::ScannerEngine, storage::main::StorageEngine};
 // use diesel_async::{pooled_connection::AsyncDieselConnectionManager, AsyncPgConnection};
 
 
@@ -9,5 +11,7 @@ use diesel::{r2d2, MysqlConnection};
 pub struct AppState {
     // pub diesel_pool_postgres_async: bb8::Pool<AsyncDieselConnectionManager<AsyncPgConnection>>,
     pub diesel_pool_mysql: Arc<r2d2::Pool<r2d2::ConnectionManager<MysqlConnection>>>,
+    pub storage: Arc<StorageEngine>,
+    pub scanner: Arc<ScannerEngine>,
     pub status: String
 }
@@ -0,0 +1,220 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde_json::{Map, Value};
+use validator::{ValidationError, ValidationErrors};
+
+use crate::{
+    dto::{
+        database::JsonRow,
+        enumerator::{
+            aggregate_function::AggregateFunction, date_bucket::DateBucket,
+            filter_data_type::FilterDataType, filter_match_mode::FilterMatchMode,
+        },
+        request::{
+            aggregate_request::{Aggregate, Aggregation},
+            filter_request::Filter,
+        },
+        response::{aggregate_response::AggregateColumn, app_error::AppError},
+    },
+    util::string_manipulation,
+};
+
+/// MySQL spelling of the `date_serializer` format
+const SQL_DATE_FORMAT: &str = "%Y-%m-%d %H:%i:%s";
+const MAX_ROWS: i64 = 10_000;
+
+/// Build the `SELECT JSON_OBJECT(...) AS json_row` query of an aggregation
+/// over `table`. `columns` lists what may be grouped or aggregated.
+pub fn build_query(
+    table: &str,
+    columns: &[(&str, FilterDataType)],
+    aggregation: Aggregation,
+    filters: Vec<Filter>,
+    include_deleted: bool,
+) -> Result<(String, Vec<AggregateColumn>), AppError> {
+    let data_type_of = |field: &'static str, column: &str| {
+        columns
+            .iter()
+            .find(|(name, _)| *name == column)
+            .map(|(_, data_type)| data_type.clone())
+            .ok_or_else(|| invalid(field, format!("unknown column {}", column)))
+    };
+
+    // filters end up in the WHERE clause as column names as well
+    for filter in &filters {
+        data_type_of("_filter", &string_manipulation::cleanse_string(&filter.id))?;
+    }
+
+    let mut result_columns = Vec::new();
+    let mut select = Vec::new();
+    let mut group_by = Vec::new();
+
+    for group in aggregation._group_by.unwrap_or_default() {
+        let column = string_manipulation::cleanse_string(&group.id);
+        let data_type = data_type_of("_group_by", &column)?;
+        let (name, expression, result_type) = match group.bucket {
+            Some(bucket) => {
+                if data_type != FilterDataType::DATE {
+                    return Err(invalid("_group_by", format!("{} is not a datetime column", column)));
+                }
+                let (suffix, format) = match bucket {
+                    DateBucket::HOUR => ("hour", "%Y-%m-%d %H:00"),
+                    DateBucket::DAY => ("day", "%Y-%m-%d"),
+                    DateBucket::WEEK => ("week", "%x-W%v"),
+                    DateBucket::MONTH => ("month", "%Y-%m"),
+                    DateBucket::YEAR => ("year", "%Y"),
+                };
+                (
+                    format!("{}_{}", column, suffix),
+                    format!("DATE_FORMAT({}, '{}')", column, format),
+                    FilterDataType::TEXT,
+                )
+            }
+            None if data_type == FilterDataType::DATE => (
+                column.clone(),
+                format!("DATE_FORMAT({}, '{}')", column, SQL_DATE_FORMAT),
+                data_type,
+            ),
+            None => (column.clone(), column.clone(), data_type),
+        };
+        select.push(format!("'{}', {}", name, expression));
+        group_by.push(expression);
+        result_columns.push(AggregateColumn {
+            name,
+            data_type: result_type,
+        });
+    }
+
+    let mut aggregates = aggregation._aggregate.unwrap_or_default();
+    if aggregates.is_empty() {
+        aggregates.push(Aggregate {
+            function: AggregateFunction::COUNT,
+            id: None,
+        });
+    }
+    for aggregate in aggregates {
+        let function = format!("{:?}", aggregate.function);
+        let (name, expression, result_type) = match aggregate.id {
+            None if aggregate.function == AggregateFunction::COUNT => {
+                ("count".to_string(), "COUNT(*)".to_string(), FilterDataType::NUMBER)
+            }
+            None => {
+                return Err(invalid("_aggregate", format!("{} needs a column", function)));
+            }
+            Some(value) => {
+                let column = string_manipulation::cleanse_string(&value);
+                let data_type = data_type_of("_aggregate", &column)?;
+                let name = format!("{}_{}", function.to_lowercase(), column);
+                match aggregate.function {
+                    AggregateFunction::COUNT => (name, format!("COUNT({})", column), FilterDataType::NUMBER),
+                    AggregateFunction::SUM | AggregateFunction::AVG => {
+                        if data_type != FilterDataType::NUMBER {
+                            return Err(invalid(
+                                "_aggregate",
+                                format!("{} needs a number column, {} is not", function, column),
+                            ));
+                        }
+                        (name, format!("{}({})", function, column), FilterDataType::NUMBER)
+                    }
+                    AggregateFunction::MIN | AggregateFunction::MAX => {
+                        let expression = if data_type == FilterDataType::DATE {
+                            format!("DATE_FORMAT({}({}), '{}')", function, column, SQL_DATE_FORMAT)
+                        } else {
+                            format!("{}({})", function, column)
+                        };
+                        (name, expression, data_type)
+                    }
+                }
+            }
+        };
+        select.push(format!("'{}', {}", name, expression));
+        result_columns.push(AggregateColumn {
+            name,
+            data_type: result_type,
+        });
+    }
+
+    let mut query = format!(
+        "SELECT JSON_OBJECT({}) AS json_row FROM {} {}",
+        select.join(", "),
+        table,
+        where_clause(filters, include_deleted)
+    );
+    if !group_by.is_empty() {
+        query = format!(
+            "{} GROUP BY {} ORDER BY {}",
+            query,
+            group_by.join(", "),
+            group_by.join(", ")
+        );
+    }
+    query = format!("{} LIMIT {}", query, MAX_ROWS);
+    Ok((query, result_columns))
+}
+
+/// Same `_filter` semantics as pagination, the ids checked by `build_query`.
+fn where_clause(filters: Vec<Filter>, include_deleted: bool) -> String {
+    let mut conditions = Vec::new();
+    for filter in filters {
+        let filter_id = string_manipulation::cleanse_string(&filter.id);
+        let filter_value = string_manipulation::cleanse_string(&filter.value);
+        let condition = match filter.match_mode {
+            FilterMatchMode::CONTAINS => format!("{} LIKE '%{}%'", filter_id, filter_value),
+            FilterMatchMode::SW => format!("{} LIKE '{}%'", filter_id, filter_value),
+            FilterMatchMode::EW => format!("{} LIKE '%{}'", filter_id, filter_value),
+            FilterMatchMode::BETWEEN => continue,
+            FilterMatchMode::EQUALS => format!("{} = '{}'", filter_id, filter_value),
+            FilterMatchMode::NOT => format!("{} <> '{}'", filter_id, filter_value),
+            FilterMatchMode::LT => format!("{} < '{}'", filter_id, filter_value),
+            FilterMatchMode::GT => format!("{} > '{}'", filter_id, filter_value),
+        };
+        conditions.push(condition);
+    }
+    if !include_deleted {
+        conditions.push("is_delete = 0".to_string());
+    }
+    if conditions.is_empty() {
+        return String::new();
+    }
+    format!("WHERE {}", conditions.join(" AND "))
+}
+
+fn invalid(field: &'static str, message: String) -> AppError {
+    let mut errors = ValidationErrors::new();
+    errors.add(field, ValidationError::new("aggregate").with_message(message.into()));
+    AppError::InvalidRequest(errors)
+}
+
+/// Decode the rows in column order, booleans as `true`/`false` and datetimes
+/// in local time like `date_serializer`.
+pub fn to_rows(json_rows: Vec<JsonRow>, columns: &[AggregateColumn]) -> Result<Vec<Value>, AppError> {
+    json_rows
+        .into_iter()
+        .map(|json_row| {
+            let mut object: Map<String, Value> = serde_json::from_str(&json_row.json_row)
+                .map_err(|error| AppError::Other(format!("decode row failed: {}", error)))?;
+            let mut row = Map::new();
+            for column in columns {
+                let value = object.remove(&column.name).unwrap_or(Value::Null);
+                let value = match (&column.data_type, value) {
+                    (FilterDataType::BOOLEAN, Value::Number(number)) => {
+                        Value::Bool(number.as_i64().unwrap_or_default() != 0)
+                    }
+                    (FilterDataType::DATE, Value::String(text)) => Value::String(to_local(&text)),
+                    (_, value) => value,
+                };
+                row.insert(column.name.clone(), value);
+            }
+            Ok(Value::Object(row))
+        })
+        .collect()
+}
+
+fn to_local(utc: &str) -> String {
+    match NaiveDateTime::parse_from_str(utc, "%Y-%m-%d %H:%M:%S") {
+        Ok(value) => {
+            let datetime: DateTime<Local> = Local.from_utc_datetime(&value);
+            datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+        }
+        Err(_) => utc.to_string(),
+    }
+}
@@ -0,0 +1,104 @@
+use axum::{http::StatusCode, Json};
+use diesel::{Connection, MysqlConnection};
+use serde_json::json;
+
+use crate::dto::{
+    enumerator::bulk_mode::BulkMode,
+    response::{app_error::AppError, app_response::AppResponse, bulk_response::BulkItemResult},
+};
+
+/// Run `operation` for every item and collect a result per index.
+/// `operation` returns the id of the row it touched. Returns whether the
+/// work was kept, which is always the case in best effort mode.
+pub fn run<T, F>(
+    conn: &mut MysqlConnection,
+    mode: BulkMode,
+    items: Vec<T>,
+    success_status: StatusCode,
+    mut operation: F,
+) -> (Vec<BulkItemResult>, bool)
+where
+    F: FnMut(&mut MysqlConnection, T) -> Result<i64, AppError>,
+{
+    let item_count = items.len();
+    let mut results = Vec::with_capacity(item_count);
+
+    if mode == BulkMode::BestEffort {
+        for (index, item) in items.into_iter().enumerate() {
+            results.push(to_result(index, operation(conn, item), success_status));
+        }
+        return (results, true);
+    }
+
+    let outcome = conn.transaction::<(), diesel::result::Error, _>(|conn| {
+        for (index, item) in items.into_iter().enumerate() {
+            let result = to_result(index, operation(conn, item), success_status);
+            let is_failed = result.error.is_some();
+            results.push(result);
+            if is_failed {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+        }
+        Ok(())
+    });
+    if outcome.is_ok() {
+        return (results, true);
+    }
+
+    // report what the rollback undid and what was never attempted
+    for result in results.iter_mut().filter(|result| result.error.is_none()) {
+        result.status = StatusCode::CONFLICT.as_str().to_string();
+        result.error = Some(json!("rolled back"));
+    }
+    for index in results.len()..item_count {
+        results.push(BulkItemResult {
+            index,
+            id: None,
+            status: StatusCode::CONFLICT.as_str().to_string(),
+            error: Some(json!("not processed")),
+        });
+    }
+    (results, false)
+}
+
+fn to_result(index: usize, result: Result<i64, AppError>, success_status: StatusCode) -> BulkItemResult {
+    match result {
+        Ok(id) => BulkItemResult {
+            index,
+            id: Some(id),
+            status: success_status.as_str().to_string(),
+            error: None,
+        },
+        Err(error) => {
+            let (status_code, error) = error.status_and_error();
+            BulkItemResult {
+                index,
+                id: None,
+                status: status_code.as_str().to_string(),
+                error: Some(error),
+            }
+        }
+    }
+}
+
+/// 200 when the batch was kept, 400 when an atomic batch was rolled back.
+pub fn into_response(
+    results: Vec<BulkItemResult>,
+    committed: bool,
+) -> (StatusCode, Json<AppResponse<Vec<BulkItemResult>>>) {
+    let (status_code, message) = if committed {
+        (StatusCode::OK, "success")
+    } else {
+        (StatusCode::BAD_REQUEST, "error")
+    };
+    (
+        status_code,
+        Json(AppResponse {
+            status: status_code.as_str().to_string(),
+            message: message.to_owned(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            data: Some(results),
+            error: None,
+        }),
+    )
+}
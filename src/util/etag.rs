@@ -0,0 +1,56 @@
+use axum::http::{
+    header::{IF_MATCH, IF_NONE_MATCH},
+    HeaderMap,
+};
+
+use crate::dto::response::app_error::AppError;
+
+/// Strong entity tag of a row, it changes with every bump of `row_version`.
+pub fn of(id: i64, row_version: i64) -> String {
+    format!("\"{}-{}\"", id, row_version)
+}
+
+/// Conditional GET: `true` when `If-None-Match` lists `etag` (weak comparison),
+/// so the client copy is current and a 304 can be sent.
+pub fn is_not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    let Some(value) = headers.get(IF_NONE_MATCH).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    value.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.trim_start_matches("W/") == etag.trim_start_matches("W/")
+    })
+}
+
+/// Bulk items carry the `row_version` they were read at instead of an
+/// `If-Match` header, same outcome: 428 when missing, 412 when stale.
+pub fn check_row_version(id: i64, expected: Option<i64>, current: i64) -> Result<(), AppError> {
+    let Some(expected) = expected else {
+        return Err(AppError::PreconditionRequired);
+    };
+    if expected != current {
+        return Err(AppError::PreconditionFailed(format!(
+            "resource was modified, current etag: {}",
+            of(id, current)
+        )));
+    }
+    Ok(())
+}
+
+/// Writes must carry an `If-Match` listing the current `etag` (strong
+/// comparison), otherwise someone else's change would be overwritten.
+pub fn check_if_match(headers: &HeaderMap, etag: &str) -> Result<(), AppError> {
+    let Some(value) = headers.get(IF_MATCH).and_then(|value| value.to_str().ok()) else {
+        return Err(AppError::PreconditionRequired);
+    };
+    let is_match = value
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate == etag);
+    if !is_match {
+        return Err(AppError::PreconditionFailed(format!(
+            "resource was modified, current etag: {}",
+            etag
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,301 @@
+use std::io;
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header::ACCEPT, HeaderMap, Response},
+};
+use chrono::Local;
+use diesel::{r2d2, MysqlConnection};
+use futures_util::{stream, Stream};
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+use validator::{ValidationError, ValidationErrors};
+
+use crate::dto::{
+    enumerator::export_format::ExportFormat, request::sort_request::Sort,
+    response::app_error::AppError,
+};
+
+/// rows read from the database per query
+pub const PAGE_SIZE: i64 = 500;
+/// CSV pages written ahead of the client
+const CHANNEL_CAPACITY: usize = 4;
+/// data rows that fit a worksheet below its header row
+const MAX_XLSX_ROWS: u32 = 1_048_575;
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+pub type PooledConnection = r2d2::PooledConnection<r2d2::ConnectionManager<MysqlConnection>>;
+
+/// `?format=` wins, then the `Accept` header, CSV otherwise.
+pub fn resolve_format(format: Option<ExportFormat>, headers: &HeaderMap) -> ExportFormat {
+    if let Some(value) = format {
+        return value;
+    }
+    let accept = headers
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if accept.contains(ExportFormat::Xlsx.content_type()) {
+        return ExportFormat::Xlsx;
+    }
+    ExportFormat::Csv
+}
+
+/// Requested columns in request order, every exportable column when none are given.
+pub fn resolve_columns(requested: Option<String>, exportable: &[&str]) -> Result<Vec<String>, AppError> {
+    let requested = requested.unwrap_or_default();
+    let columns: Vec<String> = requested
+        .split(',')
+        .map(|column| column.trim().to_string())
+        .filter(|column| !column.is_empty())
+        .collect();
+    if columns.is_empty() {
+        return Ok(exportable.iter().map(|column| column.to_string()).collect());
+    }
+
+    if let Some(unknown) = columns.iter().find(|column| !exportable.contains(&column.as_str())) {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "_columns",
+            ValidationError::new("column").with_message(
+                format!("unknown column {}, allowed: {}", unknown, exportable.join(",")).into(),
+            ),
+        );
+        return Err(AppError::InvalidRequest(errors));
+    }
+    Ok(columns)
+}
+
+/// LIMIT/OFFSET paging needs a total order, the primary key always breaks ties.
+pub fn stable_sorts(mut sorts: Vec<Sort>) -> Vec<Sort> {
+    if sorts.last().is_none_or(|sort| sort.id != "id") {
+        sorts.push(Sort {
+            id: "id".to_string(),
+            desc: false,
+        });
+    }
+    sorts
+}
+
+/// Serialize entities the way the JSON API does, dates through `date_serializer`.
+pub fn to_rows<T: Serialize>(items: Vec<T>) -> Result<Vec<Value>, AppError> {
+    items
+        .into_iter()
+        .map(|item| {
+            serde_json::to_value(item)
+                .map_err(|error| AppError::Other(format!("serialize failed: {}", error)))
+        })
+        .collect()
+}
+
+/// Write the CSV from a blocking task, Diesel queries block, one page at a
+/// time and return the chunks as a response body stream. A failed page ends
+/// the stream with an error, so the client never takes a truncated file for a
+/// complete one.
+pub fn stream_csv<F>(
+    mut conn: PooledConnection,
+    columns: Vec<String>,
+    mut fetch_page: F,
+) -> impl Stream<Item = Result<Bytes, io::Error>>
+where
+    F: FnMut(&mut MysqlConnection, i64) -> Result<Vec<Value>, AppError> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel::<Result<Bytes, io::Error>>(CHANNEL_CAPACITY);
+
+    tokio::task::spawn_blocking(move || {
+        let mut page = 0;
+        let mut rows: Vec<Value> = Vec::new();
+        loop {
+            let chunk = match csv_chunk(&columns, &rows, page == 0) {
+                Ok(value) => Ok(Bytes::from(value)),
+                Err(error) => {
+                    log::error!("export: write page {} failed: {:?}", page, error);
+                    Err(io::Error::other(format!("write page {} failed: {:?}", page, error)))
+                }
+            };
+            let is_failed = chunk.is_err();
+            if sender.blocking_send(chunk).is_err() {
+                log::error!("export: client went away");
+                return;
+            }
+            if is_failed || (page > 0 && (rows.len() as i64) < PAGE_SIZE) {
+                return;
+            }
+
+            rows = match fetch_page(&mut conn, page) {
+                Ok(value) => value,
+                Err(error) => {
+                    log::error!("export: read page {} failed: {:?}", page, error);
+                    let _ = sender.blocking_send(Err(io::Error::other(format!(
+                        "read page {} failed: {:?}",
+                        page, error
+                    ))));
+                    return;
+                }
+            };
+            page += 1;
+        }
+    });
+
+    stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    })
+}
+
+/// Whole CSV document with a header row, for small files built in memory.
+pub fn build_csv(columns: &[String], rows: &[Value]) -> Result<Vec<u8>, AppError> {
+    csv_chunk(columns, rows, true)
+}
+
+fn csv_chunk(columns: &[String], rows: &[Value], with_header: bool) -> Result<Vec<u8>, AppError> {
+    let mut csv_writer = csv::Writer::from_writer(Vec::new());
+    if with_header {
+        csv_writer
+            .write_record(columns)
+            .map_err(|error| AppError::Other(format!("csv failed: {}", error)))?;
+    }
+    for row in rows {
+        let record: Vec<String> = columns.iter().map(|column| to_text(&row[column])).collect();
+        csv_writer
+            .write_record(&record)
+            .map_err(|error| AppError::Other(format!("csv failed: {}", error)))?;
+    }
+    csv_writer
+        .into_inner()
+        .map_err(|error| AppError::Other(format!("csv failed: {}", error)))
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Single sheet workbook, header row in bold, built in memory for small files.
+pub fn build_xlsx(columns: &[String], rows: &[Value]) -> Result<Vec<u8>, AppError> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    write_xlsx_header(worksheet, columns).map_err(xlsx_error)?;
+    write_xlsx_rows(worksheet, 1, columns, rows).map_err(xlsx_error)?;
+
+    workbook.save_to_buffer().map_err(xlsx_error)
+}
+
+/// Write the workbook page by page from a blocking task into a temporary
+/// file, then stream that file. The worksheet stays in memory until it is
+/// saved, the fetched rows and the finished file do not.
+pub async fn stream_xlsx<F>(
+    mut conn: PooledConnection,
+    columns: Vec<String>,
+    mut fetch_page: F,
+) -> Result<impl Stream<Item = Result<Bytes, io::Error>>, AppError>
+where
+    F: FnMut(&mut MysqlConnection, i64) -> Result<Vec<Value>, AppError> + Send + 'static,
+{
+    let path = std::env::temp_dir().join(format!("export-{}.xlsx", Uuid::new_v4()));
+    let save_path = path.clone();
+    let saved = tokio::task::spawn_blocking(move || -> Result<(), AppError> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        write_xlsx_header(worksheet, &columns).map_err(xlsx_error)?;
+
+        let mut page = 0;
+        let mut row_number: u32 = 1;
+        loop {
+            let rows = fetch_page(&mut conn, page)?;
+            if row_number as usize + rows.len() > MAX_XLSX_ROWS as usize + 1 {
+                return Err(AppError::UnprocessableEntity(format!(
+                    "export exceeds {} rows, narrow the filter or use csv",
+                    MAX_XLSX_ROWS
+                )));
+            }
+            write_xlsx_rows(worksheet, row_number, &columns, &rows).map_err(xlsx_error)?;
+            row_number += rows.len() as u32;
+            if (rows.len() as i64) < PAGE_SIZE {
+                break;
+            }
+            page += 1;
+        }
+        workbook.save(&save_path).map_err(xlsx_error)
+    })
+    .await
+    .map_err(|error| AppError::Other(format!("xlsx failed: {}", error)))?;
+    if let Err(error) = saved {
+        let _ = tokio::fs::remove_file(&path).await;
+        return Err(error);
+    }
+
+    let file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|error| AppError::Other(format!("xlsx failed: {}", error)));
+    // the open handle keeps the content readable after the name is gone
+    let _ = tokio::fs::remove_file(&path).await;
+    Ok(ReaderStream::new(file?))
+}
+
+fn xlsx_error(error: XlsxError) -> AppError {
+    AppError::Other(format!("xlsx failed: {}", error))
+}
+
+fn write_xlsx_header(worksheet: &mut Worksheet, columns: &[String]) -> Result<(), XlsxError> {
+    let header_format = Format::new().set_bold();
+    for (col, column) in columns.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, column, &header_format)?;
+    }
+    Ok(())
+}
+
+fn write_xlsx_rows(
+    worksheet: &mut Worksheet,
+    first_row: u32,
+    columns: &[String],
+    rows: &[Value],
+) -> Result<(), XlsxError> {
+    for (index, row) in rows.iter().enumerate() {
+        let row_number = first_row + index as u32;
+        for (col, column) in columns.iter().enumerate() {
+            let col = col as u16;
+            match &row[column] {
+                Value::Null => {}
+                // ids beyond 2^53 would lose digits as a spreadsheet number
+                Value::Number(number) if number.as_i64().is_some_and(|value| value.abs() > MAX_SAFE_INTEGER) => {
+                    worksheet.write_string(row_number, col, number.to_string())?;
+                }
+                Value::Number(number) => {
+                    worksheet.write_number(row_number, col, number.as_f64().unwrap_or_default())?;
+                }
+                Value::Bool(flag) => {
+                    worksheet.write_boolean(row_number, col, *flag)?;
+                }
+                other => {
+                    worksheet.write_string(row_number, col, to_text(other))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Attachment response named `{prefix}-{timestamp}.{extension}`.
+pub fn response(format: ExportFormat, prefix: &str, body: Body) -> Response<Body> {
+    let file_name = format!(
+        "{}-{}.{}",
+        prefix,
+        Local::now().format("%Y%m%d%H%M%S"),
+        format.extension()
+    );
+    Response::builder()
+        .header("Content-Type", format.content_type())
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", file_name),
+        )
+        .body(body)
+        .unwrap()
+}
@@ -0,0 +1,22 @@
+use serde_json::Value;
+
+/// Apply an RFC 7386 JSON merge patch to `target`: objects are merged
+/// recursively, `null` removes a member and any other value replaces it.
+pub fn apply(target: &mut Value, patch: &Value) {
+    let Value::Object(patch_members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    if let Value::Object(target_members) = target {
+        for (key, value) in patch_members {
+            if value.is_null() {
+                target_members.remove(key);
+            } else {
+                apply(target_members.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
@@ -1,2 +1,13 @@
+pub mod aggregate;
+pub mod bulk;
+pub mod etag;
+pub mod export;
+pub mod merge_patch;
+pub mod password;
+pub mod projection;
+pub mod search;
 pub mod serializer;
-pub mod string_manipulation;
\ No newline at end of file
+pub mod signed_url;
+pub mod string_manipulation;
+pub mod token;
+pub mod unit_of_work;
\ No newline at end of file
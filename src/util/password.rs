@@ -0,0 +1,32 @@
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+
+use crate::dto::response::app_error::AppError;
+
+/// Argon2id PHC string of `password`, salted with the random bytes of a v4 uuid.
+pub fn hash(password: &str) -> Result<String, AppError> {
+    let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())
+        .map_err(|error| AppError::Other(format!("hash password failed: {}", error)))?;
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|error| AppError::Other(format!("hash password failed: {}", error)))?;
+    Ok(hash.to_string())
+}
+
+/// `hash` on the blocking pool, argon2 is CPU bound and would stall the
+/// async workers.
+pub async fn hash_blocking(password: String) -> Result<String, AppError> {
+    tokio::task::spawn_blocking(move || hash(&password))
+        .await
+        .map_err(|error| AppError::Other(format!("hash password failed: {}", error)))?
+}
+
+/// Whether `password` matches a hash produced by `hash`.
+pub fn verify(password: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(parsed) => Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok(),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,93 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use validator::{ValidationError, ValidationErrors};
+
+use crate::dto::response::app_error::AppError;
+
+/// Columns of a table as the projection sees them.
+pub struct Columns {
+    pub all: &'static [&'static str],
+    /// may be read as `NULL` when not requested
+    pub nullable: &'static [&'static str],
+    /// large columns left out of lists unless requested
+    pub blobs: &'static [&'static str],
+}
+
+/// Requested fields in request order. Without `_fields` a single row gets
+/// every column and a list every column but the blobs.
+pub fn resolve(requested: Option<String>, columns: &Columns, is_list: bool) -> Result<Vec<String>, AppError> {
+    let fields: Vec<String> = requested
+        .unwrap_or_default()
+        .split(',')
+        .map(|field| field.trim().to_string())
+        .filter(|field| !field.is_empty())
+        .collect();
+    if fields.is_empty() {
+        return Ok(columns
+            .all
+            .iter()
+            .filter(|column| !is_list || !columns.blobs.contains(column))
+            .map(|column| column.to_string())
+            .collect());
+    }
+
+    if let Some(unknown) = fields.iter().find(|field| !columns.all.contains(&field.as_str())) {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "_fields",
+            ValidationError::new("field").with_message(
+                format!("unknown field {}, allowed: {}", unknown, columns.all.join(",")).into(),
+            ),
+        );
+        return Err(AppError::InvalidRequest(errors));
+    }
+    Ok(fields)
+}
+
+/// SELECT list that still maps onto the full entity: nullable columns that
+/// were not requested are read as `NULL`, the rest are small enough to read.
+pub fn select_list(fields: &[String], columns: &Columns) -> String {
+    columns
+        .all
+        .iter()
+        .map(|column| {
+            if columns.nullable.contains(column) && !fields.iter().any(|field| field == column) {
+                format!("NULL AS {}", column)
+            } else {
+                column.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Requested fields plus the columns the server reads for itself, e.g. the
+/// ones a search scores and highlights. Only `fields` go to the output.
+pub fn with_required(fields: &[String], required: &[&str]) -> Vec<String> {
+    let mut read_fields = fields.to_vec();
+    for column in required {
+        if !read_fields.iter().any(|field| field == column) {
+            read_fields.push(column.to_string());
+        }
+    }
+    read_fields
+}
+
+/// Entity serialized with only the requested fields.
+pub fn to_value<T: Serialize>(item: &T, fields: &[String]) -> Result<Value, AppError> {
+    let value = serde_json::to_value(item)
+        .map_err(|error| AppError::Other(format!("serialize failed: {}", error)))?;
+    let mut projected = Map::new();
+    if let Value::Object(mut object) = value {
+        for field in fields {
+            if let Some(field_value) = object.remove(field) {
+                projected.insert(field.clone(), field_value);
+            }
+        }
+    }
+    Ok(Value::Object(projected))
+}
+
+pub fn to_values<T: Serialize>(items: &[T], fields: &[String]) -> Result<Vec<Value>, AppError> {
+    items.iter().map(|item| to_value(item, fields)).collect()
+}
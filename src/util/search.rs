@@ -0,0 +1,279 @@
+use std::collections::{BTreeMap, HashMap};
+
+use diesel::MysqlConnection;
+use serde::Serialize;
+use serde_json::Value;
+use validator::{ValidationError, ValidationErrors};
+
+use crate::{
+    dto::{
+        database::SearchRow, enumerator::search_mode::SearchMode,
+        response::{app_error::AppError, search_response::SearchHit},
+    },
+    util::projection,
+};
+
+/// rows a fuzzy search ranks in memory
+pub const FUZZY_CANDIDATES: i64 = 1000;
+/// leading characters a fuzzy word has to share, InnoDB's `innodb_ft_min_token_size`
+const FUZZY_PREFIX_LENGTH: usize = 3;
+
+/// Lowercased words of `_q`, anything but letters and digits separates them
+/// so boolean mode operators never reach MySQL.
+pub fn terms(q: Option<String>) -> Result<Vec<String>, AppError> {
+    let terms = words(&q.unwrap_or_default())
+        .into_iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<String>>();
+    if terms.is_empty() {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "_q",
+            ValidationError::new("required").with_message("search words are required".into()),
+        );
+        return Err(AppError::InvalidRequest(errors));
+    }
+    Ok(terms)
+}
+
+/// Ranked ids and their count, `?` binds: against, against, include deleted,
+/// limit, offset for the first and against, include deleted for the count.
+pub fn queries(table: &str, searchable: &[&str], mode: SearchMode) -> (String, String) {
+    let matcher = format!(
+        "MATCH({}) AGAINST (? {})",
+        searchable.join(", "),
+        match mode {
+            SearchMode::Natural => "IN NATURAL LANGUAGE MODE",
+            SearchMode::Prefix | SearchMode::Fuzzy => "IN BOOLEAN MODE",
+        }
+    );
+    let query = format!(
+        "SELECT id, {} * 1.0E0 AS score
+            FROM {}
+            WHERE {} AND (? OR is_delete = 0)
+            ORDER BY score DESC, id ASC
+            LIMIT ? OFFSET ?",
+        matcher, table, matcher
+    );
+    let query_count = format!(
+        "SELECT COUNT(*) AS count
+            FROM {}
+            WHERE {} AND (? OR is_delete = 0)",
+        table, matcher
+    );
+    (query, query_count)
+}
+
+/// The `AGAINST` string of the mode.
+pub fn against(terms: &[String], mode: SearchMode) -> String {
+    match mode {
+        SearchMode::Natural => terms.join(" "),
+        SearchMode::Prefix => terms
+            .iter()
+            .map(|term| format!("+{}*", term))
+            .collect::<Vec<String>>()
+            .join(" "),
+        SearchMode::Fuzzy => terms
+            .iter()
+            .map(|term| format!("{}*", term.chars().take(FUZZY_PREFIX_LENGTH).collect::<String>()))
+            .collect::<Vec<String>>()
+            .join(" "),
+    }
+}
+
+/// `IN (...)` list of the matched ids.
+pub fn id_list(rows: &[SearchRow]) -> String {
+    rows.iter()
+        .map(|row| row.id.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Entities paired with their score, in the order of the ranked ids.
+pub fn in_rank_order<T, F>(rows: &[SearchRow], items: Vec<T>, id_of: F) -> Vec<(T, f64)>
+where
+    F: Fn(&T) -> i64,
+{
+    let mut by_id: HashMap<i64, T> = items.into_iter().map(|item| (id_of(&item), item)).collect();
+    rows.iter()
+        .filter_map(|row| by_id.remove(&row.id).map(|item| (item, row.score)))
+        .collect()
+}
+
+/// One page of results with the total. Natural and prefix pages come ranked
+/// from MySQL; it has no edit distance, so a fuzzy search reads the rows
+/// sharing the first letters of a word and ranks them here.
+pub fn page<T, F>(
+    conn: &mut MysqlConnection,
+    mode: SearchMode,
+    page: i64,
+    size: i64,
+    terms: &[String],
+    searchable: &[&str],
+    mut fetch: F,
+) -> Result<(Vec<(T, f64)>, i64), AppError>
+where
+    T: Serialize,
+    F: FnMut(&mut MysqlConnection, i64, i64) -> Result<(Vec<(T, f64)>, i64), AppError>,
+{
+    if mode != SearchMode::Fuzzy {
+        return fetch(conn, size, size * page);
+    }
+
+    let (candidates, _) = fetch(conn, FUZZY_CANDIDATES, 0)?;
+    let mut ranked = Vec::new();
+    for (item, _) in candidates {
+        let texts = texts(&item, searchable)?;
+        if let Some(score) = fuzzy_score(&texts, terms) {
+            ranked.push((item, score));
+        }
+    }
+    ranked.sort_by(|left, right| right.1.total_cmp(&left.1));
+    let total = ranked.len() as i64;
+    let content = ranked
+        .into_iter()
+        .skip((size * page) as usize)
+        .take(size as usize)
+        .collect();
+    Ok((content, total))
+}
+
+/// Result with the requested fields and the highlighted searched fields.
+pub fn hit<T: Serialize>(
+    item: &T,
+    score: f64,
+    fields: &[String],
+    searchable: &[&str],
+    terms: &[String],
+    mode: SearchMode,
+) -> Result<SearchHit, AppError> {
+    let mut highlight = BTreeMap::new();
+    for (column, text) in texts(item, searchable)? {
+        if let Some(value) = highlight_text(&text, terms, mode) {
+            highlight.insert(column, value);
+        }
+    }
+    Ok(SearchHit {
+        score,
+        highlight,
+        data: projection::to_value(item, fields)?,
+    })
+}
+
+/// Text with every matching word wrapped in `<em>`, HTML escaped, `None`
+/// when nothing matched.
+pub fn highlight_text(text: &str, terms: &[String], mode: SearchMode) -> Option<String> {
+    let mut highlighted = String::new();
+    let mut word = String::new();
+    let mut matched = false;
+    for character in text.chars().chain(std::iter::once('\0')) {
+        if character.is_alphanumeric() {
+            word.push(character);
+            continue;
+        }
+        if !word.is_empty() {
+            let lowercase = word.to_lowercase();
+            if terms.iter().any(|term| similarity(&lowercase, term, mode).is_some()) {
+                matched = true;
+                highlighted.push_str(&format!("<em>{}</em>", escape_html(&word)));
+            } else {
+                highlighted.push_str(&escape_html(&word));
+            }
+            word.clear();
+        }
+        if character != '\0' {
+            highlighted.push_str(&escape_html(&character.to_string()));
+        }
+    }
+    if matched {
+        return Some(highlighted);
+    }
+    None
+}
+
+/// Searched fields of an entity that hold text.
+fn texts<T: Serialize>(item: &T, searchable: &[&str]) -> Result<Vec<(String, String)>, AppError> {
+    let value = serde_json::to_value(item)
+        .map_err(|error| AppError::Other(format!("serialize failed: {}", error)))?;
+    Ok(searchable
+        .iter()
+        .filter_map(|column| match &value[*column] {
+            Value::String(text) => Some((column.to_string(), text.clone())),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Sum over the terms of their best word similarity, `None` when no term
+/// is close to any word.
+fn fuzzy_score(texts: &[(String, String)], terms: &[String]) -> Option<f64> {
+    let words: Vec<String> = texts
+        .iter()
+        .flat_map(|(_, text)| words(text))
+        .map(|word| word.to_lowercase())
+        .collect();
+    let score: f64 = terms
+        .iter()
+        .map(|term| {
+            words
+                .iter()
+                .filter_map(|word| similarity(word, term, SearchMode::Fuzzy))
+                .fold(0.0, f64::max)
+        })
+        .sum();
+    if score > 0.0 {
+        return Some(score);
+    }
+    None
+}
+
+/// How close a lowercased word is to a term, from 0 to 1, `None` when the
+/// mode does not count it as a match.
+fn similarity(word: &str, term: &str, mode: SearchMode) -> Option<f64> {
+    let word_length = word.chars().count();
+    let term_length = term.chars().count();
+    let longest = word_length.max(term_length) as f64;
+    match mode {
+        SearchMode::Natural => (word == term).then_some(1.0),
+        SearchMode::Prefix => word.starts_with(term).then(|| term_length as f64 / longest),
+        SearchMode::Fuzzy => {
+            let mut distance = levenshtein(word, term);
+            if word.starts_with(term) {
+                distance = distance.min(word_length - term_length);
+            }
+            let allowed = if term_length <= 4 { 1 } else { 2 };
+            if distance > allowed && !word.starts_with(term) {
+                return None;
+            }
+            Some(1.0 - distance as f64 / longest)
+        }
+    }
+}
+
+fn levenshtein(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left_char != *right_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|character: char| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_string())
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -143,3 +143,47 @@ pub mod filters_serializer {
         };
     }
 }
+
+
+/// Query parameter holding a JSON document, e.g. `_group_by=[{"id":"created_on"}]`.
+pub mod json_string_serializer {
+    use serde::{de::DeserializeOwned, de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, T: Serialize>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => {
+                let json_string = serde_json::to_string(value).map_err(serde::ser::Error::custom)?;
+                serializer.serialize_str(&json_string)
+            }
+            None => serializer.serialize_str("[]"),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: DeserializeOwned>(
+        deserializer: D,
+    ) -> Result<Option<T>, D::Error> {
+        let json_string: String = Deserialize::deserialize(deserializer)?;
+        serde_json::from_str(&json_string)
+            .map(Some)
+            .map_err(|error| D::Error::custom(format!("{}", error)))
+    }
+}
+
+/// A column holding a JSON document, written as nested JSON rather than a string.
+pub mod json_text_serializer {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::Value;
+
+    pub fn serialize<S: Serializer>(text: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        let value: Value = serde_json::from_str(text).map_err(serde::ser::Error::custom)?;
+        value.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        let value: Value = Deserialize::deserialize(deserializer)?;
+        serde_json::to_string(&value).map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,59 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{config::environment::CONFIG, dto::response::app_error::AppError};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Fails startup when links could be forged with an empty secret.
+pub fn initialize() -> Result<(), AppError> {
+    if CONFIG.storage_url_secret.is_empty() {
+        return Err(AppError::Other(format!("STORAGE_URL_SECRET is not set")));
+    }
+    Ok(())
+}
+
+fn new_mac(resource: &str, expires: i64) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(CONFIG.storage_url_secret.as_bytes())
+        .expect("hmac accepts keys of any size");
+    mac.update(format!("{}:{}", resource, expires).as_bytes());
+    mac
+}
+
+/// Hex encoded HMAC-SHA256 over `resource:expires`, where `resource` names
+/// what the link grants access to (e.g. `m_file/10`).
+pub fn sign(resource: &str, expires: i64) -> String {
+    new_mac(resource, expires)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Constant-time check of a signature produced by `sign`.
+pub fn verify(resource: &str, expires: i64, signature: &str) -> bool {
+    if !signature.len().is_multiple_of(2) || !signature.is_ascii() {
+        return false;
+    }
+    let signature_bytes: Option<Vec<u8>> = (0..signature.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&signature[index..index + 2], 16).ok())
+        .collect();
+    match signature_bytes {
+        Some(value) => new_mac(resource, expires).verify_slice(&value).is_ok(),
+        None => false,
+    }
+}
+
+/// Refuse a request whose `signature` does not grant `resource` or whose
+/// `expires` has passed.
+pub fn ensure_valid(resource: &str, expires: i64, signature: &str) -> Result<(), AppError> {
+    if !verify(resource, expires, signature) {
+        return Err(AppError::Forbidden("invalid signature".to_string()));
+    }
+    if expires < chrono::Utc::now().timestamp() {
+        return Err(AppError::Forbidden("link expired".to_string()));
+    }
+    Ok(())
+}
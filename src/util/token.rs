@@ -0,0 +1,67 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{config::environment::CONFIG, dto::response::app_error::AppError};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The only header tokens are issued with.
+const HEADER: &str = r#"{"alg":"HS256","typ":"JWT"}"#;
+
+/// Claims of an access token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// `m_user.id`
+    pub sub: i64,
+    /// `m_role.code` of the user
+    pub role: Option<String>,
+    /// unix seconds
+    pub exp: i64,
+}
+
+/// Fails startup when tokens could be forged with an empty key.
+pub fn initialize() -> Result<(), AppError> {
+    if CONFIG.jwt_key.is_empty() {
+        return Err(AppError::Other(format!("JWT_KEY is not set")));
+    }
+    Ok(())
+}
+
+fn new_mac(signing_input: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(CONFIG.jwt_key.as_bytes())
+        .expect("hmac accepts keys of any size");
+    mac.update(signing_input.as_bytes());
+    mac
+}
+
+/// HS256 JWT of `claims`, signed with `JWT_KEY`.
+pub fn issue(claims: &Claims) -> Result<String, AppError> {
+    let payload = serde_json::to_vec(claims)
+        .map_err(|error| AppError::Other(format!("issue token failed: {}", error)))?;
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(HEADER),
+        URL_SAFE_NO_PAD.encode(payload)
+    );
+    let signature = URL_SAFE_NO_PAD.encode(new_mac(&signing_input).finalize().into_bytes());
+    Ok(format!("{}.{}", signing_input, signature))
+}
+
+/// Claims of a token issued by `issue` that has not expired yet.
+pub fn verify(token: &str) -> Option<Claims> {
+    let (signing_input, signature) = token.rsplit_once('.')?;
+    let (header, payload) = signing_input.split_once('.')?;
+    if URL_SAFE_NO_PAD.decode(header).ok()? != HEADER.as_bytes() {
+        return None;
+    }
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    new_mac(signing_input).verify_slice(&signature).ok()?;
+
+    let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    if claims.exp <= chrono::Utc::now().timestamp() {
+        return None;
+    }
+    Some(claims)
+}
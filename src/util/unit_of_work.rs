@@ -0,0 +1,47 @@
+use diesel::{
+    connection::{AnsiTransactionManager, TransactionManager},
+    Connection, MysqlConnection,
+};
+
+use crate::dto::response::app_error::AppError;
+
+/// Run a check-and-write sequence as one transaction: committed when `work`
+/// returns `Ok`, rolled back on any `AppError`. Repository writes inside it
+/// that open their own transaction become savepoints of this one.
+pub fn run<T, F>(conn: &mut MysqlConnection, work: F) -> Result<T, AppError>
+where
+    F: FnOnce(&mut MysqlConnection) -> Result<T, AppError>,
+{
+    let result = conn.transaction(work);
+    if let Err(error) = &result {
+        log::info!("unit of work rolled back: {:?}", error);
+    }
+    result
+}
+
+/// Open a transaction for work that has to await between its statements
+/// (e.g. while streaming a request body), which `run` cannot hold. Always
+/// close it with `finish`.
+pub fn begin(conn: &mut MysqlConnection) -> Result<(), AppError> {
+    AnsiTransactionManager::begin_transaction(conn)
+        .map_err(|error| AppError::Other(format!("begin transaction failed: {}", error)))
+}
+
+/// Commit the transaction opened by `begin` when `result` is `Ok`, roll it
+/// back otherwise.
+pub fn finish<T>(conn: &mut MysqlConnection, result: Result<T, AppError>) -> Result<T, AppError> {
+    match result {
+        Ok(value) => {
+            AnsiTransactionManager::commit_transaction(conn)
+                .map_err(|error| AppError::Other(format!("commit transaction failed: {}", error)))?;
+            Ok(value)
+        }
+        Err(error) => {
+            log::info!("unit of work rolled back: {:?}", error);
+            if let Err(rollback_error) = AnsiTransactionManager::rollback_transaction(conn) {
+                log::error!("rollback transaction failed: {}", rollback_error);
+            }
+            Err(error)
+        }
+    }
+}